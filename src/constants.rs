@@ -1 +1,50 @@
+use near_sdk::Gas;
+
+// version of the contract interface, bumped together with the crate version on the breaking changes
+pub const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const CONTRACT_SOURCE_LINK: &str = "https://github.com/yra1029/near_payment_receiver";
+
+pub const NANOS_IN_WEEK: u64 = 604800000000000;
 pub const NANOS_IN_DAY: u64 = 86400000000000;
+pub const NANOS_IN_HOUR: u64 = 3600000000000;
+pub const NANOS_IN_MINUTE: u64 = 60000000000;
+pub const NANOS_IN_SECOND: u64 = 1000000000;
+
+pub const MIN_PERIOD_DURATION_SECS: u64 = 60;
+pub const MAX_MEMO_LENGTH: usize = 256;
+pub const MAX_METADATA_LENGTH: usize = 1024;
+pub const MAX_CLIENT_NONCE_LENGTH: usize = 64;
+pub const DEFAULT_PAGINATION_LIMIT: u64 = 50;
+pub const MAX_PAGINATION_LIMIT: u64 = 100;
+pub const MAX_BATCH_SIZE: usize = 50;
+// bumped on every change of the exported account state layout
+pub const EXPORT_STATE_VERSION: u32 = 11;
+// the claim history of the payment is compacted when it reaches this length
+pub const MAX_CLAIM_HISTORY_LENGTH: u32 = 100;
+
+pub const DEFAULT_MAX_PAYMENTS_PER_ACCOUNT: u32 = 1000;
+pub const DEFAULT_MIN_PAYMENT_AMOUNT: u128 = 1;
+// the minimal period duration in nanoseconds, could be lowered by the owner for the testnets
+pub const DEFAULT_MIN_PERIOD_DURATION: u64 = MIN_PERIOD_DURATION_SECS * NANOS_IN_SECOND;
+// the longest period of the payment, about 100 years, so that the conversion to nanoseconds can't overflow
+pub const MAX_PERIOD_DAYS: u64 = 36_500;
+// days after the end date when the owner could settle the stream abandoned by both parties
+pub const DEFAULT_ABANDONMENT_GRACE_DAYS: u64 = 30;
+// days until the settlement offer which is not answered by the receiver expires
+pub const DEFAULT_SETTLEMENT_OFFER_DAYS: u64 = 7;
+// days between the rejection of the started stream by the issuer and its settlement, it is settled right away by default
+pub const DEFAULT_ISSUER_CANCELLATION_NOTICE_DAYS: u64 = 0;
+
+// gas attached to the callback which checks the result of the outgoing transfer
+pub const GAS_FOR_TRANSFER_CALLBACK: Gas = Gas(10_000_000_000_000);
+// gas required to schedule the outgoing transfer with its callback, the fees of both receipts
+// and of the data dependency between them are burnt by the call, checked before the state is changed
+pub const GAS_FOR_TRANSFER: Gas = Gas(15_000_000_000_000);
+
+// gas kept for the rest of the call when the payments are processed in bulk
+pub const BULK_OPERATION_GAS_FLOOR: Gas = Gas(20_000_000_000_000);
+
+// hard cap of the claim fee, 10%
+pub const MAX_FEE_BPS: u16 = 1000;
+// weights of the split stream receivers should sum up to this value
+pub const TOTAL_WEIGHT_BPS: u16 = 10000;
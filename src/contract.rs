@@ -1,14 +1,31 @@
+pub mod admin;
+pub mod amend_payment;
+pub mod cancellation;
+pub mod claim_delegate;
 pub mod claim_payment;
 pub mod create_payment;
+pub mod expire_payment;
 mod general_impl;
+pub mod payout_address;
 pub mod process_pending_payment;
+pub mod receiver_prefs;
 pub mod reject_payment;
+pub mod settlement_offer;
+pub mod view;
 
-use crate::error::{require, ContractError};
+use crate::constants::{
+    DEFAULT_ABANDONMENT_GRACE_DAYS, DEFAULT_ISSUER_CANCELLATION_NOTICE_DAYS,
+    DEFAULT_MAX_PAYMENTS_PER_ACCOUNT, DEFAULT_MIN_PAYMENT_AMOUNT, DEFAULT_MIN_PERIOD_DURATION,
+    DEFAULT_SETTLEMENT_OFFER_DAYS,
+};
+use crate::error::{ensure, ContractError};
+use crate::public::claim_history::ClaimRecord;
+use crate::public::completed_payment::CompletedPaymentRecord;
 use crate::public::payment_receipt::PaymentReceipt;
-use crate::public::StorageKey;
+use crate::public::receiver_prefs::ReceiverPrefs;
+use crate::public::{ContractSourceMetadata, StorageKey};
 use crate::Result;
-use near_sdk::store::UnorderedSet;
+use near_sdk::store::{LookupMap, UnorderedSet, Vector};
 use near_sdk::{assert_one_yocto, env};
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
@@ -24,6 +41,46 @@ pub struct PaymentContract {
     receiver_ledger: UnorderedMap<AccountId, UnorderedSet<u64>>,
     payment_info_ledger: UnorderedMap<u64, PaymentReceipt>,
     payment_id_counter: u64,
+    max_payments_per_account: u32,
+    min_payment_amount: u128,
+    completed_payment_ledger: UnorderedMap<u64, CompletedPaymentRecord>,
+    fee_bps: u16,
+    fee_recipient: AccountId,
+    collected_fees: u128,
+    allowed_receivers: UnorderedSet<AccountId>,
+    allowlist_enabled: bool,
+    claim_history: LookupMap<u64, Vector<ClaimRecord>>,
+    owner_id: AccountId,
+    paused: bool,
+    failed_transfers: UnorderedMap<AccountId, u128>,
+    // issuer and the client nonce of the active payment to its id
+    client_nonces: LookupMap<(AccountId, String), u64>,
+    receiver_prefs: LookupMap<AccountId, ReceiverPrefs>,
+    // deposits of the active payments which are not paid out yet
+    total_locked_near: u128,
+    abandonment_grace_days: u64,
+    // the minimal period duration of the new payments in nanoseconds
+    min_period_duration: u64,
+    // stricter limit for the issuers on top of max_payments_per_account, not applied when None
+    max_active_payments_per_issuer: Option<u32>,
+    // index of the next receipt checked by migrate_storage, None until the migration is started
+    migration_cursor: Option<u64>,
+    // index of the next receipt and the sum of the previous ones, kept by recalculate_total_locked between the pages
+    locked_recalculation: Option<(u64, u128)>,
+    source_metadata: ContractSourceMetadata,
+    // streaming to itself is a legitimate vesting of the own funds, so it is allowed by default
+    self_payments_allowed: bool,
+    settlement_offer_days: u64,
+    issuer_cancellation_notice_days: u64,
+}
+
+// layout of the contract state deployed before the settings were added, read once by migrate
+#[derive(BorshDeserialize, BorshSerialize)]
+struct OldState {
+    issuer_ledger: UnorderedMap<AccountId, UnorderedSet<u64>>,
+    receiver_ledger: UnorderedMap<AccountId, UnorderedSet<u64>>,
+    payment_info_ledger: UnorderedMap<u64, PaymentReceipt>,
+    payment_id_counter: u64,
 }
 
 #[near_bindgen]
@@ -33,16 +90,63 @@ impl PaymentContract {
     #[handle_result]
     pub fn new() -> Result<Self> {
         assert_one_yocto(); // Required to check that initializer has a full access key
-        require(
+        ensure!(
             env::predecessor_account_id() == env::current_account_id(),
             ContractError::InitializeError,
-        )?;
+        );
 
-        Ok(PaymentContract {
+        Ok(PaymentContract::from_old_state(OldState {
             issuer_ledger: UnorderedMap::new(StorageKey::IssuerLedger),
             receiver_ledger: UnorderedMap::new(StorageKey::ReceiverLedger),
             payment_info_ledger: UnorderedMap::new(StorageKey::PaymentReceiptLedger),
             payment_id_counter: 1,
-        })
+        }))
+    }
+
+    // called together with the deployment of the new code over the OldState layout, the ledgers are kept
+    // and the settings get the defaults. the owner restores the locked total by recalculate_total_locked
+    // and rewrites the legacy receipts by migrate_storage
+    #[private]
+    #[init(ignore_state)]
+    #[handle_result]
+    pub fn migrate() -> Result<Self> {
+        let old_state: OldState = env::state_read().ok_or(ContractError::StateNotInitialized)?;
+
+        Ok(PaymentContract::from_old_state(old_state))
+    }
+}
+
+impl PaymentContract {
+    fn from_old_state(old_state: OldState) -> Self {
+        PaymentContract {
+            issuer_ledger: old_state.issuer_ledger,
+            receiver_ledger: old_state.receiver_ledger,
+            payment_info_ledger: old_state.payment_info_ledger,
+            payment_id_counter: old_state.payment_id_counter,
+            max_payments_per_account: DEFAULT_MAX_PAYMENTS_PER_ACCOUNT,
+            min_payment_amount: DEFAULT_MIN_PAYMENT_AMOUNT,
+            completed_payment_ledger: UnorderedMap::new(StorageKey::CompletedPaymentLedger),
+            fee_bps: 0,
+            fee_recipient: env::current_account_id(),
+            collected_fees: 0,
+            allowed_receivers: UnorderedSet::new(StorageKey::AllowedReceivers),
+            allowlist_enabled: false,
+            claim_history: LookupMap::new(StorageKey::ClaimHistoryLedger),
+            owner_id: env::current_account_id(),
+            paused: false,
+            failed_transfers: UnorderedMap::new(StorageKey::FailedTransfers),
+            client_nonces: LookupMap::new(StorageKey::ClientNonces),
+            receiver_prefs: LookupMap::new(StorageKey::ReceiverPrefs),
+            total_locked_near: 0,
+            abandonment_grace_days: DEFAULT_ABANDONMENT_GRACE_DAYS,
+            min_period_duration: DEFAULT_MIN_PERIOD_DURATION,
+            max_active_payments_per_issuer: None,
+            migration_cursor: None,
+            locked_recalculation: None,
+            source_metadata: ContractSourceMetadata::default(),
+            self_payments_allowed: true,
+            settlement_offer_days: DEFAULT_SETTLEMENT_OFFER_DAYS,
+            issuer_cancellation_notice_days: DEFAULT_ISSUER_CANCELLATION_NOTICE_DAYS,
+        }
     }
 }
@@ -0,0 +1,1075 @@
+use super::PaymentContract;
+use crate::constants::{GAS_FOR_TRANSFER, GAS_FOR_TRANSFER_CALLBACK, MAX_FEE_BPS};
+use crate::contract::PaymentContractExt;
+use crate::error::{ensure, ContractError};
+use crate::events::{ContractEvent, MinimumsUpdated, OwnerChanged};
+use crate::public::{ContractSourceMetadata, LockedRecalculationResult, MigrationResult};
+use crate::Result;
+use near_sdk::{
+    assert_one_yocto, env,
+    json_types::{U128, U64},
+    near_bindgen, AccountId, Gas,
+};
+
+#[near_bindgen]
+impl PaymentContract {
+    #[payable]
+    #[handle_result]
+    pub fn set_limits(&mut self, max_payments_per_account: u32) -> Result<()> {
+        assert_one_yocto();
+        self.assert_owner()?;
+
+        self.max_payments_per_account = max_payments_per_account;
+
+        Ok(())
+    }
+
+    // min_period_duration is the floor of the period duration in nanoseconds
+    #[payable]
+    #[handle_result]
+    pub fn set_minimums(
+        &mut self,
+        min_payment_amount: U128,
+        min_period_duration: U64,
+    ) -> Result<()> {
+        assert_one_yocto();
+        self.assert_owner()?;
+
+        self.min_payment_amount = min_payment_amount.0;
+        self.min_period_duration = min_period_duration.0;
+
+        ContractEvent::MinimumsUpdated(vec![MinimumsUpdated {
+            min_payment_amount,
+            min_period_duration,
+        }])
+        .emit();
+
+        Ok(())
+    }
+
+    // None removes the issuer specific limit
+    #[payable]
+    #[handle_result]
+    pub fn set_max_active_payments_per_issuer(&mut self, limit: Option<u32>) -> Result<()> {
+        assert_one_yocto();
+        self.assert_owner()?;
+
+        self.max_active_payments_per_issuer = limit;
+
+        Ok(())
+    }
+
+    #[payable]
+    #[handle_result]
+    pub fn set_fee_config(&mut self, fee_bps: u16, fee_recipient: AccountId) -> Result<()> {
+        assert_one_yocto();
+        self.assert_owner()?;
+
+        ensure!(
+            fee_bps <= MAX_FEE_BPS,
+            ContractError::FeeTooHigh(fee_bps, MAX_FEE_BPS),
+        );
+
+        self.fee_bps = fee_bps;
+        self.fee_recipient = fee_recipient;
+
+        Ok(())
+    }
+
+    #[payable]
+    #[handle_result]
+    pub fn add_allowed_receiver(&mut self, account_id: AccountId) -> Result<()> {
+        assert_one_yocto();
+        self.assert_owner()?;
+
+        self.allowed_receivers.insert(account_id);
+
+        Ok(())
+    }
+
+    #[payable]
+    #[handle_result]
+    pub fn remove_allowed_receiver(&mut self, account_id: AccountId) -> Result<()> {
+        assert_one_yocto();
+        self.assert_owner()?;
+
+        self.allowed_receivers.remove(&account_id);
+
+        Ok(())
+    }
+
+    // the list is kept when the allowlist is disabled, so it could be enabled back later
+    #[payable]
+    #[handle_result]
+    pub fn set_allowlist_enabled(&mut self, enabled: bool) -> Result<()> {
+        assert_one_yocto();
+        self.assert_owner()?;
+
+        self.allowlist_enabled = enabled;
+
+        Ok(())
+    }
+
+    #[payable]
+    #[handle_result]
+    pub fn set_self_payments_allowed(&mut self, allowed: bool) -> Result<()> {
+        assert_one_yocto();
+        self.assert_owner()?;
+
+        self.self_payments_allowed = allowed;
+
+        Ok(())
+    }
+
+    #[payable]
+    #[handle_result]
+    pub fn set_abandonment_grace_days(&mut self, abandonment_grace_days: U64) -> Result<()> {
+        assert_one_yocto();
+        self.assert_owner()?;
+
+        self.abandonment_grace_days = abandonment_grace_days.0;
+
+        Ok(())
+    }
+
+    #[payable]
+    #[handle_result]
+    pub fn set_settlement_offer_days(&mut self, settlement_offer_days: U64) -> Result<()> {
+        assert_one_yocto();
+        self.assert_owner()?;
+
+        self.settlement_offer_days = settlement_offer_days.0;
+
+        Ok(())
+    }
+
+    // applied to the rejections made after the change, 0 makes the issuer rejection immediate
+    #[payable]
+    #[handle_result]
+    pub fn set_issuer_cancellation_notice_days(&mut self, notice_days: U64) -> Result<()> {
+        assert_one_yocto();
+        self.assert_owner()?;
+
+        self.issuer_cancellation_notice_days = notice_days.0;
+
+        Ok(())
+    }
+
+    // the account id is validated during the deserialization, so an empty or invalid owner could not be set
+    #[payable]
+    #[handle_result]
+    pub fn set_owner(&mut self, new_owner: AccountId) -> Result<()> {
+        assert_one_yocto();
+        self.assert_owner()?;
+
+        let old_owner = std::mem::replace(&mut self.owner_id, new_owner.clone());
+
+        ContractEvent::OwnerChanged(vec![OwnerChanged {
+            old_owner,
+            new_owner,
+        }])
+        .emit();
+
+        Ok(())
+    }
+
+    // stops the creation, approval and claiming of the payments for the incident response
+    #[payable]
+    #[handle_result]
+    pub fn set_paused(&mut self, paused: bool) -> Result<()> {
+        assert_one_yocto();
+        self.assert_owner()?;
+
+        self.paused = paused;
+
+        Ok(())
+    }
+
+    // one-time migration of the running total for the payments created before it was introduced, should be repeated
+    // until nothing remains. the sum is applied by the last page, the contract should be paused meanwhile,
+    // so that the payments are not changed between the pages
+    #[payable]
+    #[handle_result]
+    pub fn recalculate_total_locked(
+        &mut self,
+        batch_size: U64,
+    ) -> Result<LockedRecalculationResult> {
+        assert_one_yocto();
+        self.assert_owner()?;
+
+        let len = u64::from(self.payment_info_ledger.len());
+        let (cursor, staged_total) = self.locked_recalculation.unwrap_or((0, 0));
+        let cursor = cursor.min(len);
+        let batch_size = batch_size.0.min(len - cursor);
+
+        let staged_total = self
+            .sum_obligations(cursor as usize, batch_size as usize)?
+            .saturating_add(staged_total);
+
+        let cursor = cursor + batch_size;
+        let remaining = len - cursor;
+
+        if remaining == 0 {
+            self.total_locked_near = staged_total;
+            self.locked_recalculation = None;
+        } else {
+            self.locked_recalculation = Some((cursor, staged_total));
+        }
+
+        Ok(LockedRecalculationResult {
+            total_locked: staged_total.into(),
+            remaining: remaining.into(),
+        })
+    }
+
+    #[payable]
+    #[handle_result]
+    pub fn set_source_metadata(&mut self, source_metadata: ContractSourceMetadata) -> Result<()> {
+        assert_one_yocto();
+        self.assert_owner()?;
+
+        self.source_metadata = source_metadata;
+
+        Ok(())
+    }
+
+    // rewrites the legacy receipts with the current layout, should be repeated until nothing remains.
+    // the legacy receipts are still converted on read, so the ones moved behind the cursor by the removals are not lost
+    #[payable]
+    #[handle_result]
+    pub fn migrate_storage(&mut self, batch_size: U64) -> Result<MigrationResult> {
+        assert_one_yocto();
+        self.assert_owner()?;
+
+        let len = u64::from(self.payment_info_ledger.len());
+        let cursor = self.migration_cursor.unwrap_or(0).min(len);
+        let batch_size = batch_size.0.min(len - cursor);
+
+        let mut migrated: u64 = 0;
+        for (_, receipt) in self
+            .payment_info_ledger
+            .iter_mut()
+            .skip(cursor as usize)
+            .take(batch_size as usize)
+        {
+            if !receipt.is_current() {
+                receipt.into_current_mut();
+                migrated += 1;
+            }
+        }
+
+        let cursor = cursor + batch_size;
+        self.migration_cursor = Some(cursor);
+
+        Ok(MigrationResult {
+            migrated: migrated.into(),
+            remaining: (len - cursor).into(),
+        })
+    }
+
+    // the fees which could not be received are kept for the withdrawal of the failed transfers
+    #[payable]
+    #[handle_result]
+    pub fn withdraw_fees(&mut self) -> Result<U128> {
+        assert_one_yocto();
+        let caller = env::predecessor_account_id();
+
+        ensure!(
+            caller == self.fee_recipient,
+            ContractError::NotFeeRecipient(caller.clone()),
+        );
+        self.check_prepaid_gas(Gas(GAS_FOR_TRANSFER.0 + GAS_FOR_TRANSFER_CALLBACK.0))?;
+
+        let amount = std::mem::take(&mut self.collected_fees);
+
+        self.transfer_with_fallback(caller, amount);
+
+        Ok(amount.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constants::{
+            DEFAULT_ISSUER_CANCELLATION_NOTICE_DAYS, DEFAULT_MAX_PAYMENTS_PER_ACCOUNT,
+            DEFAULT_MIN_PAYMENT_AMOUNT, DEFAULT_MIN_PERIOD_DURATION, DEFAULT_SETTLEMENT_OFFER_DAYS,
+            MAX_PAGINATION_LIMIT, NANOS_IN_DAY,
+        },
+        contract::{
+            general_impl::{
+                insert_ledger_payment_id,
+                tests::{
+                    contract_acc, create_payment, get_context, get_context_with_storage_usage,
+                    issuer_acc, receiver_acc, set_block_timestamp,
+                },
+            },
+            OldState,
+        },
+        public::{
+            payment_info::PaymentInfoV1,
+            payment_receipt::{PaymentReceipt, PaymentReceiptV1},
+            ContractLimits, FeeConfig, ProcessStatus, StorageKey,
+        },
+    };
+
+    use super::*;
+    use near_sdk::{
+        store::UnorderedMap,
+        test_utils::{accounts, get_logs},
+        testing_env, PromiseError,
+    };
+
+    fn get_contract_with_limit(max_payments_per_account: u32) -> PaymentContract {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        contract.set_limits(max_payments_per_account).unwrap();
+
+        contract
+    }
+
+    #[test]
+    fn test_set_limits() {
+        let contract = get_contract_with_limit(5);
+
+        assert_eq!(
+            contract.get_limits(),
+            ContractLimits {
+                max_payments_per_account: 5,
+                min_payment_amount: U128(DEFAULT_MIN_PAYMENT_AMOUNT),
+                min_period_duration: U64(DEFAULT_MIN_PERIOD_DURATION),
+            }
+        );
+    }
+
+    #[test]
+    fn test_set_limits_not_owner() {
+        let mut contract = get_contract_with_limit(5);
+
+        testing_env!(get_context(issuer_acc(), 1));
+        assert_eq!(
+            contract.set_limits(10),
+            Err(ContractError::NotContractOwner(issuer_acc()))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_set_limits_without_yocto() {
+        let mut contract = get_contract_with_limit(5);
+
+        testing_env!(get_context(contract_acc(), 0));
+        contract.set_limits(10).unwrap();
+    }
+
+    #[test]
+    fn test_issuer_payments_limit() {
+        let mut contract = get_contract_with_limit(2);
+
+        // issuer creates payments for different receivers up to the limit
+        testing_env!(get_context(issuer_acc(), 10));
+        for receiver in [accounts(2), accounts(3)] {
+            contract
+                .create_payment(U64(1), U128(1), receiver, None)
+                .unwrap();
+        }
+
+        assert_eq!(
+            contract.create_payment(U64(1), U128(1), accounts(4), None),
+            Err(ContractError::TooManyActivePayments(issuer_acc(), 2, 2))
+        );
+    }
+
+    #[test]
+    fn test_max_active_payments_per_issuer() {
+        let mut contract = get_contract_with_limit(5);
+        assert_eq!(contract.get_max_active_payments_per_issuer(), None);
+
+        contract
+            .set_max_active_payments_per_issuer(Some(1))
+            .unwrap();
+        assert_eq!(contract.get_max_active_payments_per_issuer(), Some(1));
+
+        testing_env!(get_context(issuer_acc(), 10));
+        contract
+            .create_payment(U64(1), U128(1), accounts(2), None)
+            .unwrap();
+
+        assert_eq!(
+            contract.create_payment(U64(1), U128(1), accounts(3), None),
+            Err(ContractError::IssuerPaymentLimitReached(issuer_acc(), 1))
+        );
+
+        // the other issuers are limited separately
+        testing_env!(get_context(accounts(3), 10));
+        contract
+            .create_payment(U64(1), U128(1), accounts(2), None)
+            .unwrap();
+
+        // the limit is removed, only max_payments_per_account is applied
+        testing_env!(get_context(contract_acc(), 1));
+        contract.set_max_active_payments_per_issuer(None).unwrap();
+
+        testing_env!(get_context(issuer_acc(), 10));
+        contract
+            .create_payment(U64(1), U128(1), accounts(3), None)
+            .unwrap();
+
+        testing_env!(get_context(issuer_acc(), 1));
+        assert_eq!(
+            contract.set_max_active_payments_per_issuer(Some(1)),
+            Err(ContractError::NotContractOwner(issuer_acc()))
+        );
+    }
+
+    #[test]
+    fn test_receiver_payments_limit() {
+        let mut contract = get_contract_with_limit(2);
+
+        // different issuers create payments for the same receiver up to the limit
+        for issuer in [accounts(3), accounts(4)] {
+            testing_env!(get_context(issuer, 10));
+            contract
+                .create_payment(U64(1), U128(1), receiver_acc(), None)
+                .unwrap();
+        }
+
+        testing_env!(get_context(accounts(5), 10));
+        assert_eq!(
+            contract.create_payment(U64(1), U128(1), receiver_acc(), None),
+            Err(ContractError::TooManyActivePayments(receiver_acc(), 2, 2))
+        );
+    }
+
+    #[test]
+    fn test_set_minimums() {
+        let mut contract = get_contract_with_limit(5);
+
+        contract
+            .set_minimums(U128(10), U64(2 * NANOS_IN_DAY))
+            .unwrap();
+
+        assert_eq!(
+            contract.get_limits(),
+            ContractLimits {
+                max_payments_per_account: 5,
+                min_payment_amount: U128(10),
+                min_period_duration: U64(2 * NANOS_IN_DAY),
+            }
+        );
+
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"near_payment_receiver","version":"1.0.0","event":"minimums_updated","data":[{"min_payment_amount":"10","min_period_duration":"172800000000000"}]}"#
+            ]
+        );
+    }
+
+    #[test]
+    fn test_set_minimums_not_owner() {
+        let mut contract = get_contract_with_limit(5);
+
+        testing_env!(get_context(issuer_acc(), 1));
+        assert_eq!(
+            contract.set_minimums(U128(10), U64(2)),
+            Err(ContractError::NotContractOwner(issuer_acc()))
+        );
+    }
+
+    #[test]
+    fn test_minimums_enforced_on_create_payment() {
+        let mut contract = get_contract_with_limit(5);
+        contract
+            .set_minimums(U128(10), U64(2 * NANOS_IN_DAY))
+            .unwrap();
+
+        testing_env!(get_context(issuer_acc(), 100));
+
+        // values exactly at the minimums are allowed
+        assert!(contract
+            .create_payment(U64(2), U128(10), receiver_acc(), None)
+            .is_ok());
+
+        assert_eq!(
+            contract.create_payment(U64(2), U128(9), receiver_acc(), None),
+            Err(ContractError::PaymentAmountBelowMinimum(9, 10))
+        );
+
+        assert_eq!(
+            contract.create_payment(U64(1), U128(10), receiver_acc(), None),
+            Err(ContractError::PeriodDurationTooShort(86400, 172800))
+        );
+    }
+
+    #[test]
+    fn test_set_fee_config() {
+        let mut contract = get_contract_with_limit(5);
+
+        contract.set_fee_config(50, accounts(3)).unwrap();
+        assert_eq!(
+            contract.get_fee_config(),
+            FeeConfig {
+                fee_bps: 50,
+                fee_recipient: accounts(3),
+            }
+        );
+
+        assert_eq!(
+            contract.set_fee_config(MAX_FEE_BPS + 1, accounts(3)),
+            Err(ContractError::FeeTooHigh(MAX_FEE_BPS + 1, MAX_FEE_BPS))
+        );
+        assert_eq!(contract.set_fee_config(MAX_FEE_BPS, accounts(3)), Ok(()));
+        assert_eq!(contract.get_fee_config().fee_bps, 1000);
+
+        testing_env!(get_context(issuer_acc(), 1));
+        assert_eq!(
+            contract.set_fee_config(0, issuer_acc()),
+            Err(ContractError::NotContractOwner(issuer_acc()))
+        );
+    }
+
+    #[test]
+    fn test_withdraw_fees() {
+        let mut contract = get_contract_with_limit(5);
+        contract.set_fee_config(50, accounts(3)).unwrap();
+        contract.collected_fees = 100;
+
+        testing_env!(get_context(issuer_acc(), 1));
+        assert_eq!(
+            contract.withdraw_fees(),
+            Err(ContractError::NotFeeRecipient(issuer_acc()))
+        );
+
+        testing_env!(get_context(accounts(3), 1));
+        assert_eq!(contract.withdraw_fees(), Ok(U128(100)));
+        assert_eq!(contract.get_collected_fees(), U128(0));
+
+        // the fees are not lost when the recipient could not receive them
+        contract.on_transfer(accounts(3), U128(100), Err(PromiseError::Failed));
+        assert_eq!(contract.get_failed_transfer_balance(accounts(3)), U128(100));
+
+        // nothing is left to withdraw
+        assert_eq!(contract.withdraw_fees(), Ok(U128(0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_withdraw_fees_without_yocto() {
+        let mut contract = get_contract_with_limit(5);
+        contract.set_fee_config(50, accounts(3)).unwrap();
+        contract.collected_fees = 100;
+
+        testing_env!(get_context(accounts(3), 0));
+        contract.withdraw_fees().unwrap();
+    }
+
+    #[test]
+    fn test_receivers_allowlist() {
+        let mut contract = get_contract_with_limit(5);
+
+        contract.add_allowed_receiver(accounts(2)).unwrap();
+        contract.add_allowed_receiver(accounts(3)).unwrap();
+        contract.remove_allowed_receiver(accounts(3)).unwrap();
+
+        // the allowlist is not applied until it is enabled
+        assert!(contract.is_receiver_allowed(accounts(4)));
+
+        contract.set_allowlist_enabled(true).unwrap();
+        assert!(contract.is_receiver_allowed(accounts(2)));
+        assert!(!contract.is_receiver_allowed(accounts(3)));
+        assert_eq!(
+            contract.get_allowed_receivers(None, None),
+            vec![accounts(2)]
+        );
+
+        testing_env!(get_context(issuer_acc(), 10));
+        assert!(contract
+            .create_payment(U64(1), U128(1), accounts(2), None)
+            .is_ok());
+        assert_eq!(
+            contract.create_payment(U64(1), U128(1), accounts(3), None),
+            Err(ContractError::ReceiverNotAllowed(accounts(3)))
+        );
+
+        // disabling the allowlist restores the open behavior without clearing the list
+        testing_env!(get_context(contract_acc(), 1));
+        contract.set_allowlist_enabled(false).unwrap();
+        assert_eq!(
+            contract.get_allowed_receivers(None, None),
+            vec![accounts(2)]
+        );
+
+        testing_env!(get_context(issuer_acc(), 10));
+        assert!(contract
+            .create_payment(U64(1), U128(1), accounts(3), None)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_get_allowed_receivers_limit_is_capped() {
+        let mut contract = get_contract_with_limit(5);
+
+        for index in 0..=MAX_PAGINATION_LIMIT {
+            contract
+                .add_allowed_receiver(format!("receiver{}.near", index).parse().unwrap())
+                .unwrap();
+        }
+
+        assert_eq!(
+            contract
+                .get_allowed_receivers(None, Some(U64(u64::MAX)))
+                .len() as u64,
+            MAX_PAGINATION_LIMIT
+        );
+        assert_eq!(
+            contract
+                .get_allowed_receivers(Some(U64(MAX_PAGINATION_LIMIT)), None)
+                .len(),
+            1
+        );
+    }
+    #[test]
+    fn test_receivers_allowlist_not_owner() {
+        let mut contract = get_contract_with_limit(5);
+
+        testing_env!(get_context(issuer_acc(), 1));
+        assert_eq!(
+            contract.add_allowed_receiver(issuer_acc()),
+            Err(ContractError::NotContractOwner(issuer_acc()))
+        );
+        assert_eq!(
+            contract.remove_allowed_receiver(issuer_acc()),
+            Err(ContractError::NotContractOwner(issuer_acc()))
+        );
+        assert_eq!(
+            contract.set_allowlist_enabled(true),
+            Err(ContractError::NotContractOwner(issuer_acc()))
+        );
+    }
+
+    #[test]
+    fn test_pause() {
+        let mut contract = get_contract_with_limit(5);
+
+        testing_env!(get_context(issuer_acc(), 10));
+        let payment_id = contract
+            .create_payment(U64(1), U128(1), receiver_acc(), None)
+            .unwrap();
+
+        // pause as the owner
+        testing_env!(get_context(contract_acc(), 1));
+        contract.set_paused(true).unwrap();
+
+        testing_env!(get_context(issuer_acc(), 10));
+        assert_eq!(
+            contract.create_payment(U64(1), U128(1), receiver_acc(), None),
+            Err(ContractError::ContractPaused)
+        );
+
+        testing_env!(get_context(receiver_acc(), 1));
+        assert_eq!(
+            contract.process_pending_payment(ProcessStatus::Approve(payment_id)),
+            Err(ContractError::ContractPaused)
+        );
+        assert_eq!(
+            contract.claim_payment(payment_id),
+            Err(ContractError::ContractPaused)
+        );
+
+        // the payment processing is resumed after the unpause
+        testing_env!(get_context(contract_acc(), 1));
+        contract.set_paused(false).unwrap();
+
+        testing_env!(get_context(receiver_acc(), 0));
+        assert!(contract
+            .process_pending_payment(ProcessStatus::Approve(payment_id))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_pause_not_owner() {
+        let mut contract = get_contract_with_limit(5);
+
+        testing_env!(get_context(issuer_acc(), 1));
+        assert_eq!(
+            contract.set_paused(true),
+            Err(ContractError::NotContractOwner(issuer_acc()))
+        );
+    }
+
+    #[test]
+    fn test_set_owner() {
+        let mut contract = get_contract_with_limit(5);
+        assert_eq!(contract.get_owner(), contract_acc());
+
+        contract.set_owner(accounts(3)).unwrap();
+        assert_eq!(contract.get_owner(), accounts(3));
+        assert_eq!(
+            get_logs(),
+            vec![format!(
+                r#"EVENT_JSON:{{"standard":"near_payment_receiver","version":"1.0.0","event":"owner_changed","data":[{{"old_owner":"{}","new_owner":"{}"}}]}}"#,
+                contract_acc(),
+                accounts(3)
+            )]
+        );
+
+        // the previous owner loses the access
+        assert_eq!(
+            contract.set_paused(true),
+            Err(ContractError::NotContractOwner(contract_acc()))
+        );
+
+        // the new owner manages the contract
+        testing_env!(get_context(accounts(3), 1));
+        contract.set_paused(true).unwrap();
+        contract.set_owner(contract_acc()).unwrap();
+        assert_eq!(contract.get_owner(), contract_acc());
+    }
+
+    #[test]
+    fn test_set_owner_not_owner() {
+        let mut contract = get_contract_with_limit(5);
+
+        testing_env!(get_context(issuer_acc(), 1));
+        assert_eq!(
+            contract.set_owner(issuer_acc()),
+            Err(ContractError::NotContractOwner(issuer_acc()))
+        );
+        assert_eq!(contract.get_owner(), contract_acc());
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_set_owner_without_yocto() {
+        let mut contract = get_contract_with_limit(5);
+
+        testing_env!(get_context(contract_acc(), 0));
+        let _ = contract.set_owner(accounts(3));
+    }
+
+    #[test]
+    fn test_recalculate_total_locked() {
+        let mut contract = get_contract_with_limit(5);
+
+        create_payment(&mut contract, 10, 1);
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        // the claimed amount is not locked anymore
+        set_block_timestamp(NANOS_IN_DAY * 3 + 1);
+        contract
+            .claim_payment_impl(&receiver_acc(), payment_id)
+            .unwrap();
+        assert_eq!(contract.get_total_locked_near(), U128(17));
+
+        // the running total is restored from the active payments page by page
+        contract.total_locked_near = 0;
+        testing_env!(get_context(contract_acc(), 1));
+        let first_page = contract.recalculate_total_locked(U64(1)).unwrap();
+        assert_eq!(first_page.remaining, U64(1));
+        assert_eq!(contract.get_total_locked_near(), U128(0));
+
+        assert_eq!(
+            contract.recalculate_total_locked(U64(1)),
+            Ok(LockedRecalculationResult {
+                total_locked: U128(17),
+                remaining: U64(0),
+            })
+        );
+        assert_eq!(contract.get_total_locked_near(), U128(17));
+
+        // the finished recalculation is started over by the next call
+        assert_eq!(
+            contract.recalculate_total_locked(U64(10)),
+            Ok(LockedRecalculationResult {
+                total_locked: U128(17),
+                remaining: U64(0),
+            })
+        );
+
+        testing_env!(get_context(issuer_acc(), 1));
+        assert_eq!(
+            contract.recalculate_total_locked(U64(10)),
+            Err(ContractError::NotContractOwner(issuer_acc()))
+        );
+    }
+
+    #[test]
+    fn test_migrate_old_state() {
+        let mut context = get_context(contract_acc(), 0);
+        context.current_account_id = contract_acc();
+        testing_env!(context);
+
+        assert_eq!(
+            PaymentContract::migrate().err(),
+            Some(ContractError::StateNotInitialized)
+        );
+
+        // state deployed with the previous version of the contract
+        let mut old_state = OldState {
+            issuer_ledger: UnorderedMap::new(StorageKey::IssuerLedger),
+            receiver_ledger: UnorderedMap::new(StorageKey::ReceiverLedger),
+            payment_info_ledger: UnorderedMap::new(StorageKey::PaymentReceiptLedger),
+            payment_id_counter: 2,
+        };
+        old_state.payment_info_ledger.insert(
+            1,
+            PaymentReceiptV1 {
+                payment_info: PaymentInfoV1 {
+                    initiale_date: Some(1),
+                    period_duration: NANOS_IN_DAY,
+                    payment_amount: 1,
+                    total_amount: 10,
+                    last_payment_date: None,
+                },
+                issuer: issuer_acc(),
+                receiver: receiver_acc(),
+            }
+            .into(),
+        );
+        insert_ledger_payment_id(
+            &mut old_state.issuer_ledger,
+            &issuer_acc(),
+            1,
+            StorageKey::IssuerLedgerRecord { user: issuer_acc() },
+        );
+        insert_ledger_payment_id(
+            &mut old_state.receiver_ledger,
+            &receiver_acc(),
+            1,
+            StorageKey::ReceiverLedgerRecord {
+                user: receiver_acc(),
+            },
+        );
+        env::state_write(&old_state);
+        // the collections are written to the storage when they are dropped
+        drop(old_state);
+
+        let mut contract = PaymentContract::migrate().unwrap();
+
+        // the ledgers and the counter are kept, the settings get the defaults
+        assert_eq!(
+            contract.get_limits(),
+            ContractLimits {
+                max_payments_per_account: DEFAULT_MAX_PAYMENTS_PER_ACCOUNT,
+                min_payment_amount: U128(DEFAULT_MIN_PAYMENT_AMOUNT),
+                min_period_duration: U64(DEFAULT_MIN_PERIOD_DURATION),
+            }
+        );
+        assert_eq!(
+            contract.get_payment(U64(1)).unwrap().receiver,
+            receiver_acc()
+        );
+        assert!(contract.issuer_ledger[&issuer_acc()].contains(&1));
+        assert!(contract.receiver_ledger[&receiver_acc()].contains(&1));
+
+        // the deposits of the legacy payments are counted by the following owner calls
+        testing_env!(get_context(contract_acc(), 1));
+        assert_eq!(
+            contract.recalculate_total_locked(U64(10)),
+            Ok(LockedRecalculationResult {
+                total_locked: U128(10),
+                remaining: U64(0),
+            })
+        );
+        assert_eq!(
+            contract.migrate_storage(U64(10)),
+            Ok(MigrationResult {
+                migrated: U64(1),
+                remaining: U64(0),
+            })
+        );
+        assert!(contract.payment_info_ledger[&1].is_current());
+
+        // the new payment continues the id sequence
+        testing_env!(get_context_with_storage_usage(issuer_acc(), 10));
+        assert_eq!(
+            contract.create_payment(U64(1), U128(1), receiver_acc(), None),
+            Ok(U64(2))
+        );
+    }
+
+    #[test]
+    fn test_migrate_storage() {
+        let mut contract = get_contract_with_limit(5);
+
+        for _ in 0..3 {
+            create_payment(&mut contract, 10, 1);
+        }
+
+        // receipts stored with the previous version of the contract
+        for payment_id in [1, 3] {
+            contract.payment_info_ledger.insert(
+                payment_id,
+                PaymentReceiptV1 {
+                    payment_info: PaymentInfoV1 {
+                        initiale_date: None,
+                        period_duration: NANOS_IN_DAY,
+                        payment_amount: 1,
+                        total_amount: 10,
+                        last_payment_date: None,
+                    },
+                    issuer: issuer_acc(),
+                    receiver: receiver_acc(),
+                }
+                .into(),
+            );
+        }
+
+        testing_env!(get_context(issuer_acc(), 1));
+        assert_eq!(
+            contract.migrate_storage(U64(2)),
+            Err(ContractError::NotContractOwner(issuer_acc()))
+        );
+
+        testing_env!(get_context(contract_acc(), 1));
+        assert_eq!(
+            contract.migrate_storage(U64(2)),
+            Ok(MigrationResult {
+                migrated: U64(1),
+                remaining: U64(1),
+            })
+        );
+        assert!(matches!(
+            contract.payment_info_ledger.get(&1),
+            Some(PaymentReceipt::V2(_))
+        ));
+        assert!(matches!(
+            contract.payment_info_ledger.get(&3),
+            Some(PaymentReceipt::V1(_))
+        ));
+
+        assert_eq!(
+            contract.migrate_storage(U64(2)),
+            Ok(MigrationResult {
+                migrated: U64(1),
+                remaining: U64(0),
+            })
+        );
+        assert!(matches!(
+            contract.payment_info_ledger.get(&3),
+            Some(PaymentReceipt::V2(_))
+        ));
+
+        let receipt = contract.payment_info_ledger[&3].into_current();
+        assert_eq!(receipt.created_at, 0);
+        assert_eq!(receipt.approved_at, None);
+        assert_eq!(receipt.memo, None);
+
+        // the repeated call is a no-op
+        assert_eq!(
+            contract.migrate_storage(U64(2)),
+            Ok(MigrationResult {
+                migrated: U64(0),
+                remaining: U64(0),
+            })
+        );
+    }
+
+    #[test]
+    fn test_set_source_metadata() {
+        let mut contract = get_contract_with_limit(5);
+
+        let source_metadata = ContractSourceMetadata {
+            version: Some("1.1.1".to_string()),
+            link: None,
+            standards: vec![],
+        };
+
+        testing_env!(get_context(issuer_acc(), 1));
+        assert_eq!(
+            contract.set_source_metadata(source_metadata.clone()),
+            Err(ContractError::NotContractOwner(issuer_acc()))
+        );
+        assert_eq!(
+            contract.contract_source_metadata(),
+            ContractSourceMetadata::default()
+        );
+
+        testing_env!(get_context(contract_acc(), 1));
+        contract
+            .set_source_metadata(source_metadata.clone())
+            .unwrap();
+        assert_eq!(contract.contract_source_metadata(), source_metadata);
+    }
+
+    #[test]
+    fn test_self_payments() {
+        let mut contract = get_contract_with_limit(5);
+
+        // allowed by default
+        assert!(contract.are_self_payments_allowed());
+        testing_env!(get_context(issuer_acc(), 10));
+        assert!(contract
+            .create_payment(U64(1), U128(1), issuer_acc(), None)
+            .is_ok());
+
+        testing_env!(get_context(issuer_acc(), 1));
+        assert_eq!(
+            contract.set_self_payments_allowed(false),
+            Err(ContractError::NotContractOwner(issuer_acc()))
+        );
+
+        testing_env!(get_context(contract_acc(), 1));
+        contract.set_self_payments_allowed(false).unwrap();
+        assert!(!contract.are_self_payments_allowed());
+
+        testing_env!(get_context(issuer_acc(), 10));
+        assert_eq!(
+            contract.create_payment(U64(1), U128(1), issuer_acc(), None),
+            Err(ContractError::SelfPaymentNotAllowed(issuer_acc()))
+        );
+        assert!(contract
+            .create_payment(U64(1), U128(1), receiver_acc(), None)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_set_settlement_offer_days() {
+        let mut contract = get_contract_with_limit(5);
+        assert_eq!(
+            contract.get_settlement_offer_days(),
+            U64(DEFAULT_SETTLEMENT_OFFER_DAYS)
+        );
+
+        contract.set_settlement_offer_days(U64(3)).unwrap();
+        assert_eq!(contract.get_settlement_offer_days(), U64(3));
+
+        testing_env!(get_context(issuer_acc(), 1));
+        assert_eq!(
+            contract.set_settlement_offer_days(U64(1)),
+            Err(ContractError::NotContractOwner(issuer_acc()))
+        );
+    }
+
+    #[test]
+    fn test_set_issuer_cancellation_notice_days() {
+        let mut contract = get_contract_with_limit(5);
+        assert_eq!(
+            contract.get_issuer_cancellation_notice_days(),
+            U64(DEFAULT_ISSUER_CANCELLATION_NOTICE_DAYS)
+        );
+
+        contract
+            .set_issuer_cancellation_notice_days(U64(14))
+            .unwrap();
+        assert_eq!(contract.get_issuer_cancellation_notice_days(), U64(14));
+
+        testing_env!(get_context(issuer_acc(), 1));
+        assert_eq!(
+            contract.set_issuer_cancellation_notice_days(U64(1)),
+            Err(ContractError::NotContractOwner(issuer_acc()))
+        );
+    }
+}
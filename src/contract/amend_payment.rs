@@ -0,0 +1,595 @@
+use super::PaymentContract;
+use crate::constants::{GAS_FOR_TRANSFER, GAS_FOR_TRANSFER_CALLBACK};
+use crate::contract::PaymentContractExt;
+use crate::error::{ensure, ContractError};
+use crate::events::{AmendmentProposed, AmendmentResolved, ContractEvent};
+use crate::public::completed_payment::CompletionReason;
+use crate::public::payment_info::{PaymentStatus, PeriodUnit};
+use crate::public::payment_receipt::{PendingAmendment, ReceiptState};
+use crate::Result;
+use near_sdk::{
+    assert_one_yocto, env,
+    json_types::{U128, U64},
+    near_bindgen, Gas,
+};
+
+#[near_bindgen]
+impl PaymentContract {
+    // the new terms are applied only after the receiver accepts them,
+    // the attached deposit tops up the remainder of the stream
+    #[payable]
+    #[handle_result]
+    pub fn propose_amendment(
+        &mut self,
+        payment_id: U64,
+        new_payment_amount: Option<U128>,
+        new_period_days: Option<U64>,
+    ) -> Result<()> {
+        self.check_not_paused()?;
+
+        let issuer = env::predecessor_account_id();
+        let payment_id = payment_id.0;
+        let top_up = env::attached_deposit();
+
+        self.check_payment_unlocked(payment_id)?;
+        self.check_issue_payment_id(&issuer, payment_id)?;
+
+        let payment_receipt = self.load_payment_receipt(payment_id)?;
+
+        ensure!(
+            payment_receipt.receivers.is_empty(),
+            ContractError::AmendmentNotSupported(payment_id),
+        );
+        // the terms of the stream being cancelled are not changed anymore
+        ensure!(
+            payment_receipt.state == ReceiptState::Active,
+            ContractError::CancellationAlreadyPending(payment_id),
+        );
+        ensure!(
+            payment_receipt.pending_amendment.is_none(),
+            ContractError::AmendmentAlreadyPending(payment_id),
+        );
+        ensure!(
+            new_payment_amount.is_some() || new_period_days.is_some() || top_up > 0,
+            ContractError::EmptyAmendment(payment_id),
+        );
+
+        let payment_info = &payment_receipt.payment_info;
+        let settlement_time = payment_receipt.settlement_time(env::block_timestamp());
+
+        // the amount accrued under the current terms is settled on the acceptance, so it does not fund the new terms
+        let accrued_amount =
+            match payment_info.calculate_payment_status_impl(payment_id, settlement_time)? {
+                PaymentStatus::Absent => 0,
+                PaymentStatus::PaymentReady(amount) | PaymentStatus::FinalPayment(amount) => amount,
+            };
+
+        let funded_amount = payment_info
+            .total_amount
+            .checked_sub(payment_info.claimed_amount)
+            .and_then(|value| value.checked_sub(accrued_amount))
+            .and_then(|value| value.checked_add(top_up))
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        let payment_amount =
+            new_payment_amount.map_or(payment_info.payment_amount, |value| value.0);
+
+        ensure!(
+            funded_amount >= payment_amount,
+            ContractError::InsufficientTopUp(payment_id, funded_amount, payment_amount),
+        );
+
+        let (period_duration_units, period_unit) = match new_period_days {
+            Some(period_days) => (period_days.0, PeriodUnit::Days),
+            None => (payment_receipt.period_value, payment_receipt.period_unit),
+        };
+
+        // the new terms are validated the same way as the terms of the new payment
+        let new_terms = self.build_payment_info(
+            funded_amount,
+            payment_amount,
+            period_duration_units,
+            period_unit,
+        )?;
+
+        let amendment = PendingAmendment {
+            payment_amount: new_terms.payment_amount,
+            period_duration: new_terms.period_duration,
+            top_up,
+        };
+
+        ContractEvent::AmendmentProposed(vec![AmendmentProposed {
+            payment_id: payment_id.into(),
+            payment_amount: amendment.payment_amount.into(),
+            period_duration: amendment.period_duration.into(),
+            top_up: amendment.top_up.into(),
+        }])
+        .emit();
+
+        self.lock_near(payment_id, top_up)?;
+
+        self.payment_info_ledger
+            .get_mut(&payment_id)
+            .ok_or(ContractError::PaymentIdNotExist(payment_id))?
+            .into_current_mut()
+            .pending_amendment = Some(amendment);
+
+        Ok(())
+    }
+
+    // on the acceptance the accrued amount is paid out under the old terms and the rest of the stream
+    // is restarted with the new terms, on the rejection the top-up is returned to the issuer
+    #[payable]
+    #[handle_result]
+    pub fn respond_amendment(&mut self, payment_id: U64, accept: bool) -> Result<U128> {
+        assert_one_yocto();
+        self.check_not_paused()?;
+        self.check_prepaid_gas(Gas(GAS_FOR_TRANSFER.0 + GAS_FOR_TRANSFER_CALLBACK.0))?;
+
+        let receiver = env::predecessor_account_id();
+        let payment_id = payment_id.0;
+
+        self.check_payment_unlocked(payment_id)?;
+        self.check_receiver_payment_id(&receiver, payment_id)?;
+
+        let payment_receipt = self
+            .payment_info_ledger
+            .get_mut(&payment_id)
+            .ok_or(ContractError::PaymentIdNotExist(payment_id))?
+            .into_current_mut();
+
+        ensure!(
+            payment_receipt.state == ReceiptState::Active,
+            ContractError::CancellationAlreadyPending(payment_id),
+        );
+
+        let amendment = payment_receipt
+            .pending_amendment
+            .take()
+            .ok_or(ContractError::NoPendingAmendment(payment_id))?;
+        let issuer = payment_receipt.issuer.clone();
+        let payout_account = payment_receipt.payout_account(&receiver);
+
+        if !accept {
+            ContractEvent::AmendmentResolved(vec![AmendmentResolved {
+                payment_id: payment_id.into(),
+                accepted: false,
+            }])
+            .emit();
+
+            self.release_locked_near(payment_id, amendment.top_up)?;
+            self.transfer_with_fallback(issuer, amendment.top_up);
+
+            return Ok(U128(0));
+        }
+
+        let current_time = env::block_timestamp();
+        let settlement_time = payment_receipt.settlement_time(current_time);
+        let payment_info = &mut payment_receipt.payment_info;
+
+        let settled_amount =
+            match payment_info.calculate_payment_status_impl(payment_id, settlement_time)? {
+                PaymentStatus::Absent => 0,
+                PaymentStatus::PaymentReady(amount) | PaymentStatus::FinalPayment(amount) => {
+                    payment_info.register_claim(payment_id, amount, settlement_time)?;
+                    amount
+                }
+            };
+
+        let initiale_date = payment_info
+            .initiale_date
+            .ok_or(ContractError::PaymentReceiptNotConfirmed(payment_id))?;
+        let end_date = payment_info
+            .calculate_end_date(initiale_date)
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        // the new terms continue from the boundary of the settled period, the finished stream is restarted now
+        let start_date = if current_time >= end_date {
+            current_time
+        } else {
+            payment_info.last_payment_date.unwrap_or(initiale_date)
+        };
+
+        let remaining_amount = payment_info
+            .total_amount
+            .checked_sub(payment_info.claimed_amount)
+            .and_then(|value| value.checked_add(amendment.top_up))
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        payment_info.initiale_date = Some(start_date);
+        payment_info.last_payment_date = None;
+        payment_info.payment_amount = amendment.payment_amount;
+        payment_info.period_duration = amendment.period_duration;
+        payment_info.total_amount = remaining_amount;
+        payment_info.claimed_amount = 0;
+
+        // the offer is made for the previous terms
+        payment_receipt.settlement_offer = None;
+
+        // the new period is proposed in days, the unchanged period keeps the original unit
+        if payment_receipt
+            .period_unit
+            .nanos()
+            .checked_mul(payment_receipt.period_value)
+            != Some(amendment.period_duration)
+        {
+            payment_receipt.period_unit = PeriodUnit::Days;
+            payment_receipt.period_value = amendment.period_duration / PeriodUnit::Days.nanos();
+        }
+
+        ContractEvent::AmendmentResolved(vec![AmendmentResolved {
+            payment_id: payment_id.into(),
+            accepted: true,
+        }])
+        .emit();
+
+        if settled_amount > 0 {
+            self.record_claim(payment_id, settled_amount);
+            self.release_locked_near(payment_id, settled_amount)?;
+        }
+
+        // everything has been streamed before the acceptance
+        if remaining_amount == 0 {
+            self.remove_payment_related_data(
+                &issuer,
+                &receiver,
+                payment_id,
+                CompletionReason::Claimed,
+                0,
+            )?;
+        }
+
+        self.payout_claimed_amount(payout_account, payment_id, settled_amount)
+            .map(U128)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constants::NANOS_IN_DAY,
+        contract::general_impl::tests::{
+            check_all_account_data_removed, contract_acc, create_payment, get_context, issuer_acc,
+            receiver_acc, set_block_timestamp,
+        },
+        public::{payment_receipt::PendingAmendmentView, ProcessStatus},
+    };
+
+    use super::*;
+    use near_sdk::{test_utils::get_logs, testing_env};
+
+    fn create_approved_payment(contract: &mut PaymentContract, deposit: u128, amount: u128) -> u64 {
+        let payment_id = create_payment(contract, deposit, amount);
+
+        testing_env!(get_context(receiver_acc(), 0));
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        payment_id
+    }
+
+    fn propose(
+        contract: &mut PaymentContract,
+        payment_id: u64,
+        timestamp: u64,
+        top_up: u128,
+        new_payment_amount: Option<u128>,
+        new_period_days: Option<u64>,
+    ) -> Result<()> {
+        let mut context = get_context(issuer_acc(), top_up);
+        context.block_timestamp = timestamp;
+        testing_env!(context);
+
+        contract.propose_amendment(
+            U64(payment_id),
+            new_payment_amount.map(U128),
+            new_period_days.map(U64),
+        )
+    }
+
+    fn respond(
+        contract: &mut PaymentContract,
+        payment_id: u64,
+        timestamp: u64,
+        accept: bool,
+    ) -> Result<U128> {
+        let mut context = get_context(receiver_acc(), 1);
+        context.block_timestamp = timestamp;
+        testing_env!(context);
+
+        contract.respond_amendment(U64(payment_id), accept)
+    }
+
+    #[test]
+    fn test_accept_amendment_mid_period() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_approved_payment(&mut contract, 10, 1);
+
+        // 7 is left for the new terms after 3 accrued periods, the top-up makes it 11
+        propose(
+            &mut contract,
+            payment_id,
+            NANOS_IN_DAY * 3 + NANOS_IN_DAY / 2,
+            4,
+            Some(2),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            contract
+                .get_payment(U64(payment_id))
+                .unwrap()
+                .pending_amendment,
+            Some(PendingAmendmentView {
+                payment_amount: U128(2),
+                period_duration: U64(NANOS_IN_DAY),
+                top_up: U128(4),
+            })
+        );
+        assert_eq!(contract.get_total_locked_near(), U128(14));
+
+        // the accrued periods are paid out under the old terms
+        assert_eq!(
+            respond(
+                &mut contract,
+                payment_id,
+                NANOS_IN_DAY * 3 + NANOS_IN_DAY / 2,
+                true
+            ),
+            Ok(U128(3))
+        );
+
+        let payment_info = contract.get_payment(U64(payment_id)).unwrap().payment_info;
+        assert_eq!(payment_info.initiale_date, Some(U64(NANOS_IN_DAY * 3)));
+        assert_eq!(payment_info.last_payment_date, None);
+        assert_eq!(payment_info.payment_amount, U128(2));
+        assert_eq!(payment_info.total_amount, U128(11));
+        assert_eq!(payment_info.claimed_amount, U128(0));
+        assert_eq!(payment_info.lifetime_claimed_amount, U128(3));
+        assert_eq!(contract.get_total_locked_near(), U128(11));
+
+        // the part of the period passed before the acceptance is paid under the new terms
+        contract.set_payment_locked(payment_id, false);
+        set_block_timestamp(NANOS_IN_DAY * 5);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(4)
+        );
+
+        // the final event reports the claims made under both terms
+        set_block_timestamp(NANOS_IN_DAY * 10);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(7)
+        );
+        assert_eq!(
+            get_logs().last().unwrap(),
+            &format!(
+                r#"EVENT_JSON:{{"standard":"near_payment_receiver","version":"1.0.0","event":"payment_claimed","data":[{{"payment_id":"{}","receiver":"{}","claimed_amount":"14"}}]}}"#,
+                payment_id,
+                receiver_acc()
+            )
+        );
+    }
+
+    #[test]
+    fn test_reject_amendment() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_approved_payment(&mut contract, 10, 1);
+
+        propose(&mut contract, payment_id, NANOS_IN_DAY, 5, Some(2), Some(2)).unwrap();
+        assert_eq!(contract.get_total_locked_near(), U128(15));
+
+        assert_eq!(
+            respond(&mut contract, payment_id, NANOS_IN_DAY * 2, false),
+            Ok(U128(0))
+        );
+
+        // the top-up is refunded and the terms are kept
+        let payment_receipt = contract.get_payment(U64(payment_id)).unwrap();
+        assert_eq!(payment_receipt.pending_amendment, None);
+        assert_eq!(payment_receipt.payment_info.payment_amount, U128(1));
+        assert_eq!(payment_receipt.payment_info.total_amount, U128(10));
+        assert_eq!(contract.get_total_locked_near(), U128(10));
+
+        // the new amendment could be proposed after the response
+        propose(
+            &mut contract,
+            payment_id,
+            NANOS_IN_DAY * 2,
+            0,
+            Some(2),
+            None,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_amendment_errors() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        // the unconfirmed payment could not be amended
+        assert_eq!(
+            propose(&mut contract, payment_id, 0, 0, Some(2), None),
+            Err(ContractError::PaymentReceiptNotConfirmed(payment_id))
+        );
+
+        testing_env!(get_context(receiver_acc(), 0));
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        assert_eq!(
+            propose(&mut contract, payment_id, 0, 0, None, None),
+            Err(ContractError::EmptyAmendment(payment_id))
+        );
+        assert_eq!(
+            respond(&mut contract, payment_id, 0, true),
+            Err(ContractError::NoPendingAmendment(payment_id))
+        );
+
+        // the top-up should fund at least one period of the new terms
+        assert_eq!(
+            propose(&mut contract, payment_id, 0, 10, Some(21), None),
+            Err(ContractError::InsufficientTopUp(payment_id, 20, 21))
+        );
+
+        // only the issuer could propose the amendment
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 0;
+        testing_env!(context);
+        assert_eq!(
+            contract.propose_amendment(U64(payment_id), Some(U128(2)), None),
+            Err(ContractError::IssuerAccountNotExist(receiver_acc()))
+        );
+
+        propose(&mut contract, payment_id, 0, 0, Some(2), None).unwrap();
+        assert_eq!(
+            propose(&mut contract, payment_id, 0, 0, Some(3), None),
+            Err(ContractError::AmendmentAlreadyPending(payment_id))
+        );
+
+        // the amendment stays pending when the gas is not enough for the payout and its callback
+        let mut context = get_context(receiver_acc(), 1);
+        context.prepaid_gas = Gas(GAS_FOR_TRANSFER.0);
+        testing_env!(context);
+        assert_eq!(
+            contract.respond_amendment(U64(payment_id), true),
+            Err(ContractError::InsufficientGas(
+                GAS_FOR_TRANSFER.0 + GAS_FOR_TRANSFER_CALLBACK.0,
+                GAS_FOR_TRANSFER.0
+            ))
+        );
+        assert!(contract
+            .get_payment(U64(payment_id))
+            .unwrap()
+            .pending_amendment
+            .is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_respond_amendment_without_yocto() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_approved_payment(&mut contract, 10, 1);
+        propose(&mut contract, payment_id, 0, 0, Some(2), None).unwrap();
+
+        testing_env!(get_context(receiver_acc(), 0));
+        contract.respond_amendment(U64(payment_id), true).unwrap();
+    }
+
+    #[test]
+    fn test_accept_amendment_after_end_date() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_approved_payment(&mut contract, 10, 1);
+
+        // the finished stream is extended by the top-up only
+        propose(
+            &mut contract,
+            payment_id,
+            NANOS_IN_DAY * 12,
+            6,
+            Some(3),
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            respond(&mut contract, payment_id, NANOS_IN_DAY * 15, true),
+            Ok(U128(10))
+        );
+
+        let payment_info = contract.get_payment(U64(payment_id)).unwrap().payment_info;
+        assert_eq!(payment_info.initiale_date, Some(U64(NANOS_IN_DAY * 15)));
+        assert_eq!(payment_info.total_amount, U128(6));
+        assert_eq!(payment_info.payment_amount, U128(3));
+        assert_eq!(contract.get_total_locked_near(), U128(6));
+    }
+
+    #[test]
+    fn test_accept_amendment_without_remainder() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_approved_payment(&mut contract, 10, 1);
+
+        propose(
+            &mut contract,
+            payment_id,
+            NANOS_IN_DAY * 5,
+            0,
+            Some(2),
+            None,
+        )
+        .unwrap();
+
+        // the rest of the stream has been accrued before the acceptance
+        assert_eq!(
+            respond(&mut contract, payment_id, NANOS_IN_DAY * 10, true),
+            Ok(U128(10))
+        );
+
+        check_all_account_data_removed(&contract, payment_id);
+        assert_eq!(contract.get_total_locked_near(), U128(0));
+    }
+
+    #[test]
+    fn test_remove_payment_with_pending_amendment() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_approved_payment(&mut contract, 10, 1);
+
+        propose(
+            &mut contract,
+            payment_id,
+            NANOS_IN_DAY * 5,
+            3,
+            Some(2),
+            None,
+        )
+        .unwrap();
+
+        // the final claim closes the stream, the top-up is refunded to the issuer
+        set_block_timestamp(NANOS_IN_DAY * 10);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(10)
+        );
+
+        check_all_account_data_removed(&contract, payment_id);
+        assert_eq!(contract.get_total_locked_near(), U128(0));
+    }
+}
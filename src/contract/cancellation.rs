@@ -0,0 +1,373 @@
+use super::PaymentContract;
+use crate::constants::{GAS_FOR_TRANSFER, GAS_FOR_TRANSFER_CALLBACK, NANOS_IN_DAY};
+use crate::contract::PaymentContractExt;
+use crate::error::{ensure, ContractError};
+use crate::events::{
+    AmendmentResolved, CancellationFinalized, CancellationRequested, ContractEvent,
+};
+use crate::public::completed_payment::CompletionReason;
+use crate::public::payment_receipt::ReceiptState;
+use crate::public::RejectPaymentResult;
+use crate::Result;
+use near_sdk::{
+    assert_one_yocto, env,
+    json_types::{U128, U64},
+    near_bindgen, Gas,
+};
+
+#[near_bindgen]
+impl PaymentContract {
+    // nothing is paid out by the request, the split is made by finalize_cancellation
+    #[handle_result]
+    pub(crate) fn request_cancellation(&mut self, payment_id: u64) -> Result<RejectPaymentResult> {
+        let effective_at = self
+            .issuer_cancellation_notice_days
+            .checked_mul(NANOS_IN_DAY)
+            .and_then(|notice_period| env::block_timestamp().checked_add(notice_period))
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        let payment_receipt = self
+            .payment_info_ledger
+            .get_mut(&payment_id)
+            .ok_or(ContractError::PaymentIdNotExist(payment_id))?
+            .into_current_mut();
+
+        payment_receipt.state = ReceiptState::CancellationPending { effective_at };
+
+        // the proposed terms could not be accepted anymore, so their top-up is returned to the issuer
+        let pending_amendment = payment_receipt.pending_amendment.take();
+        let issuer = payment_receipt.issuer.clone();
+
+        ContractEvent::CancellationRequested(vec![CancellationRequested {
+            payment_id: payment_id.into(),
+            effective_at: effective_at.into(),
+        }])
+        .emit();
+
+        if let Some(amendment) = pending_amendment {
+            ContractEvent::AmendmentResolved(vec![AmendmentResolved {
+                payment_id: payment_id.into(),
+                accepted: false,
+            }])
+            .emit();
+
+            self.release_locked_near(payment_id, amendment.top_up)?;
+            self.transfer_with_fallback(issuer, amendment.top_up);
+        }
+
+        Ok(RejectPaymentResult {
+            issuer_refund: U128(0),
+            receiver_payout: U128(0),
+        })
+    }
+
+    // could be called by anyone, the stream is split as it was at the effective date
+    #[payable]
+    #[handle_result]
+    pub fn finalize_cancellation(&mut self, payment_id: U64) -> Result<RejectPaymentResult> {
+        assert_one_yocto();
+        // the issuer refund and the receiver payout are transferred together
+        self.check_prepaid_gas(Gas(GAS_FOR_TRANSFER.0 * 2 + GAS_FOR_TRANSFER_CALLBACK.0))?;
+
+        let payment_id = payment_id.0;
+        self.check_payment_unlocked(payment_id)?;
+
+        let effective_at = match self.load_payment_receipt(payment_id)?.state {
+            ReceiptState::CancellationPending { effective_at } => effective_at,
+            ReceiptState::Active => return Err(ContractError::NoPendingCancellation(payment_id)),
+        };
+
+        ensure!(
+            env::block_timestamp() >= effective_at,
+            ContractError::CancellationNotEffective(payment_id, effective_at),
+        );
+
+        let repayment_info =
+            self.reject_payment_receipt_impl(payment_id, CompletionReason::RejectedByIssuer)?;
+        let result = self.pay_out_repayment(payment_id, repayment_info)?;
+
+        ContractEvent::CancellationFinalized(vec![CancellationFinalized {
+            payment_id: payment_id.into(),
+            issuer_refund: result.issuer_refund,
+            receiver_payout: result.receiver_payout,
+        }])
+        .emit();
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        contract::general_impl::tests::{
+            check_all_account_data_removed, contract_acc, create_payment, get_context, issuer_acc,
+            receiver_acc,
+        },
+        public::{payment_receipt::ReceiptStateView, PaymentRole, ProcessStatus},
+    };
+
+    use super::*;
+    use near_sdk::{
+        test_utils::{accounts, get_logs},
+        testing_env, AccountId,
+    };
+
+    fn get_contract_with_payment(notice_days: u64) -> (PaymentContract, u64) {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        contract
+            .set_issuer_cancellation_notice_days(U64(notice_days))
+            .unwrap();
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        testing_env!(get_context(receiver_acc(), 0));
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        (contract, payment_id)
+    }
+
+    fn set_caller_at(account_id: AccountId, block_timestamp: u64) {
+        let mut context = get_context(account_id, 1);
+        context.block_timestamp = block_timestamp;
+        testing_env!(context);
+    }
+
+    #[test]
+    fn test_cancellation_with_pending_amendment() {
+        let (mut contract, payment_id) = get_contract_with_payment(3);
+
+        let mut context = get_context(issuer_acc(), 4);
+        context.block_timestamp = NANOS_IN_DAY * 2 + 1;
+        testing_env!(context);
+        contract
+            .propose_amendment(U64(payment_id), Some(U128(2)), None)
+            .unwrap();
+        assert_eq!(contract.get_total_locked_near(), U128(14));
+
+        // the cancellation drops the proposed terms and returns their top-up
+        set_caller_at(issuer_acc(), NANOS_IN_DAY * 2 + 1);
+        contract
+            .reject_payment_receipt(U64(payment_id), PaymentRole::Issuer)
+            .unwrap();
+        assert_eq!(
+            get_logs().last().unwrap(),
+            &format!(
+                r#"EVENT_JSON:{{"standard":"near_payment_receiver","version":"1.0.0","event":"amendment_resolved","data":[{{"payment_id":"{}","accepted":false}}]}}"#,
+                payment_id
+            )
+        );
+        assert_eq!(
+            contract
+                .get_payment(U64(payment_id))
+                .unwrap()
+                .pending_amendment,
+            None
+        );
+        assert_eq!(contract.get_total_locked_near(), U128(10));
+
+        // the stream being cancelled could not be amended after the effective date
+        set_caller_at(receiver_acc(), NANOS_IN_DAY * 6);
+        assert_eq!(
+            contract.respond_amendment(U64(payment_id), true),
+            Err(ContractError::CancellationAlreadyPending(payment_id))
+        );
+
+        let mut context = get_context(issuer_acc(), 4);
+        context.block_timestamp = NANOS_IN_DAY * 6;
+        testing_env!(context);
+        assert_eq!(
+            contract.propose_amendment(U64(payment_id), Some(U128(2)), None),
+            Err(ContractError::CancellationAlreadyPending(payment_id))
+        );
+
+        // only the periods before the effective date are paid to the receiver
+        set_caller_at(accounts(5), NANOS_IN_DAY * 6);
+        assert_eq!(
+            contract.finalize_cancellation(U64(payment_id)),
+            Ok(RejectPaymentResult {
+                issuer_refund: U128(5),
+                receiver_payout: U128(5),
+            })
+        );
+    }
+
+    #[test]
+    fn test_cancellation_notice_period() {
+        let (mut contract, payment_id) = get_contract_with_payment(3);
+
+        set_caller_at(issuer_acc(), NANOS_IN_DAY * 2 + 1);
+        assert_eq!(
+            contract.reject_payment_receipt(U64(payment_id), PaymentRole::Issuer),
+            Ok(RejectPaymentResult {
+                issuer_refund: U128(0),
+                receiver_payout: U128(0),
+            })
+        );
+        assert_eq!(
+            get_logs().last().unwrap(),
+            &format!(
+                r#"EVENT_JSON:{{"standard":"near_payment_receiver","version":"1.0.0","event":"cancellation_requested","data":[{{"payment_id":"{}","effective_at":"{}"}}]}}"#,
+                payment_id,
+                NANOS_IN_DAY * 5 + 1
+            )
+        );
+
+        let effective_at = NANOS_IN_DAY * 5 + 1;
+        assert_eq!(
+            contract.get_payment(U64(payment_id)).unwrap().state,
+            ReceiptStateView::CancellationPending {
+                effective_at: U64(effective_at)
+            }
+        );
+        assert_eq!(
+            contract.reject_payment_receipt(U64(payment_id), PaymentRole::Issuer),
+            Err(ContractError::CancellationAlreadyPending(payment_id))
+        );
+
+        // the receiver claims normally before the effective date
+        set_caller_at(receiver_acc(), NANOS_IN_DAY * 4 + 1);
+        assert_eq!(contract.claim_payment(U64(payment_id)), Ok(U128(4)));
+        contract.on_claim_transfer(U64(payment_id), U128(4), receiver_acc(), Ok(()));
+
+        set_caller_at(accounts(5), effective_at - 1);
+        assert_eq!(
+            contract.finalize_cancellation(U64(payment_id)),
+            Err(ContractError::CancellationNotEffective(
+                payment_id,
+                effective_at
+            ))
+        );
+
+        // the periods after the effective date are not vested
+        set_caller_at(receiver_acc(), NANOS_IN_DAY * 8);
+        assert_eq!(contract.claim_payment(U64(payment_id)), Ok(U128(1)));
+        contract.on_claim_transfer(U64(payment_id), U128(1), receiver_acc(), Ok(()));
+        assert_eq!(contract.claim_payment(U64(payment_id)), Ok(U128(0)));
+
+        // anyone could finalize the cancellation
+        set_caller_at(accounts(5), NANOS_IN_DAY * 8);
+        assert_eq!(
+            contract.finalize_cancellation(U64(payment_id)),
+            Ok(RejectPaymentResult {
+                issuer_refund: U128(5),
+                receiver_payout: U128(0),
+            })
+        );
+        assert_eq!(
+            get_logs().last().unwrap(),
+            &format!(
+                r#"EVENT_JSON:{{"standard":"near_payment_receiver","version":"1.0.0","event":"cancellation_finalized","data":[{{"payment_id":"{}","issuer_refund":"5","receiver_payout":"0"}}]}}"#,
+                payment_id
+            )
+        );
+        assert_eq!(
+            contract
+                .get_completed_payment(U64(payment_id))
+                .unwrap()
+                .completion_reason,
+            CompletionReason::RejectedByIssuer
+        );
+        check_all_account_data_removed(&contract, payment_id);
+    }
+
+    #[test]
+    fn test_finalize_cancellation_at_effective_date() {
+        let (mut contract, payment_id) = get_contract_with_payment(3);
+
+        set_caller_at(issuer_acc(), NANOS_IN_DAY);
+        contract
+            .reject_payment_receipt(U64(payment_id), PaymentRole::Issuer)
+            .unwrap();
+
+        // the split is made as of the effective date, not as of the finalization
+        set_caller_at(accounts(5), NANOS_IN_DAY * 4);
+        assert_eq!(
+            contract.finalize_cancellation(U64(payment_id)),
+            Ok(RejectPaymentResult {
+                issuer_refund: U128(6),
+                receiver_payout: U128(4),
+            })
+        );
+        check_all_account_data_removed(&contract, payment_id);
+
+        assert_eq!(
+            contract.finalize_cancellation(U64(payment_id)),
+            Err(ContractError::PaymentIdNotExist(payment_id))
+        );
+    }
+
+    #[test]
+    fn test_receiver_rejection_during_cancellation() {
+        let (mut contract, payment_id) = get_contract_with_payment(3);
+
+        set_caller_at(issuer_acc(), NANOS_IN_DAY);
+        contract
+            .reject_payment_receipt(U64(payment_id), PaymentRole::Issuer)
+            .unwrap();
+
+        // the receiver rejection is immediate, the vesting still stops at the effective date
+        set_caller_at(receiver_acc(), NANOS_IN_DAY * 9);
+        assert_eq!(
+            contract.reject_payment_receipt(U64(payment_id), PaymentRole::Receiver),
+            Ok(RejectPaymentResult {
+                issuer_refund: U128(6),
+                receiver_payout: U128(4),
+            })
+        );
+        check_all_account_data_removed(&contract, payment_id);
+    }
+
+    #[test]
+    fn test_cancellation_without_notice() {
+        let (mut contract, payment_id) = get_contract_with_payment(0);
+
+        set_caller_at(accounts(5), NANOS_IN_DAY);
+        assert_eq!(
+            contract.finalize_cancellation(U64(payment_id)),
+            Err(ContractError::NoPendingCancellation(payment_id))
+        );
+
+        // the issuer rejection is immediate without the notice period
+        set_caller_at(issuer_acc(), NANOS_IN_DAY * 2 + 1);
+        assert_eq!(
+            contract.reject_payment_receipt(U64(payment_id), PaymentRole::Issuer),
+            Ok(RejectPaymentResult {
+                issuer_refund: U128(8),
+                receiver_payout: U128(2),
+            })
+        );
+        check_all_account_data_removed(&contract, payment_id);
+    }
+
+    #[test]
+    fn test_pending_payment_not_cancelled_with_notice() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        contract
+            .set_issuer_cancellation_notice_days(U64(3))
+            .unwrap();
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        // nothing is vested before the approval, so the notice period is not started
+        set_caller_at(issuer_acc(), NANOS_IN_DAY);
+        assert_eq!(
+            contract.reject_payment_receipt(U64(payment_id), PaymentRole::Issuer),
+            Err(ContractError::PaymentReceiptNotConfirmed(payment_id))
+        );
+        assert_eq!(
+            contract.get_payment(U64(payment_id)).unwrap().state,
+            ReceiptStateView::Active
+        );
+    }
+}
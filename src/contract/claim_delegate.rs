@@ -0,0 +1,173 @@
+use super::PaymentContract;
+use crate::contract::PaymentContractExt;
+use crate::error::{ensure, ContractError};
+use crate::Result;
+use near_sdk::{assert_one_yocto, env, json_types::U64, near_bindgen, AccountId};
+
+#[near_bindgen]
+impl PaymentContract {
+    // the delegate could only trigger the claims, it can't reject the payment or change its settings.
+    // None or the receiver account itself removes the delegate
+    #[payable]
+    #[handle_result]
+    pub fn set_claim_delegate(
+        &mut self,
+        payment_id: U64,
+        delegate: Option<AccountId>,
+    ) -> Result<()> {
+        assert_one_yocto();
+
+        let caller = env::predecessor_account_id();
+        let payment_id = payment_id.0;
+
+        self.check_receiver_payment_id(&caller, payment_id)?;
+
+        let payment_receipt = self
+            .payment_info_ledger
+            .get_mut(&payment_id)
+            .ok_or(ContractError::PaymentIdNotExist(payment_id))?
+            .into_current_mut();
+
+        // the shares of the other receivers of the split stream are claimed by themselves
+        ensure!(
+            payment_receipt.receiver == caller,
+            ContractError::NotPrimaryReceiver(caller.clone(), payment_id),
+        );
+
+        payment_receipt.claim_delegate = delegate.filter(|delegate| delegate != &caller);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constants::NANOS_IN_DAY,
+        contract::general_impl::tests::{
+            contract_acc, create_payment, get_context, issuer_acc, receiver_acc,
+        },
+        public::{PaymentRole, ProcessStatus},
+    };
+
+    use super::*;
+    use near_sdk::{
+        json_types::U128,
+        test_utils::{accounts, get_created_receipts},
+        testing_env,
+    };
+
+    fn create_approved_payment(contract: &mut PaymentContract) -> u64 {
+        let payment_id = create_payment(contract, 10, 1);
+
+        testing_env!(get_context(receiver_acc(), 0));
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        payment_id
+    }
+
+    fn set_caller_at(account_id: AccountId, block_timestamp: u64) {
+        let mut context = get_context(account_id, 1);
+        context.block_timestamp = block_timestamp;
+        testing_env!(context);
+    }
+
+    #[test]
+    fn test_delegate_claims_to_receiver() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_approved_payment(&mut contract);
+
+        testing_env!(get_context(receiver_acc(), 1));
+        contract
+            .set_claim_delegate(U64(payment_id), Some(accounts(4)))
+            .unwrap();
+        assert_eq!(
+            contract
+                .get_payment(U64(payment_id))
+                .unwrap()
+                .claim_delegate,
+            Some(accounts(4))
+        );
+
+        // the delegate can't reject the payment
+        set_caller_at(accounts(4), NANOS_IN_DAY * 3);
+        assert_eq!(
+            contract.reject_payment_receipt(U64(payment_id), PaymentRole::Receiver),
+            Err(ContractError::ReceiverAccountNotExist(accounts(4)))
+        );
+
+        assert_eq!(contract.claim_payment(U64(payment_id)), Ok(U128(3)));
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == receiver_acc()));
+        assert!(get_created_receipts()
+            .iter()
+            .all(|receipt| receipt.receiver_id != accounts(4)));
+    }
+
+    #[test]
+    fn test_third_party_claim_fails() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_approved_payment(&mut contract);
+
+        testing_env!(get_context(receiver_acc(), 1));
+        contract
+            .set_claim_delegate(U64(payment_id), Some(accounts(4)))
+            .unwrap();
+
+        set_caller_at(accounts(5), NANOS_IN_DAY * 3);
+        assert_eq!(
+            contract.claim_payment(U64(payment_id)),
+            Err(ContractError::ReceiverAccountNotExist(accounts(5)))
+        );
+
+        // only the receiver could set the delegate
+        testing_env!(get_context(issuer_acc(), 1));
+        assert_eq!(
+            contract.set_claim_delegate(U64(payment_id), Some(issuer_acc())),
+            Err(ContractError::ReceiverAccountNotExist(issuer_acc()))
+        );
+    }
+
+    #[test]
+    fn test_unset_claim_delegate() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_approved_payment(&mut contract);
+
+        testing_env!(get_context(receiver_acc(), 1));
+        contract
+            .set_claim_delegate(U64(payment_id), Some(accounts(4)))
+            .unwrap();
+        contract.set_claim_delegate(U64(payment_id), None).unwrap();
+        assert_eq!(
+            contract
+                .get_payment(U64(payment_id))
+                .unwrap()
+                .claim_delegate,
+            None
+        );
+
+        set_caller_at(accounts(4), NANOS_IN_DAY * 3);
+        assert_eq!(
+            contract.claim_payment(U64(payment_id)),
+            Err(ContractError::ReceiverAccountNotExist(accounts(4)))
+        );
+    }
+}
@@ -1,71 +1,429 @@
 use super::PaymentContract;
+use crate::constants::{BULK_OPERATION_GAS_FLOOR, GAS_FOR_TRANSFER, GAS_FOR_TRANSFER_CALLBACK};
 use crate::contract::PaymentContractExt;
-use crate::error::ContractError;
+use crate::error::{ensure, ContractError};
+use crate::events::{ContractEvent, PaymentClaimed, TransferRecovered};
+use crate::public::completed_payment::CompletionReason;
 use crate::public::payment_info::PaymentStatus;
+use crate::public::ClaimAllResult;
 use crate::Result;
-use near_sdk::{env, json_types::U64, near_bindgen};
-use near_sdk::{AccountId, Promise};
+use near_sdk::{
+    assert_one_yocto, env,
+    json_types::{U128, U64},
+    near_bindgen,
+};
+use near_sdk::{AccountId, Gas, Promise, PromiseError};
 
 #[near_bindgen]
 impl PaymentContract {
     #[handle_result]
-    fn claim_payment_impl(&mut self, caller: &AccountId, payment_id: u64) -> Result<u128> {
-        self.check_reciever_payment_id(&caller, payment_id)?;
+    pub(crate) fn claim_payment_impl(
+        &mut self,
+        caller: &AccountId,
+        payment_id: u64,
+    ) -> Result<u128> {
+        self.check_not_paused()?;
+        self.check_payment_unlocked(payment_id)?;
+        self.check_receiver_payment_id(caller, payment_id)?;
+
+        if !self.load_payment_receipt(payment_id)?.receivers.is_empty() {
+            return self.claim_receiver_share_impl(caller, payment_id);
+        }
 
         let payment_receipt = self
             .payment_info_ledger
             .get_mut(&payment_id)
-            .ok_or_else(|| ContractError::PaymentIdNotExist(payment_id))?
+            .ok_or(ContractError::PaymentIdNotExist(payment_id))?
             .into_current_mut();
 
+        let claim_time = payment_receipt.settlement_time(env::block_timestamp());
         let payment_info = &mut payment_receipt.payment_info;
 
-        let payment_status = payment_info.calculate_payment_status(payment_id)?;
+        payment_info.check_claim_interval(env::block_timestamp())?;
+
+        let payment_status = payment_info.calculate_payment_status_impl(payment_id, claim_time)?;
 
         match payment_status {
             PaymentStatus::Absent => Ok(0), // nothing is required to be done in this case
             PaymentStatus::PaymentReady(amount) => {
-                payment_info.last_payment_date = env::block_timestamp().into();
+                payment_info.register_claim(payment_id, amount, claim_time)?;
+                payment_info.last_claim_date = Some(env::block_timestamp());
+                self.record_claim(payment_id, amount);
+                self.release_locked_near(payment_id, amount)?;
 
                 Ok(amount)
             }
             PaymentStatus::FinalPayment(amount) => {
+                payment_info.register_claim(payment_id, amount, claim_time)?;
+                let claimed_amount = payment_info.lifetime_claimed_amount;
                 let issuer = payment_receipt.issuer.clone();
-                self.remove_payment_related_data(&issuer, &caller, payment_id)?;
+                let receiver = payment_receipt.receiver.clone();
+                self.record_claim(payment_id, amount);
+                self.remove_payment_related_data(
+                    &issuer,
+                    &receiver,
+                    payment_id,
+                    CompletionReason::Claimed,
+                    amount,
+                )?;
+
+                ContractEvent::PaymentClaimed(vec![PaymentClaimed {
+                    payment_id: payment_id.into(),
+                    receiver,
+                    claimed_amount: claimed_amount.into(),
+                }])
+                .emit();
 
                 Ok(amount)
             }
         }
     }
 
+    // each receiver of the split stream claims the weighted share of the periods passed since the last own claim
+    #[handle_result]
+    fn claim_receiver_share_impl(&mut self, caller: &AccountId, payment_id: u64) -> Result<u128> {
+        let payment_receipt = self
+            .payment_info_ledger
+            .get_mut(&payment_id)
+            .ok_or(ContractError::PaymentIdNotExist(payment_id))?
+            .into_current_mut();
+
+        let claim_time = payment_receipt.settlement_time(env::block_timestamp());
+        let share = payment_receipt
+            .receivers
+            .iter_mut()
+            .find(|share| &share.receiver == caller)
+            .ok_or_else(|| ContractError::ReceiverAccountNotExist(caller.clone()))?;
+
+        let share_payment_info = share.payment_info(&payment_receipt.payment_info);
+        share_payment_info.check_claim_interval(env::block_timestamp())?;
+
+        let payment_status =
+            share_payment_info.calculate_payment_status_impl(payment_id, claim_time)?;
+
+        let amount = match payment_status {
+            PaymentStatus::Absent => return Ok(0), // nothing is required to be done in this case
+            PaymentStatus::PaymentReady(amount) | PaymentStatus::FinalPayment(amount) => amount,
+        };
+
+        let payout = share
+            .weighted_payout(amount)
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        share.last_payment_date =
+            Some(share_payment_info.calculate_claim_date(payment_id, claim_time)?);
+        share.claimed_amount = share
+            .claimed_amount
+            .checked_add(amount)
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+        share.last_claim_date = Some(env::block_timestamp());
+
+        // the stream keeps the total of the weighted payouts of all its receivers
+        payment_receipt
+            .payment_info
+            .record_lifetime_claim(payment_id, payout)?;
+
+        // the payment is completed only when the last of the receivers gets the final payment
+        let all_shares_claimed = payment_receipt
+            .all_shares_claimed()
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        let claimed_amount = payment_receipt.payment_info.lifetime_claimed_amount;
+        let issuer = payment_receipt.issuer.clone();
+        let receiver = payment_receipt.receiver.clone();
+        self.record_claim(payment_id, payout);
+
+        if all_shares_claimed {
+            self.remove_payment_related_data(
+                &issuer,
+                &receiver,
+                payment_id,
+                CompletionReason::Claimed,
+                payout,
+            )?;
+
+            ContractEvent::PaymentClaimed(vec![PaymentClaimed {
+                payment_id: payment_id.into(),
+                receiver,
+                claimed_amount: claimed_amount.into(),
+            }])
+            .emit();
+        } else {
+            self.release_locked_near(payment_id, payout)?;
+        }
+
+        Ok(payout)
+    }
+
+    #[payable]
+    #[handle_result]
+    pub fn claim_payment(&mut self, payment_id: U64) -> Result<U128> {
+        assert_one_yocto();
+        self.check_prepaid_gas(Gas(GAS_FOR_TRANSFER.0 + GAS_FOR_TRANSFER_CALLBACK.0))?;
+        let caller = env::predecessor_account_id();
+
+        let payment_id = payment_id.0;
+        let receiver = self.claim_receiver(payment_id, caller);
+
+        // resolved before the claim, because the final payment is removed by it
+        let payout_account = self.payout_account(payment_id, &receiver);
+
+        let amount = self.claim_payment_impl(&receiver, payment_id)?;
+
+        self.payout_claimed_amount(payout_account, payment_id, amount)
+            .map(U128)
+    }
+
+    // claims every confirmed stream of the caller, the net amounts are paid by one transfer per payout account
+    #[payable]
+    #[handle_result]
+    pub fn claim_all(&mut self) -> Result<ClaimAllResult> {
+        assert_one_yocto();
+        let required_gas =
+            BULK_OPERATION_GAS_FLOOR.0 + GAS_FOR_TRANSFER.0 + GAS_FOR_TRANSFER_CALLBACK.0;
+        self.check_prepaid_gas(Gas(required_gas))?;
+        let caller = env::predecessor_account_id();
+
+        self.claim_all_impl(&caller, BULK_OPERATION_GAS_FLOOR)
+    }
+
+    // the ids are collected first, because the final payments are removed from the receiver ledger
+    #[handle_result]
+    pub(crate) fn claim_all_impl(
+        &mut self,
+        caller: &AccountId,
+        gas_floor: Gas,
+    ) -> Result<ClaimAllResult> {
+        let payment_ids: Vec<u64> = self
+            .receiver_ledger
+            .get(caller)
+            .map(|payment_ids| payment_ids.iter().copied().collect())
+            .unwrap_or_default();
+
+        let mut claims = vec![];
+        let mut transfers: Vec<(AccountId, u128, Vec<U64>)> = vec![];
+        let mut continuation = false;
+
+        for payment_id in payment_ids {
+            // pending and locked streams have nothing to claim right now
+            let claimable = self
+                .payment_info_ledger
+                .get(&payment_id)
+                .map(|payment_receipt| payment_receipt.into_current())
+                .is_some_and(|payment_receipt| {
+                    payment_receipt.payment_info.initiale_date.is_some() && !payment_receipt.locked
+                });
+
+            if !claimable {
+                continue;
+            }
+
+            // resolved before the claim, because the final payment is removed by it
+            let payout_account = self.payout_account(payment_id, caller);
+
+            // every payout account adds a transfer and its callback, they are scheduled after the loop
+            let payouts_number = transfers.len()
+                + usize::from(
+                    !transfers
+                        .iter()
+                        .any(|(account_id, _, _)| account_id == &payout_account),
+                );
+            let required_gas = (payouts_number as u64)
+                .saturating_mul(GAS_FOR_TRANSFER.0 + GAS_FOR_TRANSFER_CALLBACK.0)
+                .saturating_add(gas_floor.0);
+            let remaining_gas = env::prepaid_gas().0.saturating_sub(env::used_gas().0);
+
+            if remaining_gas < required_gas {
+                continuation = true;
+                break;
+            }
+
+            // the throttled streams are claimed by the later calls
+            let amount = match self.claim_payment_impl(caller, payment_id) {
+                Ok(0) | Err(ContractError::ClaimTooEarly(_)) => continue,
+                result => result?,
+            };
+
+            let amount = self.charge_payout_fee(payment_id, &payout_account, amount)?;
+            claims.push((U64(payment_id), U128(amount)));
+
+            match transfers
+                .iter_mut()
+                .find(|(account_id, _, _)| account_id == &payout_account)
+            {
+                Some((_, total_amount, payment_ids)) => {
+                    *total_amount += amount;
+                    payment_ids.push(U64(payment_id));
+                }
+                None => transfers.push((payout_account, amount, vec![U64(payment_id)])),
+            }
+        }
+
+        for (account_id, amount, payment_ids) in transfers {
+            if amount == 0 {
+                continue;
+            }
+
+            // no other operation is allowed on the included payments until the transfer is resolved
+            for payment_id in &payment_ids {
+                self.set_payment_locked(payment_id.0, true);
+            }
+
+            Promise::new(account_id.clone()).transfer(amount).then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_TRANSFER_CALLBACK)
+                    .on_claim_all_transfer(payment_ids, U128(amount), account_id),
+            );
+        }
+
+        Ok(ClaimAllResult {
+            claims,
+            continuation,
+        })
+    }
+
+    // unlike claim_payment, nothing ready to be claimed is treated as an error
+    #[payable]
     #[handle_result]
-    pub fn claim_payment(&mut self, payment_id: U64) -> Result<()> {
+    pub fn withdraw_and_continue(&mut self, payment_id: U64) -> Result<U128> {
+        assert_one_yocto();
+        self.check_prepaid_gas(Gas(GAS_FOR_TRANSFER.0 + GAS_FOR_TRANSFER_CALLBACK.0))?;
         let caller = env::predecessor_account_id();
 
-        let amount = self.claim_payment_impl(&caller, payment_id.0)?;
+        let payment_id = payment_id.0;
+
+        // resolved before the claim, because the final payment is removed by it
+        let payout_account = self.payout_account(payment_id, &caller);
+
+        let amount = self.claim_payment_impl(&caller, payment_id)?;
+
+        ensure!(amount > 0, ContractError::NothingToWithdraw(payment_id));
+
+        self.payout_claimed_amount(payout_account, payment_id, amount)
+            .map(U128)
+    }
+
+    // charges the fee and transfers the net amount to the payout account of the receiver
+    #[handle_result]
+    pub(crate) fn payout_claimed_amount(
+        &mut self,
+        payout_account: AccountId,
+        payment_id: u64,
+        amount: u128,
+    ) -> Result<u128> {
+        if amount == 0 {
+            return Ok(0);
+        }
+
+        let amount = self.charge_payout_fee(payment_id, &payout_account, amount)?;
 
         if amount > 0 {
-            // This case could not fail because we are paying back to the predecessor
-            Promise::new(caller).transfer(amount);
+            // no other operation is allowed on the payment until the transfer is resolved
+            self.set_payment_locked(payment_id, true);
+
+            // the receiver account could be deleted before the transfer is executed
+            Promise::new(payout_account.clone()).transfer(amount).then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_TRANSFER_CALLBACK)
+                    .on_claim_transfer(U64(payment_id), U128(amount), payout_account),
+            );
+        }
+
+        Ok(amount)
+    }
+
+    // the failed transfer is kept in the contract, so that the receiver could withdraw it later
+    #[private]
+    pub fn on_claim_transfer(
+        &mut self,
+        payment_id: U64,
+        amount: U128,
+        caller: AccountId,
+        #[callback_result] result: std::result::Result<(), PromiseError>,
+    ) {
+        self.set_payment_locked(payment_id.0, false);
+
+        if result.is_err() {
+            self.store_failed_transfer(caller, amount.0);
+        }
+    }
+
+    // the same as on_claim_transfer, for all the payments included in one transfer of claim_all
+    #[private]
+    pub fn on_claim_all_transfer(
+        &mut self,
+        payment_ids: Vec<U64>,
+        amount: U128,
+        caller: AccountId,
+        #[callback_result] result: std::result::Result<(), PromiseError>,
+    ) {
+        for payment_id in &payment_ids {
+            self.set_payment_locked(payment_id.0, false);
         }
 
-        Ok(())
+        if result.is_err() {
+            self.store_failed_transfer(caller, amount.0);
+        }
+    }
+
+    // the amount is stored back when the retry is failed too
+    #[payable]
+    #[handle_result]
+    pub fn withdraw_failed_transfer(&mut self) -> Result<U128> {
+        assert_one_yocto();
+        self.check_prepaid_gas(Gas(GAS_FOR_TRANSFER.0 + GAS_FOR_TRANSFER_CALLBACK.0))?;
+        let caller = env::predecessor_account_id();
+
+        let amount = self
+            .failed_transfers
+            .remove(&caller)
+            .ok_or_else(|| ContractError::NoFailedTransfer(caller.clone()))?;
+
+        Promise::new(caller.clone()).transfer(amount).then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(GAS_FOR_TRANSFER_CALLBACK)
+                .on_failed_transfer_withdrawal(caller, U128(amount)),
+        );
+
+        Ok(amount.into())
+    }
+
+    // the recovery is reported only when the retry reaches the account
+    #[private]
+    pub fn on_failed_transfer_withdrawal(
+        &mut self,
+        account_id: AccountId,
+        amount: U128,
+        #[callback_result] result: std::result::Result<(), PromiseError>,
+    ) {
+        if result.is_err() {
+            self.store_failed_transfer(account_id, amount.0);
+            return;
+        }
+
+        ContractEvent::TransferRecovered(vec![TransferRecovered { account_id, amount }]).emit();
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{
-        constants::NANOS_IN_DAY,
+        constants::{MAX_CLAIM_HISTORY_LENGTH, NANOS_IN_DAY, NANOS_IN_MINUTE},
         contract::general_impl::tests::{
-            check_all_data_removed, contract_acc, create_payment, get_context, receiver_acc,
-            set_block_timestamp,
+            check_all_account_data_removed, check_all_data_removed, contract_acc, create_payment,
+            get_context, issuer_acc, receiver_acc, set_block_timestamp,
+        },
+        public::{
+            claim_history::ClaimRecord, payment_info::VestingMode, CreatePaymentOptions,
+            PaymentRole, ProcessStatus,
         },
-        public::ProcessStatus,
     };
 
     use super::*;
-    use near_sdk::testing_env;
+    use near_sdk::{
+        test_utils::{accounts, get_logs},
+        testing_env,
+    };
 
     #[test]
     fn test_claim_payment_absent() {
@@ -186,7 +544,1067 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), 10);
 
+        // the lifetime claimed amount is reported by the final claim
+        assert_eq!(
+            get_logs().last().unwrap(),
+            &format!(
+                r#"EVENT_JSON:{{"standard":"near_payment_receiver","version":"1.0.0","event":"payment_claimed","data":[{{"payment_id":"{}","receiver":"{}","claimed_amount":"10"}}]}}"#,
+                payment_id,
+                receiver_acc()
+            )
+        );
+
+        // check that the payment has been removed from all storages
+        check_all_account_data_removed(&contract, payment_id);
+    }
+
+    #[test]
+    fn test_claim_payment_returns_claimed_amount() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // create a payment
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        // set caller to receiver
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+
+        // approve the payment
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        // nothing is claimed while the first period is not finished
+        let mut context = get_context(receiver_acc(), 1);
+        context.block_timestamp = NANOS_IN_DAY / 2;
+        testing_env!(context.clone());
+        assert_eq!(contract.claim_payment(U64(payment_id)), Ok(U128(0)));
+
+        // we set to the third day(period is one day, period_amount is 1token, so we will claim 3 tokens)
+        let mut context = get_context(receiver_acc(), 1);
+        context.block_timestamp = NANOS_IN_DAY * 3 + 1;
+        testing_env!(context.clone());
+        assert_eq!(contract.claim_payment(U64(payment_id)), Ok(U128(3)));
+    }
+
+    #[test]
+    fn test_claim_payment_with_fee() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        contract.set_fee_config(50, accounts(3)).unwrap();
+
+        // create a payment
+        let payment_id = create_payment(&mut contract, 100_000, 10_000);
+
+        // set caller to receiver
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+
+        // approve the payment
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        // 3 periods are claimed, 0.5% fee is deducted
+        let mut context = get_context(receiver_acc(), 1);
+        context.block_timestamp = NANOS_IN_DAY * 3 + 1;
+        testing_env!(context.clone());
+        assert_eq!(contract.claim_payment(U64(payment_id)), Ok(U128(29_850)));
+        assert_eq!(contract.get_collected_fees(), U128(150));
+
+        assert_eq!(
+            get_logs(),
+            vec![format!(
+                r#"EVENT_JSON:{{"standard":"near_payment_receiver","version":"1.0.0","event":"receiver_payout","data":[{{"payment_id":"{}","receiver":"{}","gross":"30000","fee":"150","net":"29850"}}]}}"#,
+                payment_id,
+                receiver_acc()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_claim_payment_fee_rounding() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        contract.set_fee_config(1000, accounts(3)).unwrap();
+
+        let payment_id = create_payment(&mut contract, 1000, 333);
+
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        // 10% of 999 is 99.9, the fee is floored in favor of the receiver
+        let mut context = get_context(receiver_acc(), 1);
+        context.block_timestamp = NANOS_IN_DAY * 3 + 1;
+        testing_env!(context.clone());
+        assert_eq!(contract.claim_payment(U64(payment_id)), Ok(U128(900)));
+        assert_eq!(contract.get_collected_fees(), U128(99));
+    }
+
+    #[test]
+    fn test_claim_payment_partial_final() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // 100 tokens are streamed by 30 tokens per day, the last day pays 10 tokens
+        let payment_id = create_payment(&mut contract, 100, 30);
+
+        // set caller to receiver
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+
+        // approve the payment
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        set_block_timestamp(NANOS_IN_DAY * 3 + 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(90)
+        );
+
+        set_block_timestamp(NANOS_IN_DAY * 4 + 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(10)
+        );
+
+        // check that the payment has been removed from all storages
+        check_all_account_data_removed(&contract, payment_id);
+    }
+
+    #[test]
+    fn test_claim_linear_payment() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // 100 tokens are vested continuously during 10 days
+        testing_env!(get_context(issuer_acc(), 100));
+        let payment_id = contract
+            .create_payment(
+                U64(1),
+                U128(10),
+                receiver_acc(),
+                Some(CreatePaymentOptions {
+                    vesting_mode: VestingMode::Linear,
+                    ..Default::default()
+                }),
+            )
+            .unwrap()
+            .0;
+
+        // set caller to receiver
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+
+        // approve the payment
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        // the middle of the third day
+        set_block_timestamp(NANOS_IN_DAY * 5 / 2 + 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(25)
+        );
+        assert_eq!(
+            contract
+                .get_payment(U64(payment_id))
+                .unwrap()
+                .payment_info
+                .claimed_amount,
+            U128(25)
+        );
+
+        // one nanosecond later less than one token is vested
+        set_block_timestamp(NANOS_IN_DAY * 5 / 2 + 2);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(0)
+        );
+
+        set_block_timestamp(NANOS_IN_DAY * 10 + 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(75)
+        );
+
         // check that the payment has been removed from all storages
-        check_all_data_removed(&contract, payment_id);
+        check_all_account_data_removed(&contract, payment_id);
+    }
+
+    #[test]
+    fn test_claim_history() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // create a payment
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        // set caller to receiver
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+
+        // approve the payment
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        set_block_timestamp(NANOS_IN_DAY * 3 + 1);
+        contract
+            .claim_payment_impl(&receiver_acc(), payment_id)
+            .unwrap();
+
+        // nothing is recorded when nothing is claimed
+        contract
+            .claim_payment_impl(&receiver_acc(), payment_id)
+            .unwrap();
+
+        set_block_timestamp(NANOS_IN_DAY * 5 + 1);
+        contract
+            .claim_payment_impl(&receiver_acc(), payment_id)
+            .unwrap();
+
+        assert_eq!(
+            contract.get_claim_history(U64(payment_id), None, None),
+            vec![
+                ClaimRecord {
+                    timestamp: NANOS_IN_DAY * 3 + 1,
+                    amount: 3,
+                },
+                ClaimRecord {
+                    timestamp: NANOS_IN_DAY * 5 + 1,
+                    amount: 2,
+                },
+            ]
+        );
+        assert_eq!(
+            contract.get_claim_history(U64(payment_id), Some(U64(1)), Some(U64(1))),
+            vec![ClaimRecord {
+                timestamp: NANOS_IN_DAY * 5 + 1,
+                amount: 2,
+            }]
+        );
+
+        // the history is removed together with the payment
+        set_block_timestamp(NANOS_IN_DAY * 10 + 1);
+        contract
+            .claim_payment_impl(&receiver_acc(), payment_id)
+            .unwrap();
+        assert!(contract
+            .get_claim_history(U64(payment_id), None, None)
+            .is_empty());
+        assert!(!contract.claim_history.contains_key(&payment_id));
+    }
+
+    #[test]
+    fn test_claim_history_compaction() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // one token is streamed every minute
+        testing_env!(get_context(issuer_acc(), 1000));
+        let payment_id = contract
+            .create_payment_with_period_secs(U64(60), U128(1), receiver_acc(), None)
+            .unwrap()
+            .0;
+
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        // claim every minute until the history is full and one more time
+        for minute in 1..=u64::from(MAX_CLAIM_HISTORY_LENGTH) + 1 {
+            set_block_timestamp(NANOS_IN_MINUTE * minute + 1);
+            assert_eq!(
+                contract.claim_payment_impl(&receiver_acc(), payment_id),
+                Ok(1)
+            );
+        }
+
+        let history = contract.get_claim_history(U64(payment_id), None, Some(U64(1000)));
+        assert_eq!(history.len() as u32, MAX_CLAIM_HISTORY_LENGTH / 2 + 1);
+
+        // the compacted history keeps the whole claimed amount
+        assert_eq!(
+            history.iter().map(|record| record.amount).sum::<u128>(),
+            u128::from(MAX_CLAIM_HISTORY_LENGTH) + 1
+        );
+        assert_eq!(
+            history[0],
+            ClaimRecord {
+                timestamp: NANOS_IN_MINUTE * 2 + 1,
+                amount: 2,
+            }
+        );
+    }
+
+    #[test]
+    fn test_claim_split_payment() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // create a payment split 70/30 between two receivers
+        testing_env!(get_context(issuer_acc(), 100));
+        let payment_id = contract
+            .create_split_payment(
+                U64(1),
+                U128(10),
+                vec![(receiver_acc(), 7000), (accounts(3), 3000)],
+                None,
+            )
+            .unwrap()
+            .0;
+
+        // only the primary receiver approves the payment
+        let mut context = get_context(accounts(3), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+        assert_eq!(
+            contract.process_pending_payment(ProcessStatus::Approve(U64(payment_id))),
+            Err(ContractError::NotPrimaryReceiver(accounts(3), payment_id))
+        );
+
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        // both receivers claim their shares of 3 periods at the same timestamp
+        set_block_timestamp(NANOS_IN_DAY * 3 + 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(21)
+        );
+        assert_eq!(contract.claim_payment_impl(&accounts(3), payment_id), Ok(9));
+
+        // the repeated claim at the same timestamp gives nothing
+        assert_eq!(contract.claim_payment_impl(&accounts(3), payment_id), Ok(0));
+
+        // the payment is kept until every receiver gets the final payment
+        set_block_timestamp(NANOS_IN_DAY * 10 + 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(49)
+        );
+        assert!(contract.payment_info_ledger.get(&payment_id).is_some());
+        assert!(get_logs().is_empty());
+
+        assert_eq!(
+            contract.claim_payment_impl(&accounts(3), payment_id),
+            Ok(21)
+        );
+
+        // the closed stream reports the payouts of all its receivers
+        assert_eq!(
+            get_logs().last().unwrap(),
+            &format!(
+                r#"EVENT_JSON:{{"standard":"near_payment_receiver","version":"1.0.0","event":"payment_claimed","data":[{{"payment_id":"{}","receiver":"{}","claimed_amount":"100"}}]}}"#,
+                payment_id,
+                receiver_acc()
+            )
+        );
+
+        // check that the payment has been removed from all storages
+        check_all_account_data_removed(&contract, payment_id);
+        assert!(contract.receiver_ledger.get(&accounts(3)).is_none());
+    }
+
+    #[test]
+    fn test_failed_claim_transfer() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // successful transfer is not stored
+        contract.on_claim_transfer(U64(1), U128(10), receiver_acc(), Ok(()));
+        assert_eq!(
+            contract.get_failed_transfer_balance(receiver_acc()),
+            U128(0)
+        );
+
+        // failed transfers are accumulated
+        contract.on_claim_transfer(U64(1), U128(10), receiver_acc(), Err(PromiseError::Failed));
+        contract.on_claim_transfer(U64(2), U128(5), receiver_acc(), Err(PromiseError::Failed));
+        assert_eq!(
+            contract.get_failed_transfer_balance(receiver_acc()),
+            U128(15)
+        );
+
+        // the account withdraws all the failed transfers at once
+        testing_env!(get_context(receiver_acc(), 1));
+        assert_eq!(contract.withdraw_failed_transfer(), Ok(U128(15)));
+        assert_eq!(
+            contract.get_failed_transfer_balance(receiver_acc()),
+            U128(0)
+        );
+        assert_eq!(
+            contract.withdraw_failed_transfer(),
+            Err(ContractError::NoFailedTransfer(receiver_acc()))
+        );
+
+        // the failed retry is stored back
+        contract.on_failed_transfer_withdrawal(receiver_acc(), U128(15), Err(PromiseError::Failed));
+        assert_eq!(
+            contract.get_failed_transfer_balance(receiver_acc()),
+            U128(15)
+        );
+    }
+
+    #[test]
+    fn test_failed_transfer_events() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        contract.on_claim_transfer(U64(1), U128(10), receiver_acc(), Ok(()));
+        assert!(get_logs().is_empty());
+
+        contract.on_claim_transfer(U64(1), U128(10), receiver_acc(), Err(PromiseError::Failed));
+        assert_eq!(
+            get_logs().last().unwrap(),
+            &format!(
+                r#"EVENT_JSON:{{"standard":"near_payment_receiver","version":"1.0.0","event":"transfer_failed","data":[{{"account_id":"{}","amount":"10"}}]}}"#,
+                receiver_acc()
+            )
+        );
+
+        // nothing is reported until the outcome of the retry is known
+        testing_env!(get_context(receiver_acc(), 1));
+        assert_eq!(contract.withdraw_failed_transfer(), Ok(U128(10)));
+        assert!(get_logs().is_empty());
+
+        // the failed retry is reported again
+        testing_env!(get_context(contract_acc(), 0));
+        contract.on_failed_transfer_withdrawal(receiver_acc(), U128(10), Err(PromiseError::Failed));
+        assert_eq!(
+            get_logs(),
+            vec![format!(
+                r#"EVENT_JSON:{{"standard":"near_payment_receiver","version":"1.0.0","event":"transfer_failed","data":[{{"account_id":"{}","amount":"10"}}]}}"#,
+                receiver_acc()
+            )]
+        );
+
+        // the successful retry is reported as recovered
+        testing_env!(get_context(receiver_acc(), 1));
+        assert_eq!(contract.withdraw_failed_transfer(), Ok(U128(10)));
+        testing_env!(get_context(contract_acc(), 0));
+        contract.on_failed_transfer_withdrawal(receiver_acc(), U128(10), Ok(()));
+        assert_eq!(
+            get_logs(),
+            vec![format!(
+                r#"EVENT_JSON:{{"standard":"near_payment_receiver","version":"1.0.0","event":"transfer_recovered","data":[{{"account_id":"{}","amount":"10"}}]}}"#,
+                receiver_acc()
+            )]
+        );
+        assert_eq!(
+            contract.get_failed_transfer_balance(receiver_acc()),
+            U128(0)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_withdraw_failed_transfer_without_yocto() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        contract.on_claim_transfer(U64(1), U128(10), receiver_acc(), Err(PromiseError::Failed));
+
+        testing_env!(get_context(receiver_acc(), 0));
+        let _ = contract.withdraw_failed_transfer();
+    }
+
+    #[test]
+    fn test_withdraw_and_continue() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // create a payment
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        // set caller to receiver
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+
+        // approve the payment
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        // nothing is ready during the first period
+        let mut context = get_context(receiver_acc(), 1);
+        context.block_timestamp = NANOS_IN_DAY / 2;
+        testing_env!(context.clone());
+        assert_eq!(
+            contract.withdraw_and_continue(U64(payment_id)),
+            Err(ContractError::NothingToWithdraw(payment_id))
+        );
+
+        // the ready amount is withdrawn and the stream goes on
+        let mut context = get_context(receiver_acc(), 1);
+        context.block_timestamp = NANOS_IN_DAY * 3 + 1;
+        testing_env!(context.clone());
+        assert_eq!(contract.withdraw_and_continue(U64(payment_id)), Ok(U128(3)));
+        assert!(contract.is_payment_active(U64(payment_id)));
+        contract.on_claim_transfer(U64(payment_id), U128(3), receiver_acc(), Ok(()));
+
+        // the repeated withdrawal at the same time is refused
+        assert_eq!(
+            contract.withdraw_and_continue(U64(payment_id)),
+            Err(ContractError::NothingToWithdraw(payment_id))
+        );
+
+        // the state is not changed when the gas is not enough for the transfer and its callback
+        context.block_timestamp = NANOS_IN_DAY * 5 + 1;
+        context.prepaid_gas = Gas(GAS_FOR_TRANSFER.0);
+        testing_env!(context);
+        assert_eq!(
+            contract.withdraw_and_continue(U64(payment_id)),
+            Err(ContractError::InsufficientGas(
+                GAS_FOR_TRANSFER.0 + GAS_FOR_TRANSFER_CALLBACK.0,
+                GAS_FOR_TRANSFER.0
+            ))
+        );
+        assert_eq!(
+            contract
+                .get_payment_progress(U64(payment_id))
+                .unwrap()
+                .amount_claimed,
+            U128(3)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_withdraw_and_continue_without_yocto() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        testing_env!(get_context(receiver_acc(), 0));
+        let _ = contract.withdraw_and_continue(U64(payment_id));
+    }
+
+    #[test]
+    fn test_payment_locked_during_transfer() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // create a payment
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        // set caller to receiver
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+
+        // approve the payment
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        let mut context = get_context(receiver_acc(), 1);
+        context.block_timestamp = NANOS_IN_DAY * 3 + 1;
+        testing_env!(context.clone());
+        assert_eq!(contract.claim_payment(U64(payment_id)), Ok(U128(3)));
+
+        // the second call is refused while the transfer is in flight
+        let mut context = get_context(receiver_acc(), 1);
+        context.block_timestamp = NANOS_IN_DAY * 4 + 1;
+        testing_env!(context.clone());
+        assert_eq!(
+            contract.claim_payment(U64(payment_id)),
+            Err(ContractError::PaymentLocked(payment_id))
+        );
+        assert_eq!(
+            contract.reject_payment_receipt(U64(payment_id), PaymentRole::Receiver),
+            Err(ContractError::PaymentLocked(payment_id))
+        );
+
+        // the failed transfer unlocks the payment as well
+        contract.on_claim_transfer(
+            U64(payment_id),
+            U128(3),
+            receiver_acc(),
+            Err(PromiseError::Failed),
+        );
+        assert_eq!(contract.claim_payment(U64(payment_id)), Ok(U128(1)));
+    }
+
+    #[test]
+    fn test_claim_all() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // two daily streams, a weekly stream and a pending one
+        let first_payment_id = create_payment(&mut contract, 10, 1);
+        let final_payment_id = create_payment(&mut contract, 2, 1);
+        testing_env!(get_context(issuer_acc(), 10));
+        let absent_payment_id = contract
+            .create_payment(U64(7), U128(5), receiver_acc(), None)
+            .unwrap()
+            .0;
+        let pending_payment_id = create_payment(&mut contract, 10, 1);
+
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+        for payment_id in [first_payment_id, final_payment_id, absent_payment_id] {
+            contract
+                .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+                .unwrap();
+        }
+
+        let mut context = get_context(receiver_acc(), 1);
+        context.block_timestamp = NANOS_IN_DAY * 3 + 1;
+        testing_env!(context.clone());
+
+        let mut result = contract.claim_all().unwrap();
+        result.claims.sort_by_key(|(payment_id, _)| payment_id.0);
+        assert_eq!(
+            result,
+            ClaimAllResult {
+                claims: vec![
+                    (U64(first_payment_id), U128(3)),
+                    (U64(final_payment_id), U128(2))
+                ],
+                continuation: false,
+            }
+        );
+
+        // the final payment is cleaned up, the rest of the streams go on
+        check_all_data_removed(&contract, final_payment_id);
+        assert!(contract.is_payment_active(U64(absent_payment_id)));
+        assert!(contract.payment_exists(U64(pending_payment_id)));
+    }
+
+    #[test]
+    fn test_claim_all_locks_payments_during_transfer() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let first_payment_id = create_payment(&mut contract, 10, 1);
+        let second_payment_id = create_payment(&mut contract, 10, 1);
+
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+        for payment_id in [first_payment_id, second_payment_id] {
+            contract
+                .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+                .unwrap();
+        }
+
+        let mut context = get_context(receiver_acc(), 1);
+        context.block_timestamp = NANOS_IN_DAY * 3 + 1;
+        testing_env!(context.clone());
+        assert_eq!(contract.claim_all().unwrap().claims.len(), 2);
+
+        // both payments are refused while their transfer is in flight
+        for payment_id in [first_payment_id, second_payment_id] {
+            assert_eq!(
+                contract.claim_payment(U64(payment_id)),
+                Err(ContractError::PaymentLocked(payment_id))
+            );
+        }
+
+        // the next claim_all skips the locked payments
+        let mut context = get_context(receiver_acc(), 1);
+        context.block_timestamp = NANOS_IN_DAY * 4 + 1;
+        testing_env!(context.clone());
+        assert!(contract.claim_all().unwrap().claims.is_empty());
+
+        // the failed transfer unlocks all of them and is stored for the receiver
+        contract.on_claim_all_transfer(
+            vec![U64(first_payment_id), U64(second_payment_id)],
+            U128(6),
+            receiver_acc(),
+            Err(PromiseError::Failed),
+        );
+        assert_eq!(
+            contract.get_failed_transfer_balance(receiver_acc()),
+            U128(6)
+        );
+        assert_eq!(contract.claim_payment(U64(first_payment_id)), Ok(U128(1)));
+        assert_eq!(contract.claim_payment(U64(second_payment_id)), Ok(U128(1)));
+    }
+
+    #[test]
+    fn test_claim_all_continuation() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        set_block_timestamp(NANOS_IN_DAY * 3 + 1);
+
+        // the floor above the prepaid gas stops before the first stream
+        let gas_floor = Gas(env::prepaid_gas().0 + 1);
+        assert_eq!(
+            contract.claim_all_impl(&receiver_acc(), gas_floor),
+            Ok(ClaimAllResult {
+                claims: vec![],
+                continuation: true,
+            })
+        );
+
+        // the next call with enough gas claims the stream
+        assert_eq!(
+            contract.claim_all_impl(&receiver_acc(), Gas(0)),
+            Ok(ClaimAllResult {
+                claims: vec![(U64(payment_id), U128(3))],
+                continuation: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_claim_all_reserves_gas_per_payout_account() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let first_payment_id = create_payment(&mut contract, 10, 1);
+        let second_payment_id = create_payment(&mut contract, 10, 1);
+
+        let mut context = get_context(receiver_acc(), 1);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+        for payment_id in [first_payment_id, second_payment_id] {
+            contract
+                .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+                .unwrap();
+        }
+
+        // the second stream is paid out to another account, so it needs its own transfer
+        contract
+            .set_payout_address(U64(second_payment_id), accounts(4))
+            .unwrap();
+
+        // the gas is enough for one transfer with its callback, but not for two
+        let mut context = get_context(receiver_acc(), 1);
+        context.block_timestamp = NANOS_IN_DAY * 3 + 1;
+        context.prepaid_gas = Gas((GAS_FOR_TRANSFER.0 + GAS_FOR_TRANSFER_CALLBACK.0) * 3 / 2);
+        testing_env!(context.clone());
+
+        let result = contract.claim_all_impl(&receiver_acc(), Gas(0)).unwrap();
+        assert_eq!(result.claims.len(), 1);
+        assert!(result.continuation);
+
+        // the rest is claimed by the next call
+        context.prepaid_gas = Gas((GAS_FOR_TRANSFER.0 + GAS_FOR_TRANSFER_CALLBACK.0) * 3);
+        testing_env!(context);
+
+        let result = contract.claim_all_impl(&receiver_acc(), Gas(0)).unwrap();
+        assert_eq!(result.claims.len(), 1);
+        assert!(!result.continuation);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_claim_all_without_yocto() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        testing_env!(get_context(receiver_acc(), 0));
+        let _ = contract.claim_all();
+    }
+
+    #[test]
+    fn test_claim_payment_min_claim_interval() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // the receiver claims not more often than once in two days
+        testing_env!(get_context(issuer_acc(), 10));
+        let payment_id = contract
+            .create_payment(
+                U64(1),
+                U128(1),
+                receiver_acc(),
+                Some(CreatePaymentOptions {
+                    min_claim_interval: Some(U64(NANOS_IN_DAY * 2)),
+                    ..Default::default()
+                }),
+            )
+            .unwrap()
+            .0;
+
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        set_block_timestamp(NANOS_IN_DAY + 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Err(ContractError::ClaimTooEarly(86400))
+        );
+
+        set_block_timestamp(NANOS_IN_DAY * 2 + 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(2)
+        );
+
+        // the interval is counted from the last claim
+        set_block_timestamp(NANOS_IN_DAY * 3 + 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Err(ContractError::ClaimTooEarly(86400))
+        );
+
+        set_block_timestamp(NANOS_IN_DAY * 4 + 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(2)
+        );
+    }
+
+    #[test]
+    fn test_claim_payment_min_claim_interval_from_claim_time() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        testing_env!(get_context(issuer_acc(), 10));
+        let payment_id = contract
+            .create_payment(
+                U64(1),
+                U128(1),
+                receiver_acc(),
+                Some(CreatePaymentOptions {
+                    min_claim_interval: Some(U64(NANOS_IN_DAY * 2)),
+                    ..Default::default()
+                }),
+            )
+            .unwrap()
+            .0;
+
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        // the claim in the middle of the period is snapped to its start
+        set_block_timestamp(NANOS_IN_DAY * 2 + NANOS_IN_DAY / 2 + 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(2)
+        );
+
+        // the interval is counted from the actual time of the claim
+        set_block_timestamp(NANOS_IN_DAY * 4 + 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Err(ContractError::ClaimTooEarly(43200))
+        );
+
+        set_block_timestamp(NANOS_IN_DAY * 4 + NANOS_IN_DAY / 2 + 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(2)
+        );
+    }
+
+    #[test]
+    fn test_claim_split_payment_min_claim_interval() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        testing_env!(get_context(issuer_acc(), 100));
+        let payment_id = contract
+            .create_split_payment(
+                U64(1),
+                U128(10),
+                vec![(receiver_acc(), 7000), (accounts(3), 3000)],
+                Some(CreatePaymentOptions {
+                    min_claim_interval: Some(U64(NANOS_IN_DAY * 2)),
+                    ..Default::default()
+                }),
+            )
+            .unwrap()
+            .0;
+
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        set_block_timestamp(NANOS_IN_DAY * 2 + NANOS_IN_DAY / 2 + 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(14)
+        );
+
+        // the interval of each share is counted from the actual time of its own claim
+        set_block_timestamp(NANOS_IN_DAY * 4 + 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Err(ContractError::ClaimTooEarly(43200))
+        );
+        assert_eq!(
+            contract.claim_payment_impl(&accounts(3), payment_id),
+            Ok(12)
+        );
+
+        set_block_timestamp(NANOS_IN_DAY * 4 + NANOS_IN_DAY / 2 + 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(14)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_claim_payment_without_yocto() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        testing_env!(get_context(receiver_acc(), 0));
+        let _ = contract.claim_payment(U64(1));
+    }
+
+    #[test]
+    fn test_claim_payment_insufficient_gas() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        let mut context = get_context(receiver_acc(), 1);
+        context.prepaid_gas = Gas(GAS_FOR_TRANSFER.0);
+        testing_env!(context);
+
+        assert_eq!(
+            contract.claim_payment(U64(payment_id)),
+            Err(ContractError::InsufficientGas(
+                GAS_FOR_TRANSFER.0 + GAS_FOR_TRANSFER_CALLBACK.0,
+                GAS_FOR_TRANSFER.0
+            ))
+        );
+        assert!(contract.payment_info_ledger.get(&payment_id).is_some());
     }
 }
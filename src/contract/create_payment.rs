@@ -1,130 +1,721 @@
 use super::PaymentContract;
-use crate::constants::NANOS_IN_DAY;
+use crate::constants::{
+    MAX_CLIENT_NONCE_LENGTH, MAX_MEMO_LENGTH, MAX_METADATA_LENGTH, MAX_PERIOD_DAYS, NANOS_IN_DAY,
+    NANOS_IN_SECOND, TOTAL_WEIGHT_BPS,
+};
+use crate::contract::general_impl::{insert_ledger_payment_id, remove_ledger_payment_id};
 use crate::contract::PaymentContractExt;
-use crate::public::payment_info::PaymentInfo;
+use crate::events::{ContractEvent, PendingReceiverChanged};
+use crate::public::payment_info::{PaymentInfo, PeriodUnit, VestingMode};
 use crate::public::payment_receipt::PaymentReceipt;
-use crate::public::StorageKey;
+use crate::public::{CreatePaymentOptions, StorageKey};
 use crate::{
-    error::{require, ContractError},
+    error::{ensure, ContractError},
     Result,
 };
 use near_sdk::{
-    env,
+    assert_one_yocto, env,
     json_types::{U128, U64},
-    near_bindgen,
-    store::UnorderedSet,
-    AccountId,
+    near_bindgen, AccountId,
 };
 
+// optional settings of the new payment shared by the create methods
+struct PaymentOptions {
+    memo: Option<String>,
+    approval_deadline: Option<u64>,
+    vesting_mode: VestingMode,
+    approval_required: bool,
+    client_nonce: Option<String>,
+    min_claim_interval: Option<u64>,
+    metadata: Option<String>,
+    // fails the creation when the issuer has the same stream to the same receiver
+    require_unique: bool,
+}
+
+impl PaymentOptions {
+    fn from_create_options(options: CreatePaymentOptions, approval_required: bool) -> Self {
+        PaymentOptions {
+            memo: options.memo,
+            approval_deadline: options.approval_deadline.map(|value| value.0),
+            vesting_mode: options.vesting_mode,
+            approval_required,
+            client_nonce: options.client_nonce,
+            min_claim_interval: options.min_claim_interval.map(|value| value.0),
+            metadata: options.metadata,
+            require_unique: options.require_unique,
+        }
+    }
+}
+
 #[near_bindgen]
 impl PaymentContract {
+    // the period duration is counted in options.period_unit, days by default
     #[payable]
     #[handle_result]
     pub fn create_payment(
         &mut self,
-        days_period_duration: U64,
+        period_duration_units: U64,
         payment_amount: U128,
         receiver: AccountId,
-    ) -> Result<u64> {
-        let caller = env::predecessor_account_id();
-        let attached_deposit = env::attached_deposit();
+        options: Option<CreatePaymentOptions>,
+    ) -> Result<U64> {
+        let options = options.unwrap_or_default();
+
+        self.create_payment_impl(
+            period_duration_units.0,
+            options.period_unit.unwrap_or_default(),
+            payment_amount.0,
+            vec![(receiver, TOTAL_WEIGHT_BPS)],
+            PaymentOptions::from_create_options(options, true),
+        )
+        .map(U64)
+    }
+
+    #[payable]
+    #[handle_result]
+    pub fn create_payment_with_period_secs(
+        &mut self,
+        period_duration_secs: U64,
+        payment_amount: U128,
+        receiver: AccountId,
+        options: Option<CreatePaymentOptions>,
+    ) -> Result<U64> {
+        let options = options.unwrap_or_default();
+
+        // the period is always set in seconds
+        ensure!(
+            options.period_unit.is_none(),
+            ContractError::CreateOptionNotSupported("period_unit".to_string()),
+        );
+
+        self.create_payment_impl(
+            period_duration_secs.0,
+            PeriodUnit::Seconds,
+            payment_amount.0,
+            vec![(receiver, TOTAL_WEIGHT_BPS)],
+            PaymentOptions::from_create_options(options, true),
+        )
+        .map(U64)
+    }
+
+    // the stream is split between the receivers according to their weights in bps
+    #[payable]
+    #[handle_result]
+    pub fn create_split_payment(
+        &mut self,
+        period_duration_units: U64,
+        payment_amount: U128,
+        receivers: Vec<(AccountId, u16)>,
+        options: Option<CreatePaymentOptions>,
+    ) -> Result<U64> {
+        let options = options.unwrap_or_default();
+
+        self.create_payment_impl(
+            period_duration_units.0,
+            options.period_unit.unwrap_or_default(),
+            payment_amount.0,
+            receivers,
+            PaymentOptions::from_create_options(options, true),
+        )
+        .map(U64)
+    }
+
+    // the stream is started right away, so that the receiver claims it without the approval step
+    #[payable]
+    #[handle_result]
+    pub fn create_payment_without_approval(
+        &mut self,
+        period_duration_units: U64,
+        payment_amount: U128,
+        receiver: AccountId,
+        options: Option<CreatePaymentOptions>,
+    ) -> Result<U64> {
+        let options = options.unwrap_or_default();
+
+        // there is nothing to approve until the deadline
+        ensure!(
+            options.approval_deadline.is_none(),
+            ContractError::CreateOptionNotSupported("approval_deadline".to_string()),
+        );
 
-        let days_period_duration = days_period_duration.0;
-        let payment_amount = payment_amount.0;
+        self.create_payment_impl(
+            period_duration_units.0,
+            options.period_unit.unwrap_or_default(),
+            payment_amount.0,
+            vec![(receiver, TOTAL_WEIGHT_BPS)],
+            PaymentOptions::from_create_options(options, false),
+        )
+        .map(U64)
+    }
 
-        require(
-            attached_deposit > 0 && payment_amount > 0 && days_period_duration > 0,
+    // validates the amount and the period related params of the new payment
+    #[handle_result]
+    pub(crate) fn build_payment_info(
+        &self,
+        attached_deposit: u128,
+        payment_amount: u128,
+        period_duration_units: u64,
+        period_unit: PeriodUnit,
+    ) -> Result<PaymentInfo> {
+        ensure!(
+            attached_deposit > 0 && payment_amount > 0 && period_duration_units > 0,
             ContractError::ZeroPaymentCreationParams(
                 attached_deposit,
                 payment_amount,
-                days_period_duration,
+                period_duration_units,
             ),
-        )?;
+        );
 
-        require(
-            attached_deposit
-                .checked_rem(payment_amount)
-                .filter(|res| *res == 0)
-                .is_some(),
+        ensure!(
+            payment_amount >= self.min_payment_amount,
+            ContractError::PaymentAmountBelowMinimum(payment_amount, self.min_payment_amount),
+        );
+
+        // at least one period payment should be made, the last period pays the rest of the total amount
+        ensure!(
+            attached_deposit >= payment_amount,
             ContractError::IncorrectAmountRelatedParams(attached_deposit, payment_amount),
-        )?; // this check will guarantee that at list one period payment could be made
-            // also it checks that payment amount could be an equal part of the total amount
+        );
+
+        let max_period_units = MAX_PERIOD_DAYS * NANOS_IN_DAY / period_unit.nanos();
+        ensure!(
+            period_duration_units <= max_period_units,
+            ContractError::PeriodDurationTooLong(period_duration_units, max_period_units),
+        );
 
         let payment_id = self.payment_id_counter;
-        self.payment_id_counter += 1;
-
-        let issuer_id_store = match self.issuer_ledger.get_mut(&caller) {
-            Some(value) => value,
-            None => {
-                self.issuer_ledger.insert(
-                    caller.clone(),
-                    UnorderedSet::new(StorageKey::IssuerLedgerRecord {
-                        user: caller.clone(),
-                    }),
-                );
 
-                self.issuer_ledger.get_mut(&caller).unwrap()
-            }
-        };
+        let period_duration = period_duration_units
+            .checked_mul(period_unit.nanos())
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        ensure!(
+            period_duration >= self.min_period_duration,
+            ContractError::PeriodDurationTooShort(
+                period_duration / NANOS_IN_SECOND,
+                self.min_period_duration / NANOS_IN_SECOND,
+            ),
+        );
+
+        let payment_info = PaymentInfo::new(period_duration, payment_amount, attached_deposit);
+
+        // the stream should be able to reach its end date, otherwise the final payment could never be calculated
+        ensure!(
+            payment_info
+                .calculate_end_date(env::block_timestamp())
+                .is_some(),
+            ContractError::PaymentEndDateOverflow(
+                period_duration,
+                attached_deposit.div_ceil(payment_amount),
+            ),
+        );
+
+        Ok(payment_info)
+    }
+
+    // the description of the payment is its memo, None removes it
+    #[payable]
+    #[handle_result]
+    pub fn update_payment_description(
+        &mut self,
+        payment_id: U64,
+        description: Option<String>,
+    ) -> Result<()> {
+        assert_one_yocto();
+        self.check_not_paused()?;
 
-        require(
-            issuer_id_store.insert(payment_id),
+        let issuer = env::predecessor_account_id();
+        let payment_id = payment_id.0;
+
+        self.check_payment_unlocked(payment_id)?;
+        self.check_issue_payment_id(&issuer, payment_id)?;
+
+        if let Some(description) = &description {
+            ensure!(
+                description.len() <= MAX_MEMO_LENGTH,
+                ContractError::MemoTooLong(description.len(), MAX_MEMO_LENGTH),
+            );
+        }
+
+        self.payment_info_ledger
+            .get_mut(&payment_id)
+            .ok_or(ContractError::PaymentIdNotExist(payment_id))?
+            .into_current_mut()
+            .memo = description;
+
+        Ok(())
+    }
+
+    // fixes the mistyped receiver, allowed only until the payment is approved.
+    // the settings made by the old receiver are dropped together with it
+    #[payable]
+    #[handle_result]
+    pub fn change_pending_receiver(
+        &mut self,
+        payment_id: U64,
+        new_receiver: AccountId,
+    ) -> Result<()> {
+        assert_one_yocto();
+        self.check_not_paused()?;
+
+        let issuer = env::predecessor_account_id();
+        let payment_id = payment_id.0;
+
+        self.check_payment_unlocked(payment_id)?;
+        self.check_issue_payment_id(&issuer, payment_id)?;
+
+        let payment_receipt = self.load_payment_receipt(payment_id)?;
+        let old_receiver = payment_receipt.receiver.clone();
+
+        ensure!(
+            payment_receipt.payment_info.initiale_date.is_none(),
+            ContractError::PaymentAlreadyConfirmed(payment_id),
+        );
+
+        ensure!(
+            !payment_receipt.receiver_accounts().contains(&new_receiver),
+            ContractError::DuplicateReceiver(new_receiver),
+        );
+
+        ensure!(
+            new_receiver != issuer,
+            ContractError::SelfPaymentNotAllowed(issuer),
+        );
+
+        ensure!(
+            new_receiver != env::current_account_id()
+                && (!self.allowlist_enabled || self.allowed_receivers.contains(&new_receiver)),
+            ContractError::ReceiverNotAllowed(new_receiver),
+        );
+
+        ensure!(
+            self.get_receiver_prefs(new_receiver.clone())
+                .accepting_payments,
+            ContractError::ReceiverNotAcceptingPayments(new_receiver),
+        );
+
+        let active_payments = self
+            .receiver_ledger
+            .get(&new_receiver)
+            .map_or(0, |store| store.len());
+
+        ensure!(
+            active_payments < self.max_payments_per_account,
+            ContractError::TooManyActivePayments(
+                new_receiver,
+                active_payments,
+                self.max_payments_per_account,
+            ),
+        );
+
+        ensure!(
+            remove_ledger_payment_id(&mut self.receiver_ledger, &old_receiver, payment_id),
+            ContractError::ReceiverAccountNotExist(old_receiver),
+        );
+
+        ensure!(
+            insert_ledger_payment_id(
+                &mut self.receiver_ledger,
+                &new_receiver,
+                payment_id,
+                StorageKey::ReceiverLedgerRecord {
+                    user: new_receiver.clone(),
+                },
+            ),
             ContractError::PaymentIdAlreadyExists(payment_id),
+        );
+
+        let payment_receipt = self
+            .payment_info_ledger
+            .get_mut(&payment_id)
+            .ok_or(ContractError::PaymentIdNotExist(payment_id))?
+            .into_current_mut();
+
+        // the primary receiver of the split stream is its first share
+        for share in payment_receipt.receivers.iter_mut() {
+            if share.receiver == old_receiver {
+                share.receiver = new_receiver.clone();
+            }
+        }
+
+        payment_receipt.receiver = new_receiver.clone();
+        payment_receipt.payout_address = None;
+        payment_receipt.claim_delegate = None;
+
+        ContractEvent::PendingReceiverChanged(vec![PendingReceiverChanged {
+            payment_id: payment_id.into(),
+            old_receiver,
+            new_receiver,
+        }])
+        .emit();
+
+        Ok(())
+    }
+
+    // the metadata is replaced as a whole, None removes it
+    #[payable]
+    #[handle_result]
+    pub fn update_payment_metadata(
+        &mut self,
+        payment_id: U64,
+        metadata: Option<String>,
+    ) -> Result<()> {
+        assert_one_yocto();
+        self.check_not_paused()?;
+
+        let issuer = env::predecessor_account_id();
+        let payment_id = payment_id.0;
+
+        self.check_payment_unlocked(payment_id)?;
+        self.check_issue_payment_id(&issuer, payment_id)?;
+
+        if let Some(metadata) = &metadata {
+            ensure!(
+                metadata.len() <= MAX_METADATA_LENGTH,
+                ContractError::MetadataTooLong(metadata.len()),
+            );
+        }
+
+        self.payment_info_ledger
+            .get_mut(&payment_id)
+            .ok_or(ContractError::PaymentIdNotExist(payment_id))?
+            .into_current_mut()
+            .metadata = metadata;
+
+        Ok(())
+    }
+
+    // dry run of the create_payment validation, returns the number of periods of the payment
+    #[handle_result]
+    pub fn validate_create_payment(
+        &self,
+        attached_deposit: U128,
+        payment_amount: U128,
+        period_duration_units: U64,
+        period_unit: Option<PeriodUnit>,
+    ) -> Result<U64> {
+        let payment_info = self.build_payment_info(
+            attached_deposit.0,
+            payment_amount.0,
+            period_duration_units.0,
+            period_unit.unwrap_or_default(),
         )?;
 
-        let receiver_id_store = match self.receiver_ledger.get_mut(&receiver) {
-            Some(value) => value,
-            None => {
-                self.receiver_ledger.insert(
-                    receiver.clone(),
-                    UnorderedSet::new(StorageKey::ReceiverLedgerRecord {
-                        user: receiver.clone(),
-                    }),
-                );
+        payment_info
+            .max_payments_number()
+            .map(U64)
+            .ok_or(ContractError::InternalCalculationError(
+                self.payment_id_counter,
+            ))
+    }
+
+    #[handle_result]
+    fn create_payment_impl(
+        &mut self,
+        period_duration_units: u64,
+        period_unit: PeriodUnit,
+        payment_amount: u128,
+        receivers: Vec<(AccountId, u16)>,
+        options: PaymentOptions,
+    ) -> Result<u64> {
+        self.check_not_paused()?;
+
+        let PaymentOptions {
+            memo,
+            approval_deadline,
+            vesting_mode,
+            approval_required,
+            client_nonce,
+            min_claim_interval,
+            metadata,
+            require_unique,
+        } = options;
+
+        let caller = env::predecessor_account_id();
+        let attached_deposit = env::attached_deposit();
 
-                self.receiver_ledger.get_mut(&receiver).unwrap()
+        if let Some(client_nonce) = &client_nonce {
+            ensure!(
+                client_nonce.len() <= MAX_CLIENT_NONCE_LENGTH,
+                ContractError::ClientNonceTooLong(client_nonce.len(), MAX_CLIENT_NONCE_LENGTH),
+            );
+
+            // the retried transaction gets the already created payment back, the new deposit is refunded
+            let nonce_key = (caller.clone(), client_nonce.clone());
+            if let Some(payment_id) = self.client_nonces.get(&nonce_key) {
+                let payment_id = *payment_id;
+                self.transfer_with_fallback(caller, attached_deposit);
+
+                return Ok(payment_id);
             }
-        };
+        }
 
-        require(
-            receiver_id_store.insert(payment_id),
-            ContractError::PaymentIdAlreadyExists(payment_id),
+        if let Some(approval_deadline) = approval_deadline {
+            ensure!(
+                approval_deadline > env::block_timestamp(),
+                ContractError::InvalidApprovalDeadline(approval_deadline),
+            );
+        }
+
+        if let Some(memo) = &memo {
+            ensure!(
+                memo.len() <= MAX_MEMO_LENGTH,
+                ContractError::MemoTooLong(memo.len(), MAX_MEMO_LENGTH),
+            );
+        }
+
+        if let Some(metadata) = &metadata {
+            ensure!(
+                metadata.len() <= MAX_METADATA_LENGTH,
+                ContractError::MetadataTooLong(metadata.len()),
+            );
+        }
+
+        if require_unique {
+            for (receiver, _) in &receivers {
+                if let Some(payment_id) = self
+                    .find_duplicate_payments(
+                        caller.clone(),
+                        receiver.clone(),
+                        payment_amount.into(),
+                        attached_deposit.into(),
+                    )
+                    .first()
+                {
+                    return Err(ContractError::DuplicatePaymentDetected(payment_id.0));
+                }
+            }
+        }
+
+        let mut payment_info = self.build_payment_info(
+            attached_deposit,
+            payment_amount,
+            period_duration_units,
+            period_unit,
         )?;
+        payment_info.vesting_mode = vesting_mode;
+        payment_info.min_claim_interval = min_claim_interval;
 
-        require(
-            self.payment_info_ledger
-                .insert(
+        if !approval_required {
+            payment_info.initiale_date = Some(env::block_timestamp());
+        }
+
+        let total_weight = receivers
+            .iter()
+            .map(|(_, weight)| u32::from(*weight))
+            .sum::<u32>();
+
+        ensure!(
+            receivers.iter().all(|(_, weight)| *weight > 0)
+                && total_weight == u32::from(TOTAL_WEIGHT_BPS),
+            ContractError::InvalidReceiverWeights(total_weight, TOTAL_WEIGHT_BPS),
+        );
+
+        for (index, (receiver, weight)) in receivers.iter().enumerate() {
+            ensure!(
+                self.self_payments_allowed || receiver != &caller,
+                ContractError::SelfPaymentNotAllowed(caller.clone()),
+            );
+
+            ensure!(
+                !self.allowlist_enabled || self.allowed_receivers.contains(receiver),
+                ContractError::ReceiverNotAllowed(receiver.clone()),
+            );
+
+            ensure!(
+                self.get_receiver_prefs(receiver.clone()).accepting_payments,
+                ContractError::ReceiverNotAcceptingPayments(receiver.clone()),
+            );
+
+            ensure!(
+                receivers[..index]
+                    .iter()
+                    .all(|(account_id, _)| account_id != receiver),
+                ContractError::DuplicateReceiver(receiver.clone()),
+            );
+
+            // every receiver should get an integer part of each period payment including the last one
+            for amount in [payment_amount, attached_deposit] {
+                ensure!(
+                    amount
+                        .checked_mul(u128::from(*weight))
+                        .is_some_and(|value| value % u128::from(TOTAL_WEIGHT_BPS) == 0),
+                    ContractError::ReceiverShareNotDivisible(receiver.clone(), amount, *weight),
+                );
+            }
+        }
+
+        // limit the number of active payments to keep the ledgers of particular accounts bounded
+        for (account_id, ledger) in std::iter::once((&caller, &self.issuer_ledger)).chain(
+            receivers
+                .iter()
+                .map(|(receiver, _)| (receiver, &self.receiver_ledger)),
+        ) {
+            let active_payments = ledger.get(account_id).map_or(0, |store| store.len());
+
+            ensure!(
+                active_payments < self.max_payments_per_account,
+                ContractError::TooManyActivePayments(
+                    account_id.clone(),
+                    active_payments,
+                    self.max_payments_per_account,
+                ),
+            );
+        }
+
+        let issuer_limit = self.max_active_payments_per_issuer.unwrap_or(u32::MAX);
+        let issued_payments = self
+            .issuer_ledger
+            .get(&caller)
+            .map_or(0, |store| store.len());
+
+        ensure!(
+            issued_payments < issuer_limit,
+            ContractError::IssuerPaymentLimitReached(caller.clone(), issuer_limit),
+        );
+
+        let payment_id = self.payment_id_counter;
+
+        self.payment_id_counter = self
+            .payment_id_counter
+            .checked_add(1)
+            .ok_or(ContractError::PaymentIdSpaceExhausted)?;
+
+        ensure!(
+            insert_ledger_payment_id(
+                &mut self.issuer_ledger,
+                &caller,
+                payment_id,
+                StorageKey::IssuerLedgerRecord {
+                    user: caller.clone(),
+                },
+            ),
+            ContractError::PaymentIdAlreadyExists(payment_id),
+        );
+
+        for (receiver, _) in &receivers {
+            ensure!(
+                insert_ledger_payment_id(
+                    &mut self.receiver_ledger,
+                    receiver,
                     payment_id,
-                    PaymentReceipt::create_payment_receipt(
-                        PaymentInfo::new(
-                            days_period_duration
-                                .checked_mul(NANOS_IN_DAY)
-                                .ok_or_else(|| {
-                                    ContractError::InternalCalculationError(payment_id)
-                                })?,
-                            payment_amount,
-                            attached_deposit,
-                        ),
-                        caller,
-                        receiver,
-                    ),
-                )
+                    StorageKey::ReceiverLedgerRecord {
+                        user: receiver.clone(),
+                    },
+                ),
+                ContractError::PaymentIdAlreadyExists(payment_id),
+            );
+        }
+
+        let mut payment_receipt = if receivers.len() == 1 {
+            let (receiver, _) = receivers.into_iter().next().unwrap();
+
+            PaymentReceipt::create_payment_receipt(
+                payment_info,
+                caller.clone(),
+                receiver,
+                memo,
+                approval_deadline,
+                env::block_timestamp(),
+                approval_required,
+            )
+        } else {
+            PaymentReceipt::create_split_payment_receipt(
+                payment_info,
+                caller.clone(),
+                receivers,
+                memo,
+                approval_deadline,
+                env::block_timestamp(),
+                approval_required,
+            )
+        };
+        let current_receipt = payment_receipt.into_current_mut();
+        current_receipt.client_nonce = client_nonce.clone();
+        current_receipt.metadata = metadata;
+        current_receipt.period_unit = period_unit;
+        current_receipt.period_value = period_duration_units;
+
+        ensure!(
+            self.payment_info_ledger
+                .insert(payment_id, payment_receipt)
                 .is_none(),
             ContractError::PaymentIdAlreadyExists(payment_id),
-        )?;
+        );
+
+        self.lock_near(payment_id, attached_deposit)?;
+
+        if let Some(client_nonce) = client_nonce {
+            self.client_nonces
+                .insert((caller, client_nonce), payment_id);
+        }
 
         Ok(payment_id)
     }
 }
 
+impl PaymentContract {
+    // the counter is never reset in the production code, so that the payment ids are not reused
+    #[cfg(test)]
+    pub(crate) fn reset_counter_for_testing(&mut self, payment_id_counter: u64) {
+        self.payment_id_counter = payment_id_counter;
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use near_sdk::{store::UnorderedMap, testing_env};
+    use near_sdk::{
+        store::{LookupMap, UnorderedMap, UnorderedSet},
+        test_utils::{accounts, get_created_receipts, get_logs},
+        testing_env,
+    };
 
-    use crate::contract::general_impl::tests::{get_context, issuer_acc, receiver_acc};
+    use crate::constants::{
+        DEFAULT_ABANDONMENT_GRACE_DAYS, DEFAULT_ISSUER_CANCELLATION_NOTICE_DAYS,
+        DEFAULT_MAX_PAYMENTS_PER_ACCOUNT, DEFAULT_MIN_PAYMENT_AMOUNT, DEFAULT_MIN_PERIOD_DURATION,
+        DEFAULT_SETTLEMENT_OFFER_DAYS, MIN_PERIOD_DURATION_SECS, NANOS_IN_HOUR, NANOS_IN_MINUTE,
+        NANOS_IN_WEEK,
+    };
+    use crate::contract::general_impl::tests::{
+        contract_acc, create_payment, get_context, issuer_acc, receiver_acc,
+    };
+    use crate::public::ProcessStatus;
 
     use super::*;
 
+    fn get_contract() -> PaymentContract {
+        PaymentContract {
+            issuer_ledger: UnorderedMap::new(b"i".to_vec()),
+            receiver_ledger: UnorderedMap::new(b"r".to_vec()),
+            payment_info_ledger: UnorderedMap::new(b"p".to_vec()),
+            payment_id_counter: 0,
+            max_payments_per_account: DEFAULT_MAX_PAYMENTS_PER_ACCOUNT,
+            min_payment_amount: DEFAULT_MIN_PAYMENT_AMOUNT,
+            completed_payment_ledger: UnorderedMap::new(b"c".to_vec()),
+            fee_bps: 0,
+            fee_recipient: issuer_acc(),
+            collected_fees: 0,
+            allowed_receivers: UnorderedSet::new(b"a".to_vec()),
+            allowlist_enabled: false,
+            claim_history: LookupMap::new(b"h".to_vec()),
+            client_nonces: LookupMap::new(b"n".to_vec()),
+            owner_id: contract_acc(),
+            paused: false,
+            failed_transfers: UnorderedMap::new(b"f".to_vec()),
+            receiver_prefs: LookupMap::new(b"o".to_vec()),
+            total_locked_near: 0,
+            abandonment_grace_days: DEFAULT_ABANDONMENT_GRACE_DAYS,
+            min_period_duration: DEFAULT_MIN_PERIOD_DURATION,
+            max_active_payments_per_issuer: None,
+            migration_cursor: None,
+            locked_recalculation: None,
+            source_metadata: Default::default(),
+            self_payments_allowed: true,
+            settlement_offer_days: DEFAULT_SETTLEMENT_OFFER_DAYS,
+            issuer_cancellation_notice_days: DEFAULT_ISSUER_CANCELLATION_NOTICE_DAYS,
+        }
+    }
+
     #[test]
     fn test_create_payment() {
         let context = get_context(issuer_acc(), 100);
@@ -135,11 +726,13 @@ mod tests {
             receiver_ledger: UnorderedMap::new(b"receiver_ledger".to_vec()),
             payment_info_ledger: UnorderedMap::new(b"payment_info_ledger".to_vec()),
             payment_id_counter: 0,
+            ..get_contract()
         };
 
         let payment_id = contract
-            .create_payment(U64(30), U128(10), receiver_acc())
-            .unwrap();
+            .create_payment(U64(30), U128(10), receiver_acc(), None)
+            .unwrap()
+            .0;
 
         assert_eq!(payment_id, 0);
 
@@ -174,16 +767,17 @@ mod tests {
             receiver_ledger: UnorderedMap::new(b"r".to_vec()),
             payment_info_ledger: UnorderedMap::new(b"p".to_vec()),
             payment_id_counter: 0,
+            ..get_contract()
         };
 
-        let days_period_duration = U64(0);
+        let period_duration_units = U64(0);
         let payment_amount = U128(0);
 
         let context = get_context(issuer_acc(), 100);
         testing_env!(context.clone());
 
         assert_eq!(
-            contract.create_payment(days_period_duration, payment_amount, receiver_acc()),
+            contract.create_payment(period_duration_units, payment_amount, receiver_acc(), None),
             Err(ContractError::ZeroPaymentCreationParams(100, 0, 0))
         );
     }
@@ -195,17 +789,893 @@ mod tests {
             receiver_ledger: UnorderedMap::new(b"r".to_vec()),
             payment_info_ledger: UnorderedMap::new(b"p".to_vec()),
             payment_id_counter: 0,
+            ..get_contract()
         };
 
-        let days_period_duration = U64(7);
-        let payment_amount = U128(99);
+        let period_duration_units = U64(7);
+        let payment_amount = U128(101);
+
+        let context = get_context(issuer_acc(), 100);
+        testing_env!(context.clone());
+
+        assert_eq!(
+            contract.create_payment(period_duration_units, payment_amount, receiver_acc(), None),
+            Err(ContractError::IncorrectAmountRelatedParams(100, 101))
+        );
+    }
+
+    #[test]
+    fn create_payment_with_unreachable_end_date_should_fail() {
+        let mut contract = get_contract();
+
+        // period duration itself fits into u64, but 10 periods of it do not
+        let period_duration_units = U64(MAX_PERIOD_DAYS);
+        let payment_amount = U128(1);
+
+        let context = get_context(issuer_acc(), 10);
+        testing_env!(context.clone());
+
+        assert_eq!(
+            contract.create_payment(period_duration_units, payment_amount, receiver_acc(), None),
+            Err(ContractError::PaymentEndDateOverflow(
+                MAX_PERIOD_DAYS * NANOS_IN_DAY,
+                10
+            ))
+        );
+
+        // nothing should be stored for the failed payment
+        assert_eq!(contract.payment_id_counter, 0);
+        assert!(contract.payment_info_ledger.get(&0).is_none());
+        assert!(contract.issuer_ledger.get(&issuer_acc()).is_none());
+    }
+
+    #[test]
+    fn test_create_payment_with_period_secs() {
+        let context = get_context(issuer_acc(), 100);
+        testing_env!(context.clone());
+
+        let mut contract = get_contract();
+
+        // hourly stream
+        let payment_id = contract
+            .create_payment_with_period_secs(U64(3600), U128(10), receiver_acc(), None)
+            .unwrap()
+            .0;
+
+        let payment_receipt = contract
+            .payment_info_ledger
+            .get(&payment_id)
+            .unwrap()
+            .into_current();
+
+        assert_eq!(payment_receipt.payment_info.period_duration, NANOS_IN_HOUR);
+
+        // the minimal period is allowed
+        let payment_id = contract
+            .create_payment_with_period_secs(U64(60), U128(10), receiver_acc(), None)
+            .unwrap()
+            .0;
+
+        let payment_receipt = contract
+            .payment_info_ledger
+            .get(&payment_id)
+            .unwrap()
+            .into_current();
+
+        assert_eq!(
+            payment_receipt.payment_info.period_duration,
+            NANOS_IN_MINUTE
+        );
+
+        // the period unit could not be changed from the seconds
+        assert_eq!(
+            contract.create_payment_with_period_secs(
+                U64(60),
+                U128(10),
+                receiver_acc(),
+                Some(CreatePaymentOptions {
+                    period_unit: Some(PeriodUnit::Minutes),
+                    ..Default::default()
+                })
+            ),
+            Err(ContractError::CreateOptionNotSupported(
+                "period_unit".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn create_payment_with_too_short_period_should_fail() {
+        let mut contract = get_contract();
 
         let context = get_context(issuer_acc(), 100);
         testing_env!(context.clone());
 
         assert_eq!(
-            contract.create_payment(days_period_duration, payment_amount, receiver_acc()),
-            Err(ContractError::IncorrectAmountRelatedParams(100, 99))
+            contract.create_payment_with_period_secs(U64(59), U128(10), receiver_acc(), None),
+            Err(ContractError::PeriodDurationTooShort(
+                59,
+                MIN_PERIOD_DURATION_SECS
+            ))
+        );
+    }
+
+    #[test]
+    fn test_create_payment_options_defaults() {
+        // the omitted options get the defaults
+        let options: CreatePaymentOptions =
+            near_sdk::serde_json::from_str(r#"{"memo":"Salary","period_unit":"Weeks"}"#).unwrap();
+
+        assert_eq!(
+            options,
+            CreatePaymentOptions {
+                memo: Some("Salary".to_string()),
+                period_unit: Some(PeriodUnit::Weeks),
+                ..Default::default()
+            }
         );
+        assert_eq!(options.vesting_mode, VestingMode::default());
+        assert!(!options.require_unique);
+    }
+
+    #[test]
+    fn test_create_payment_with_period_unit() {
+        let mut contract = get_contract();
+
+        let context = get_context(issuer_acc(), 100);
+        testing_env!(context.clone());
+
+        for (period_unit, period_duration) in [
+            (PeriodUnit::Days, NANOS_IN_DAY * 2),
+            (PeriodUnit::Minutes, NANOS_IN_MINUTE * 2),
+            (PeriodUnit::Hours, NANOS_IN_HOUR * 2),
+            (PeriodUnit::Weeks, NANOS_IN_WEEK * 2),
+        ] {
+            let payment_id = contract
+                .create_payment(
+                    U64(2),
+                    U128(10),
+                    receiver_acc(),
+                    Some(CreatePaymentOptions {
+                        period_unit: Some(period_unit),
+                        ..Default::default()
+                    }),
+                )
+                .unwrap();
+
+            // the original unit and value are kept next to the duration in nanoseconds
+            let payment_receipt = contract.get_payment(payment_id).unwrap();
+            assert_eq!(
+                payment_receipt.payment_info.period_duration,
+                U64(period_duration)
+            );
+            assert_eq!(payment_receipt.period_unit, period_unit);
+            assert_eq!(payment_receipt.period_value, U64(2));
+        }
+
+        assert_eq!(
+            contract.create_payment(
+                U64(u64::MAX / NANOS_IN_WEEK + 1),
+                U128(10),
+                receiver_acc(),
+                Some(CreatePaymentOptions {
+                    period_unit: Some(PeriodUnit::Weeks),
+                    ..Default::default()
+                })
+            ),
+            Err(ContractError::PeriodDurationTooLong(
+                u64::MAX / NANOS_IN_WEEK + 1,
+                MAX_PERIOD_DAYS / 7
+            ))
+        );
+    }
+
+    #[test]
+    fn test_create_payment_below_min_period_duration() {
+        let mut contract = get_contract();
+        contract.min_period_duration = NANOS_IN_HOUR;
+
+        let context = get_context(issuer_acc(), 100);
+        testing_env!(context.clone());
+
+        assert_eq!(
+            contract.create_payment(
+                U64(59),
+                U128(10),
+                receiver_acc(),
+                Some(CreatePaymentOptions {
+                    period_unit: Some(PeriodUnit::Minutes),
+                    ..Default::default()
+                })
+            ),
+            Err(ContractError::PeriodDurationTooShort(59 * 60, 3600))
+        );
+
+        assert!(contract
+            .create_payment(
+                U64(60),
+                U128(10),
+                receiver_acc(),
+                Some(CreatePaymentOptions {
+                    period_unit: Some(PeriodUnit::Minutes),
+                    ..Default::default()
+                })
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn test_create_payment_with_memo() {
+        let context = get_context(issuer_acc(), 100);
+        testing_env!(context.clone());
+
+        let mut contract = get_contract();
+
+        let payment_id = contract
+            .create_payment(
+                U64(30),
+                U128(10),
+                receiver_acc(),
+                Some(CreatePaymentOptions {
+                    memo: Some("Salary for March".to_string()),
+                    ..Default::default()
+                }),
+            )
+            .unwrap();
+
+        assert_eq!(
+            contract.get_payment(payment_id).unwrap().memo,
+            Some("Salary for March".to_string())
+        );
+
+        // memo is optional
+        let payment_id = contract
+            .create_payment(U64(30), U128(10), receiver_acc(), None)
+            .unwrap();
+
+        assert_eq!(contract.get_payment(payment_id).unwrap().memo, None);
+    }
+
+    #[test]
+    fn test_update_payment_description() {
+        let context = get_context(issuer_acc(), 100);
+        testing_env!(context.clone());
+
+        let mut contract = get_contract();
+
+        let payment_id = contract
+            .create_payment(
+                U64(30),
+                U128(10),
+                receiver_acc(),
+                Some(CreatePaymentOptions {
+                    memo: Some("Salary for March".to_string()),
+                    ..Default::default()
+                }),
+            )
+            .unwrap();
+        assert_eq!(
+            contract.get_payment_description(payment_id),
+            Ok(Some("Salary for March".to_string()))
+        );
+
+        testing_env!(get_context(issuer_acc(), 1));
+        contract
+            .update_payment_description(payment_id, Some("Salary for April".to_string()))
+            .unwrap();
+        assert_eq!(
+            contract.get_payment_description(payment_id),
+            Ok(Some("Salary for April".to_string()))
+        );
+
+        assert_eq!(
+            contract.update_payment_description(payment_id, Some("a".repeat(MAX_MEMO_LENGTH + 1))),
+            Err(ContractError::MemoTooLong(
+                MAX_MEMO_LENGTH + 1,
+                MAX_MEMO_LENGTH
+            ))
+        );
+
+        // the description is not changed while the transfer is in flight
+        contract.set_payment_locked(payment_id.0, true);
+        assert_eq!(
+            contract.update_payment_description(payment_id, None),
+            Err(ContractError::PaymentLocked(payment_id.0))
+        );
+        contract.set_payment_locked(payment_id.0, false);
+
+        contract.paused = true;
+        assert_eq!(
+            contract.update_payment_description(payment_id, None),
+            Err(ContractError::ContractPaused)
+        );
+        contract.paused = false;
+
+        contract
+            .update_payment_description(payment_id, None)
+            .unwrap();
+        assert_eq!(contract.get_payment_description(payment_id), Ok(None));
+
+        // only the issuer could update the description
+        testing_env!(get_context(receiver_acc(), 1));
+        assert_eq!(
+            contract.update_payment_description(payment_id, None),
+            Err(ContractError::IssuerAccountNotExist(receiver_acc()))
+        );
+
+        assert_eq!(
+            contract.get_payment_description(U64(100)),
+            Err(ContractError::PaymentIdNotExist(100))
+        );
+    }
+
+    #[test]
+    fn test_payment_metadata() {
+        let context = get_context(issuer_acc(), 100);
+        testing_env!(context.clone());
+
+        let mut contract = get_contract();
+
+        let metadata = r#"{"employee_id":"E-42","project":"apollo"}"#.to_string();
+
+        assert_eq!(
+            contract.create_payment(
+                U64(30),
+                U128(10),
+                receiver_acc(),
+                Some(CreatePaymentOptions {
+                    metadata: Some("a".repeat(MAX_METADATA_LENGTH + 1)),
+                    ..Default::default()
+                })
+            ),
+            Err(ContractError::MetadataTooLong(MAX_METADATA_LENGTH + 1))
+        );
+
+        let payment_id = contract
+            .create_payment(
+                U64(30),
+                U128(10),
+                receiver_acc(),
+                Some(CreatePaymentOptions {
+                    metadata: Some(metadata.clone()),
+                    ..Default::default()
+                }),
+            )
+            .unwrap();
+        assert_eq!(
+            contract.get_payment_metadata(payment_id),
+            Ok(Some(metadata))
+        );
+        assert_eq!(contract.get_payment_description(payment_id), Ok(None));
+
+        // the metadata of the maximal length is allowed
+        testing_env!(get_context(issuer_acc(), 1));
+        contract
+            .update_payment_metadata(payment_id, Some("a".repeat(MAX_METADATA_LENGTH)))
+            .unwrap();
+        assert_eq!(
+            contract.update_payment_metadata(payment_id, Some("a".repeat(MAX_METADATA_LENGTH + 1))),
+            Err(ContractError::MetadataTooLong(MAX_METADATA_LENGTH + 1))
+        );
+
+        // the metadata is not changed while the transfer is in flight
+        contract.set_payment_locked(payment_id.0, true);
+        assert_eq!(
+            contract.update_payment_metadata(payment_id, None),
+            Err(ContractError::PaymentLocked(payment_id.0))
+        );
+        contract.set_payment_locked(payment_id.0, false);
+
+        contract.paused = true;
+        assert_eq!(
+            contract.update_payment_metadata(payment_id, None),
+            Err(ContractError::ContractPaused)
+        );
+        contract.paused = false;
+
+        contract.update_payment_metadata(payment_id, None).unwrap();
+        assert_eq!(contract.get_payment_metadata(payment_id), Ok(None));
+
+        // only the issuer could update the metadata
+        testing_env!(get_context(receiver_acc(), 1));
+        assert_eq!(
+            contract.update_payment_metadata(payment_id, None),
+            Err(ContractError::IssuerAccountNotExist(receiver_acc()))
+        );
+
+        assert_eq!(
+            contract.get_payment_metadata(U64(100)),
+            Err(ContractError::PaymentIdNotExist(100))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_update_payment_description_without_yocto() {
+        testing_env!(get_context(issuer_acc(), 100));
+        let mut contract = get_contract();
+
+        let payment_id = contract
+            .create_payment(U64(30), U128(10), receiver_acc(), None)
+            .unwrap();
+
+        testing_env!(get_context(issuer_acc(), 0));
+        contract
+            .update_payment_description(payment_id, None)
+            .unwrap();
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_update_payment_metadata_without_yocto() {
+        testing_env!(get_context(issuer_acc(), 100));
+        let mut contract = get_contract();
+
+        let payment_id = contract
+            .create_payment(U64(30), U128(10), receiver_acc(), None)
+            .unwrap();
+
+        testing_env!(get_context(issuer_acc(), 0));
+        contract.update_payment_metadata(payment_id, None).unwrap();
+    }
+
+    #[test]
+    fn test_change_pending_receiver() {
+        let context = get_context(issuer_acc(), 10);
+        testing_env!(context.clone());
+
+        let mut contract = get_contract();
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        testing_env!(get_context(issuer_acc(), 1));
+        contract
+            .change_pending_receiver(U64(payment_id), accounts(4))
+            .unwrap();
+        assert_eq!(
+            get_logs(),
+            vec![format!(
+                r#"EVENT_JSON:{{"standard":"near_payment_receiver","version":"1.0.0","event":"pending_receiver_changed","data":[{{"payment_id":"{}","old_receiver":"{}","new_receiver":"{}"}}]}}"#,
+                payment_id,
+                receiver_acc(),
+                accounts(4)
+            )]
+        );
+
+        assert_eq!(
+            contract.get_payment(U64(payment_id)).unwrap().receiver,
+            accounts(4)
+        );
+        assert!(contract.receiver_ledger.get(&receiver_acc()).is_none());
+        assert!(contract
+            .receiver_ledger
+            .get(&accounts(4))
+            .unwrap()
+            .contains(&payment_id));
+
+        // the old receiver can't approve the payment anymore
+        testing_env!(get_context(receiver_acc(), 0));
+        assert_eq!(
+            contract.process_pending_payment(ProcessStatus::Approve(U64(payment_id))),
+            Err(ContractError::ReceiverAccountNotExist(receiver_acc()))
+        );
+
+        testing_env!(get_context(issuer_acc(), 1));
+        assert_eq!(
+            contract.change_pending_receiver(U64(payment_id), issuer_acc()),
+            Err(ContractError::SelfPaymentNotAllowed(issuer_acc()))
+        );
+        assert_eq!(
+            contract.change_pending_receiver(U64(payment_id), contract_acc()),
+            Err(ContractError::ReceiverNotAllowed(contract_acc()))
+        );
+        assert_eq!(
+            contract.change_pending_receiver(U64(payment_id), accounts(4)),
+            Err(ContractError::DuplicateReceiver(accounts(4)))
+        );
+
+        // only the issuer could change the receiver
+        testing_env!(get_context(accounts(4), 1));
+        assert_eq!(
+            contract.change_pending_receiver(U64(payment_id), accounts(5)),
+            Err(ContractError::IssuerAccountNotExist(accounts(4)))
+        );
+
+        testing_env!(get_context(accounts(4), 0));
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        testing_env!(get_context(issuer_acc(), 1));
+        assert_eq!(
+            contract.change_pending_receiver(U64(payment_id), receiver_acc()),
+            Err(ContractError::PaymentAlreadyConfirmed(payment_id))
+        );
+    }
+
+    #[test]
+    fn test_require_unique_payment() {
+        let context = get_context(issuer_acc(), 10);
+        testing_env!(context.clone());
+
+        let mut contract = get_contract();
+
+        let create = |contract: &mut PaymentContract,
+                      receiver: AccountId,
+                      payment_amount: u128,
+                      require_unique: bool| {
+            contract.create_payment(
+                U64(1),
+                U128(payment_amount),
+                receiver,
+                Some(CreatePaymentOptions {
+                    require_unique,
+                    ..Default::default()
+                }),
+            )
+        };
+
+        let payment_id = create(&mut contract, receiver_acc(), 1, true).unwrap();
+
+        // the duplicate is allowed unless the issuer asks for the check
+        let duplicate_id = create(&mut contract, receiver_acc(), 1, false).unwrap();
+        assert_eq!(
+            contract.find_duplicate_payments(issuer_acc(), receiver_acc(), U128(1), U128(10)),
+            vec![payment_id, duplicate_id]
+        );
+
+        assert_eq!(
+            create(&mut contract, receiver_acc(), 1, true),
+            Err(ContractError::DuplicatePaymentDetected(payment_id.0))
+        );
+
+        // a different receiver or amount is not a duplicate
+        assert!(create(&mut contract, accounts(4), 1, true).is_ok());
+        assert!(create(&mut contract, receiver_acc(), 2, true).is_ok());
+
+        assert_eq!(
+            contract.find_duplicate_payments(issuer_acc(), receiver_acc(), U128(1), U128(20)),
+            vec![]
+        );
+        assert_eq!(
+            contract.find_duplicate_payments(accounts(4), receiver_acc(), U128(1), U128(10)),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn create_payment_with_too_long_memo_should_fail() {
+        let mut contract = get_contract();
+
+        let context = get_context(issuer_acc(), 100);
+        testing_env!(context.clone());
+
+        // the memo of the maximal length is allowed
+        assert!(contract
+            .create_payment(
+                U64(30),
+                U128(10),
+                receiver_acc(),
+                Some(CreatePaymentOptions {
+                    memo: Some("a".repeat(MAX_MEMO_LENGTH)),
+                    ..Default::default()
+                })
+            )
+            .is_ok());
+
+        assert_eq!(
+            contract.create_payment(
+                U64(30),
+                U128(10),
+                receiver_acc(),
+                Some(CreatePaymentOptions {
+                    memo: Some("a".repeat(MAX_MEMO_LENGTH + 1)),
+                    ..Default::default()
+                })
+            ),
+            Err(ContractError::MemoTooLong(
+                MAX_MEMO_LENGTH + 1,
+                MAX_MEMO_LENGTH
+            ))
+        );
+    }
+
+    #[test]
+    fn test_create_payment_with_partial_final_payment() {
+        let context = get_context(issuer_acc(), 100);
+        testing_env!(context.clone());
+
+        let mut contract = get_contract();
+
+        let payment_id = contract
+            .create_payment(U64(30), U128(30), receiver_acc(), None)
+            .unwrap();
+
+        let payment_receipt = contract.load_payment_receipt(payment_id.0).unwrap();
+        assert_eq!(payment_receipt.payment_info.total_amount, 100);
+        assert_eq!(payment_receipt.payment_info.max_payments_number(), Some(4));
+    }
+
+    #[test]
+    fn test_create_split_payment() {
+        let context = get_context(issuer_acc(), 100);
+        testing_env!(context.clone());
+
+        let mut contract = get_contract();
+
+        let payment_id = contract
+            .create_split_payment(
+                U64(30),
+                U128(10),
+                vec![(receiver_acc(), 7000), (accounts(3), 3000)],
+                None,
+            )
+            .unwrap();
+
+        let payment_receipt = contract.load_payment_receipt(payment_id.0).unwrap();
+        assert_eq!(payment_receipt.receiver, receiver_acc());
+        assert_eq!(
+            payment_receipt.receiver_accounts(),
+            vec![receiver_acc(), accounts(3)]
+        );
+
+        // the payment is visible to both receivers
+        for receiver in [receiver_acc(), accounts(3)] {
+            assert!(contract
+                .receiver_ledger
+                .get(&receiver)
+                .unwrap()
+                .contains(&payment_id.0));
+        }
+    }
+
+    #[test]
+    fn create_split_payment_with_incorrect_weights_should_fail() {
+        let mut contract = get_contract();
+
+        let context = get_context(issuer_acc(), 100);
+        testing_env!(context.clone());
+
+        assert_eq!(
+            contract.create_split_payment(
+                U64(30),
+                U128(10),
+                vec![(receiver_acc(), 7000), (accounts(3), 2000)],
+                None
+            ),
+            Err(ContractError::InvalidReceiverWeights(
+                9000,
+                TOTAL_WEIGHT_BPS
+            ))
+        );
+
+        assert_eq!(
+            contract.create_split_payment(
+                U64(30),
+                U128(10),
+                vec![(receiver_acc(), 10000), (accounts(3), 0)],
+                None
+            ),
+            Err(ContractError::InvalidReceiverWeights(
+                10000,
+                TOTAL_WEIGHT_BPS
+            ))
+        );
+
+        assert_eq!(
+            contract.create_split_payment(U64(30), U128(10), vec![], None),
+            Err(ContractError::InvalidReceiverWeights(0, TOTAL_WEIGHT_BPS))
+        );
+
+        assert_eq!(
+            contract.create_split_payment(
+                U64(30),
+                U128(10),
+                vec![(receiver_acc(), 5000), (receiver_acc(), 5000)],
+                None
+            ),
+            Err(ContractError::DuplicateReceiver(receiver_acc()))
+        );
+
+        // 33.33% of 10 tokens is not an integer amount
+        assert_eq!(
+            contract.create_split_payment(
+                U64(30),
+                U128(10),
+                vec![(receiver_acc(), 6667), (accounts(3), 3333)],
+                None
+            ),
+            Err(ContractError::ReceiverShareNotDivisible(
+                receiver_acc(),
+                10,
+                6667
+            ))
+        );
+    }
+
+    #[test]
+    fn create_payment_with_exhausted_payment_id_space_should_fail() {
+        let context = get_context(issuer_acc(), 100);
+        testing_env!(context.clone());
+
+        let mut contract = get_contract();
+        contract.reset_counter_for_testing(u64::MAX - 1);
+
+        // the last available payment id could be used
+        assert_eq!(
+            contract.create_payment(U64(30), U128(10), receiver_acc(), None),
+            Ok(U64(u64::MAX - 1))
+        );
+
+        assert_eq!(
+            contract.create_payment(U64(30), U128(10), receiver_acc(), None),
+            Err(ContractError::PaymentIdSpaceExhausted)
+        );
+
+        // nothing should be stored for the failed payment
+        assert_eq!(contract.payment_id_counter, u64::MAX);
+        assert!(contract.payment_info_ledger.get(&u64::MAX).is_none());
+    }
+
+    #[test]
+    fn test_validate_create_payment() {
+        let contract = get_contract();
+
+        assert_eq!(
+            contract.validate_create_payment(U128(100), U128(10), U64(30), None),
+            Ok(U64(10))
+        );
+
+        // the last period pays the rest of the total amount
+        assert_eq!(
+            contract.validate_create_payment(U128(100), U128(30), U64(30), None),
+            Ok(U64(4))
+        );
+
+        // nothing should be stored by the dry run
+        assert_eq!(contract.payment_id_counter, 0);
+        assert!(contract.issuer_ledger.is_empty());
+    }
+
+    #[test]
+    fn create_payment_with_periods_over_u64_should_fail() {
+        let mut contract = get_contract();
+
+        // the number of periods itself does not fit into u64, it is still reported exactly
+        let attached_deposit = u128::from(u64::MAX) * 4 + 1;
+        let context = get_context(issuer_acc(), attached_deposit);
+        testing_env!(context.clone());
+
+        assert_eq!(
+            contract.create_payment(U64(1), U128(1), receiver_acc(), None),
+            Err(ContractError::PaymentEndDateOverflow(
+                NANOS_IN_DAY,
+                attached_deposit
+            ))
+        );
+    }
+
+    #[test]
+    fn validate_create_payment_with_incorrect_params_should_fail() {
+        let contract = get_contract();
+
+        assert_eq!(
+            contract.validate_create_payment(U128(100), U128(0), U64(0), None),
+            Err(ContractError::ZeroPaymentCreationParams(100, 0, 0))
+        );
+
+        assert_eq!(
+            contract.validate_create_payment(U128(100), U128(101), U64(7), None),
+            Err(ContractError::IncorrectAmountRelatedParams(100, 101))
+        );
+
+        assert_eq!(
+            contract.validate_create_payment(U128(10), U128(1), U64(MAX_PERIOD_DAYS), None),
+            Err(ContractError::PaymentEndDateOverflow(
+                MAX_PERIOD_DAYS * NANOS_IN_DAY,
+                10
+            ))
+        );
+
+        assert_eq!(
+            contract.validate_create_payment(U128(100), U128(10), U64(u64::MAX), None),
+            Err(ContractError::PeriodDurationTooLong(
+                u64::MAX,
+                MAX_PERIOD_DAYS
+            ))
+        );
+    }
+
+    #[test]
+    fn validate_create_payment_max_period() {
+        let contract = get_contract();
+
+        assert_eq!(
+            contract.validate_create_payment(U128(1), U128(1), U64(MAX_PERIOD_DAYS), None),
+            Ok(U64(1))
+        );
+
+        assert_eq!(
+            contract.validate_create_payment(U128(1), U128(1), U64(MAX_PERIOD_DAYS + 1), None),
+            Err(ContractError::PeriodDurationTooLong(
+                MAX_PERIOD_DAYS + 1,
+                MAX_PERIOD_DAYS
+            ))
+        );
+
+        // the limit is converted to the units of the period
+        assert_eq!(
+            contract.validate_create_payment(
+                U128(1),
+                U128(1),
+                U64(MAX_PERIOD_DAYS / 7 + 1),
+                Some(PeriodUnit::Weeks)
+            ),
+            Err(ContractError::PeriodDurationTooLong(
+                MAX_PERIOD_DAYS / 7 + 1,
+                MAX_PERIOD_DAYS / 7
+            ))
+        );
+    }
+
+    #[test]
+    fn test_create_payment_with_client_nonce() {
+        let mut contract = get_contract();
+
+        let create_with_nonce = |contract: &mut PaymentContract, client_nonce: &str| {
+            contract.create_payment(
+                U64(1),
+                U128(1),
+                receiver_acc(),
+                Some(CreatePaymentOptions {
+                    client_nonce: Some(client_nonce.to_string()),
+                    ..Default::default()
+                }),
+            )
+        };
+
+        testing_env!(get_context(issuer_acc(), 10));
+        let payment_id = create_with_nonce(&mut contract, "nonce").unwrap();
+
+        // the retried creation returns the same payment and refunds the deposit
+        testing_env!(get_context(issuer_acc(), 10));
+        assert_eq!(create_with_nonce(&mut contract, "nonce"), Ok(payment_id));
+        assert_eq!(contract.payment_id_counter, 1);
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == issuer_acc()));
+
+        // another nonce creates a new payment
+        let other_payment_id = create_with_nonce(&mut contract, "other").unwrap();
+        assert_ne!(other_payment_id, payment_id);
+
+        assert_eq!(
+            create_with_nonce(&mut contract, &"n".repeat(MAX_CLIENT_NONCE_LENGTH + 1)),
+            Err(ContractError::ClientNonceTooLong(
+                MAX_CLIENT_NONCE_LENGTH + 1,
+                MAX_CLIENT_NONCE_LENGTH
+            ))
+        );
+
+        // the nonce is freed after the final claim
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+        contract
+            .process_pending_payment(ProcessStatus::Approve(payment_id))
+            .unwrap();
+
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = NANOS_IN_DAY * 10 + 1;
+        testing_env!(context.clone());
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id.0),
+            Ok(10)
+        );
+        assert!(!contract
+            .client_nonces
+            .contains_key(&(issuer_acc(), "nonce".to_string())));
+
+        testing_env!(get_context(issuer_acc(), 10));
+        assert_ne!(create_with_nonce(&mut contract, "nonce"), Ok(payment_id));
     }
 }
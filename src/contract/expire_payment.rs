@@ -0,0 +1,326 @@
+use super::PaymentContract;
+use crate::constants::BULK_OPERATION_GAS_FLOOR;
+use crate::contract::PaymentContractExt;
+use crate::error::{ensure, ContractError};
+use crate::public::completed_payment::CompletionReason;
+use crate::Result;
+use near_sdk::{env, json_types::U64, near_bindgen, AccountId};
+
+#[near_bindgen]
+impl PaymentContract {
+    #[handle_result]
+    pub fn expire_payment(&mut self, payment_id: U64) -> Result<()> {
+        let payment_id = payment_id.0;
+
+        let payment_receipt = self.load_payment_receipt(payment_id)?;
+
+        ensure!(
+            payment_receipt.payment_info.initiale_date.is_none(),
+            ContractError::PaymentAlreadyConfirmed(payment_id),
+        );
+
+        let approval_deadline = payment_receipt
+            .approval_deadline
+            .ok_or(ContractError::NoApprovalDeadline(payment_id))?;
+
+        ensure!(
+            env::block_timestamp() > approval_deadline,
+            ContractError::ApprovalDeadlineNotReached(payment_id, approval_deadline),
+        );
+
+        let issuer = payment_receipt.issuer.clone();
+        let receiver = payment_receipt.receiver.clone();
+        let total_amount = payment_receipt.payment_info.total_amount;
+
+        self.remove_payment_related_data(
+            &issuer,
+            &receiver,
+            payment_id,
+            CompletionReason::Expired,
+            total_amount,
+        )?;
+
+        // the receiver has never confirmed the payment, so the whole amount is returned
+        self.transfer_with_fallback(issuer, total_amount);
+
+        Ok(())
+    }
+
+    // expires all the payments of the issuer which are not approved until the deadline,
+    // should be called repeatedly while it returns the ids left for the next call
+    #[handle_result]
+    pub fn cleanup_closed_payments(&mut self, issuer: AccountId) -> Result<Vec<U64>> {
+        let current_time = env::block_timestamp();
+
+        let closed_payments: Vec<(u64, u128)> = self
+            .issuer_ledger
+            .get(&issuer)
+            .map(|payment_ids| {
+                payment_ids
+                    .iter()
+                    .filter_map(|payment_id| {
+                        let payment_receipt =
+                            self.payment_info_ledger.get(payment_id)?.into_current();
+
+                        payment_receipt
+                            .is_expired(current_time)
+                            .then(|| (*payment_id, payment_receipt.payment_info.total_amount))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let unprocessed_ids = self.remove_payments_bulk(
+            &issuer,
+            closed_payments.clone(),
+            CompletionReason::Expired,
+            BULK_OPERATION_GAS_FLOOR,
+        )?;
+
+        // the whole amount of every removed payment is returned by one transfer
+        let refund_amount: u128 = closed_payments[..closed_payments.len() - unprocessed_ids.len()]
+            .iter()
+            .map(|(_, total_amount)| total_amount)
+            .sum();
+
+        self.transfer_with_fallback(issuer, refund_amount);
+
+        Ok(unprocessed_ids.into_iter().map(U64).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constants::NANOS_IN_DAY,
+        contract::general_impl::tests::{
+            check_all_account_data_removed, check_all_data_removed, contract_acc, create_payment,
+            get_context, issuer_acc, receiver_acc, set_block_timestamp,
+        },
+        public::{CreatePaymentOptions, ProcessStatus},
+    };
+
+    use super::*;
+    use near_sdk::{json_types::U128, test_utils::accounts, testing_env, Gas};
+
+    fn create_payment_with_deadline(contract: &mut PaymentContract, deadline: u64) -> u64 {
+        let context = get_context(issuer_acc(), 10);
+        testing_env!(context.clone());
+
+        contract
+            .create_payment(
+                U64(1),
+                U128(1),
+                receiver_acc(),
+                Some(CreatePaymentOptions {
+                    approval_deadline: Some(U64(deadline)),
+                    ..Default::default()
+                }),
+            )
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn test_expire_payment() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let payment_id = create_payment_with_deadline(&mut contract, NANOS_IN_DAY);
+
+        // anyone could expire the payment after the deadline
+        let mut context = get_context(accounts(3), 0);
+        context.block_timestamp = NANOS_IN_DAY + 1;
+        testing_env!(context.clone());
+
+        contract.expire_payment(U64(payment_id)).unwrap();
+
+        // check that the payment has been removed from all storages
+        check_all_account_data_removed(&contract, payment_id);
+        assert_eq!(
+            contract
+                .get_completed_payment(U64(payment_id))
+                .unwrap()
+                .completion_reason,
+            CompletionReason::Expired
+        );
+    }
+
+    #[test]
+    fn test_expire_payment_before_deadline() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let payment_id = create_payment_with_deadline(&mut contract, NANOS_IN_DAY);
+
+        // the deadline itself is still a valid time for the approval
+        set_block_timestamp(NANOS_IN_DAY);
+        assert_eq!(
+            contract.expire_payment(U64(payment_id)),
+            Err(ContractError::ApprovalDeadlineNotReached(
+                payment_id,
+                NANOS_IN_DAY
+            ))
+        );
+    }
+
+    #[test]
+    fn test_expire_approved_payment() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let payment_id = create_payment_with_deadline(&mut contract, NANOS_IN_DAY);
+
+        // the receiver approves the payment before the deadline
+        let context = get_context(receiver_acc(), 0);
+        testing_env!(context.clone());
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        set_block_timestamp(NANOS_IN_DAY + 1);
+        assert_eq!(
+            contract.expire_payment(U64(payment_id)),
+            Err(ContractError::PaymentAlreadyConfirmed(payment_id))
+        );
+    }
+
+    #[test]
+    fn test_expire_payment_without_deadline() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        set_block_timestamp(NANOS_IN_DAY * 365);
+        assert_eq!(
+            contract.expire_payment(U64(payment_id)),
+            Err(ContractError::NoApprovalDeadline(payment_id))
+        );
+    }
+
+    #[test]
+    fn test_create_payment_with_past_deadline() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let mut context = get_context(issuer_acc(), 10);
+        context.block_timestamp = NANOS_IN_DAY;
+        testing_env!(context.clone());
+
+        assert_eq!(
+            contract.create_payment(
+                U64(1),
+                U128(1),
+                receiver_acc(),
+                Some(CreatePaymentOptions {
+                    approval_deadline: Some(U64(NANOS_IN_DAY)),
+                    ..Default::default()
+                })
+            ),
+            Err(ContractError::InvalidApprovalDeadline(NANOS_IN_DAY))
+        );
+    }
+
+    #[test]
+    fn test_cleanup_closed_payments() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let expired_ids: Vec<u64> = (0..3)
+            .map(|_| create_payment_with_deadline(&mut contract, NANOS_IN_DAY))
+            .collect();
+        let active_id = create_payment_with_deadline(&mut contract, NANOS_IN_DAY * 2);
+
+        let mut context = get_context(accounts(3), 0);
+        context.block_timestamp = NANOS_IN_DAY + 1;
+        testing_env!(context.clone());
+
+        assert_eq!(contract.cleanup_closed_payments(issuer_acc()), Ok(vec![]));
+
+        for payment_id in expired_ids {
+            check_all_data_removed(&contract, payment_id);
+        }
+
+        // the payment with the later deadline is kept
+        assert!(contract.payment_exists(U64(active_id)));
+
+        // nothing is left for the next call
+        assert_eq!(contract.cleanup_closed_payments(issuer_acc()), Ok(vec![]));
+        assert_eq!(contract.cleanup_closed_payments(accounts(3)), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_remove_payments_bulk_gas_floor() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let payment_ids: Vec<u64> = (0..3)
+            .map(|_| create_payment_with_deadline(&mut contract, NANOS_IN_DAY))
+            .collect();
+
+        // every payment refunds the whole deposit
+        let payments: Vec<(u64, u128)> = payment_ids
+            .iter()
+            .map(|payment_id| (*payment_id, 10))
+            .collect();
+
+        // the floor above the prepaid gas leaves every payment unprocessed
+        let gas_floor = Gas(env::prepaid_gas().0 + 1);
+        assert_eq!(
+            contract.remove_payments_bulk(
+                &issuer_acc(),
+                payments.clone(),
+                CompletionReason::Expired,
+                gas_floor,
+            ),
+            Ok(payment_ids.clone())
+        );
+        assert!(payment_ids
+            .iter()
+            .all(|payment_id| contract.payment_exists(U64(*payment_id))));
+
+        // the unprocessed ids are finished by the next call
+        assert_eq!(
+            contract.remove_payments_bulk(
+                &issuer_acc(),
+                payments,
+                CompletionReason::Expired,
+                Gas(0),
+            ),
+            Ok(vec![])
+        );
+
+        for payment_id in payment_ids {
+            check_all_account_data_removed(&contract, payment_id);
+        }
+    }
+}
@@ -1,28 +1,241 @@
 use super::PaymentContract;
+use crate::constants::{GAS_FOR_TRANSFER_CALLBACK, MAX_CLAIM_HISTORY_LENGTH};
 use crate::contract::PaymentContractExt;
+use crate::events::{ContractEvent, ReceiverPayout, TransferFailed};
+use crate::public::claim_history::{compact_claim_history, ClaimRecord};
+use crate::public::completed_payment::{CompletedPaymentRecord, CompletionReason};
+use crate::public::payment_receipt::CurrentUserVersion;
+use crate::public::StorageKey;
 use crate::{
-    error::{require, ContractError},
+    error::{ensure, ContractError},
     Result,
 };
-use near_sdk::{near_bindgen, AccountId};
+use near_sdk::{
+    env,
+    json_types::{U128, U64},
+    near_bindgen,
+    store::{UnorderedMap, UnorderedSet, Vector},
+    AccountId, Gas, Promise, PromiseError,
+};
+use std::borrow::Cow;
 
 #[near_bindgen]
 impl PaymentContract {
     #[handle_result]
-    pub(crate) fn check_reciever_payment_id(
+    pub(crate) fn assert_owner(&self) -> Result<()> {
+        let caller = env::predecessor_account_id();
+
+        ensure!(
+            caller == self.owner_id,
+            ContractError::NotContractOwner(caller),
+        );
+
+        Ok(())
+    }
+
+    // fails before the state is changed, so that the transfer is not left unscheduled after the mutation
+    pub(crate) fn check_prepaid_gas(&self, required: Gas) -> Result<()> {
+        let prepaid = env::prepaid_gas();
+
+        ensure!(
+            prepaid.0 >= required.0,
+            ContractError::InsufficientGas(required.0, prepaid.0),
+        );
+
+        Ok(())
+    }
+
+    // the amount is accumulated, so that the account could withdraw all the failed transfers at once
+    pub(crate) fn store_failed_transfer(&mut self, account_id: AccountId, amount: u128) {
+        ContractEvent::TransferFailed(vec![TransferFailed {
+            account_id: account_id.clone(),
+            amount: amount.into(),
+        }])
+        .emit();
+
+        let failed_amount = self.failed_transfers.entry(account_id).or_insert(0);
+        *failed_amount = failed_amount.saturating_add(amount);
+    }
+
+    // the transferred amount is stored for the later withdrawal when the account could not receive it
+    pub(crate) fn transfer_with_fallback(&self, account_id: AccountId, amount: u128) {
+        if amount == 0 {
+            return;
+        }
+
+        Promise::new(account_id.clone()).transfer(amount).then(
+            Self::ext(env::current_account_id())
+                .with_static_gas(GAS_FOR_TRANSFER_CALLBACK)
+                .on_transfer(account_id, U128(amount)),
+        );
+    }
+
+    #[private]
+    pub fn on_transfer(
+        &mut self,
+        account_id: AccountId,
+        amount: U128,
+        #[callback_result] result: std::result::Result<(), PromiseError>,
+    ) {
+        if result.is_err() {
+            self.store_failed_transfer(account_id, amount.0);
+        }
+    }
+
+    // the missing payment is reported by the following checks
+    #[handle_result]
+    pub(crate) fn check_payment_unlocked(&self, payment_id: u64) -> Result<()> {
+        let locked = self
+            .payment_info_ledger
+            .get(&payment_id)
+            .is_some_and(|payment_receipt| payment_receipt.into_current().locked);
+
+        ensure!(!locked, ContractError::PaymentLocked(payment_id));
+
+        Ok(())
+    }
+
+    // the lock is kept only for the payment which is still active
+    pub(crate) fn set_payment_locked(&mut self, payment_id: u64, locked: bool) {
+        if let Some(payment_receipt) = self.payment_info_ledger.get_mut(&payment_id) {
+            payment_receipt.into_current_mut().locked = locked;
+        }
+    }
+
+    // the payments of the missing receipt are paid to the receiver itself
+    pub(crate) fn payout_account(&self, payment_id: u64, receiver: &AccountId) -> AccountId {
+        self.payment_info_ledger.get(&payment_id).map_or_else(
+            || receiver.clone(),
+            |payment_receipt| payment_receipt.into_current().payout_account(receiver),
+        )
+    }
+
+    // the delegate claims on behalf of the receiver-of-record, any other caller claims for itself
+    pub(crate) fn claim_receiver(&self, payment_id: u64, caller: AccountId) -> AccountId {
+        match self.payment_info_ledger.get(&payment_id) {
+            Some(payment_receipt) => {
+                let payment_receipt = payment_receipt.into_current();
+
+                if payment_receipt.claim_delegate.as_ref() == Some(&caller) {
+                    payment_receipt.receiver.clone()
+                } else {
+                    caller
+                }
+            }
+            None => caller,
+        }
+    }
+
+    #[handle_result]
+    pub(crate) fn check_not_paused(&self) -> Result<()> {
+        ensure!(!self.paused, ContractError::ContractPaused);
+
+        Ok(())
+    }
+
+    // deducts the protocol fee from the receiver payout and returns the net amount
+    #[handle_result]
+    pub(crate) fn charge_payout_fee(
+        &mut self,
+        payment_id: u64,
+        receiver: &AccountId,
+        amount: u128,
+    ) -> Result<u128> {
+        let fee = match self.fee_bps {
+            0 => 0,
+            fee_bps => {
+                // the fee is floored, so the rounding always favors the receiver
+                amount
+                    .checked_mul(u128::from(fee_bps))
+                    .map(|value| value / 10_000)
+                    .ok_or(ContractError::InternalCalculationError(payment_id))?
+            }
+        };
+
+        self.collected_fees = self
+            .collected_fees
+            .checked_add(fee)
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        let net = amount - fee;
+
+        ContractEvent::ReceiverPayout(vec![ReceiverPayout {
+            payment_id: payment_id.into(),
+            receiver: receiver.clone(),
+            gross: amount.into(),
+            fee: fee.into(),
+            net: net.into(),
+        }])
+        .emit();
+
+        Ok(net)
+    }
+
+    #[handle_result]
+    pub(crate) fn lock_near(&mut self, payment_id: u64, amount: u128) -> Result<()> {
+        self.total_locked_near = self
+            .total_locked_near
+            .checked_add(amount)
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        Ok(())
+    }
+
+    // called with the gross amount, the fee is not locked by the payment anymore
+    #[handle_result]
+    pub(crate) fn release_locked_near(&mut self, payment_id: u64, amount: u128) -> Result<()> {
+        self.total_locked_near = self
+            .total_locked_near
+            .checked_sub(amount)
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        Ok(())
+    }
+
+    // appends the claim to the payment history, the old records are compacted when the history is full
+    pub(crate) fn record_claim(&mut self, payment_id: u64, amount: u128) {
+        let history = self
+            .claim_history
+            .entry(payment_id)
+            .or_insert_with(|| Vector::new(StorageKey::ClaimHistory { payment_id }));
+
+        if history.len() >= MAX_CLAIM_HISTORY_LENGTH {
+            compact_claim_history(history);
+        }
+
+        history.push(ClaimRecord {
+            timestamp: env::block_timestamp(),
+            amount,
+        });
+    }
+
+    #[handle_result]
+    pub(crate) fn check_receiver_payment_id(
         &self,
         account_id: &AccountId,
         payment_id: u64,
     ) -> Result<()> {
         let receiver_id_store = self
             .receiver_ledger
-            .get(&account_id)
+            .get(account_id)
             .ok_or_else(|| ContractError::ReceiverAccountNotExist(account_id.clone()))?;
 
-        receiver_id_store
-            .contains(&payment_id)
-            .then_some(())
-            .ok_or_else(|| ContractError::PaymentIdNotExist(payment_id))
+        if receiver_id_store.contains(&payment_id) {
+            return Ok(());
+        }
+
+        Err(self.foreign_payment_error(account_id, payment_id))
+    }
+
+    #[deprecated(note = "use check_receiver_payment_id")]
+    #[allow(dead_code)]
+    #[handle_result]
+    pub(crate) fn check_reciever_payment_id(
+        &self,
+        account_id: &AccountId,
+        payment_id: u64,
+    ) -> Result<()> {
+        self.check_receiver_payment_id(account_id, payment_id)
     }
 
     #[handle_result]
@@ -33,56 +246,225 @@ impl PaymentContract {
     ) -> Result<()> {
         let issue_id_store = self
             .issuer_ledger
-            .get(&account_id)
+            .get(account_id)
             .ok_or_else(|| ContractError::IssuerAccountNotExist(account_id.clone()))?;
 
-        issue_id_store
-            .contains(&payment_id)
-            .then_some(())
-            .ok_or_else(|| ContractError::PaymentIdNotExist(payment_id))
+        if issue_id_store.contains(&payment_id) {
+            return Ok(());
+        }
+
+        Err(self.foreign_payment_error(account_id, payment_id))
+    }
+
+    // the existing payment of another account is distinguished from the mistyped id
+    fn foreign_payment_error(&self, account_id: &AccountId, payment_id: u64) -> ContractError {
+        if self.payment_info_ledger.contains_key(&payment_id) {
+            ContractError::NotAuthorizedForPayment(account_id.clone(), payment_id)
+        } else {
+            ContractError::PaymentIdNotExist(payment_id)
+        }
     }
 
+    #[handle_result]
+    pub(crate) fn load_payment_receipt(
+        &self,
+        payment_id: u64,
+    ) -> Result<Cow<'_, CurrentUserVersion>> {
+        Ok(self
+            .payment_info_ledger
+            .get(&payment_id)
+            .ok_or(ContractError::PaymentIdNotExist(payment_id))?
+            .into_current())
+    }
+
+    // idempotent, so that the repeated call cleans up the ledger records left without the receipt
     #[handle_result]
     pub(crate) fn remove_payment_related_data(
         &mut self,
         issuer: &AccountId,
         receiver: &AccountId,
         payment_id: u64,
+        completion_reason: CompletionReason,
+        refunded: u128,
     ) -> Result<()> {
-        // remove payment_id from the issue store
-        require(
-            self.issuer_ledger
-                .get_mut(&issuer)
-                .and_then(|issuer_id_store| issuer_id_store.remove(&payment_id).then_some(()))
-                .is_some(),
-            ContractError::IssuerAccountNotExist(issuer.clone()),
-        )?;
-
-        // remove related payment receipt
-        self.payment_info_ledger
-            .remove(&payment_id)
-            .ok_or_else(|| ContractError::PaymentIdNotExist(payment_id))?;
-
-        // remove payment_id from the receiver store
-        require(
-            self.receiver_ledger
-                .get_mut(&receiver)
-                .and_then(|receiver_id_store| receiver_id_store.remove(&payment_id).then_some(()))
-                .is_some(),
-            ContractError::ReceiverAccountNotExist(receiver.clone()),
-        )
+        let payment_receipt = self.payment_info_ledger.remove(&payment_id);
+
+        // the receivers of the split stream are known only from the receipt
+        let mut receivers = vec![receiver.clone()];
+        if let Some(payment_receipt) = &payment_receipt {
+            for account_id in payment_receipt.into_current().receiver_accounts() {
+                if !receivers.contains(&account_id) {
+                    receivers.push(account_id);
+                }
+            }
+        }
+
+        // the missing ledger records are reported, but they don't block the cleanup of the rest,
+        // otherwise the payment with the partial state could never be closed
+        if !remove_ledger_payment_id(&mut self.issuer_ledger, issuer, payment_id) {
+            log_missing_ledger_record("issuers", issuer, payment_id);
+        }
+
+        for receiver in receivers {
+            if !remove_ledger_payment_id(&mut self.receiver_ledger, &receiver, payment_id) {
+                log_missing_ledger_record("receivers", &receiver, payment_id);
+            }
+        }
+
+        // the rest of the cleanup is driven by the receipt, so it is done only once
+        let Some(payment_receipt) = payment_receipt else {
+            return Ok(());
+        };
+        let payment_receipt = payment_receipt.into_current();
+
+        // the amount paid out to the issuer and the receivers by the removal
+        self.release_locked_near(payment_id, refunded)?;
+
+        // the top-up of the amendment which is not accepted is returned to the issuer
+        if let Some(amendment) = &payment_receipt.pending_amendment {
+            self.release_locked_near(payment_id, amendment.top_up)?;
+            self.transfer_with_fallback(issuer.clone(), amendment.top_up);
+        }
+
+        // the nonce could be used again for a new payment
+        if let Some(client_nonce) = &payment_receipt.client_nonce {
+            self.client_nonces
+                .remove(&(issuer.clone(), client_nonce.clone()));
+        }
+
+        // the claim history is not needed anymore, the storage is reclaimed
+        if let Some(mut history) = self.claim_history.remove(&payment_id) {
+            history.clear();
+        }
+
+        // keep the metadata of the finished payment for the audit purposes
+        self.completed_payment_ledger.insert(
+            payment_id,
+            CompletedPaymentRecord {
+                issuer: issuer.clone(),
+                receiver: payment_receipt.receiver.clone(),
+                total_amount: payment_receipt.payment_info.total_amount,
+                completed_at: env::block_timestamp(),
+                completion_reason,
+            },
+        );
+
+        Ok(())
+    }
+
+    // removes the payments with their refunded amounts until the remaining gas reaches the floor,
+    // returns the ids left unprocessed
+    #[handle_result]
+    pub(crate) fn remove_payments_bulk(
+        &mut self,
+        issuer: &AccountId,
+        payments: Vec<(u64, u128)>,
+        completion_reason: CompletionReason,
+        gas_floor: Gas,
+    ) -> Result<Vec<u64>> {
+        for (index, (payment_id, refunded)) in payments.iter().enumerate() {
+            let remaining_gas = env::prepaid_gas().0.saturating_sub(env::used_gas().0);
+
+            if remaining_gas < gas_floor.0 {
+                return Ok(payments[index..]
+                    .iter()
+                    .map(|(payment_id, _)| *payment_id)
+                    .collect());
+            }
+
+            let receiver = self.load_payment_receipt(*payment_id)?.receiver.clone();
+            self.remove_payment_related_data(
+                issuer,
+                &receiver,
+                *payment_id,
+                completion_reason,
+                *refunded,
+            )?;
+        }
+
+        Ok(vec![])
     }
 }
 
+fn log_missing_ledger_record(store: &str, account_id: &AccountId, payment_id: u64) {
+    env::log_str(&format!(
+        "Payment id {} is missing in the {} store of the account {}",
+        payment_id, store, account_id
+    ));
+}
+
+// adds payment_id to the account record, the record is created with the storage key for the first payment
+pub(crate) fn insert_ledger_payment_id(
+    ledger: &mut UnorderedMap<AccountId, UnorderedSet<u64>>,
+    account_id: &AccountId,
+    payment_id: u64,
+    storage_key: StorageKey,
+) -> bool {
+    if !ledger.contains_key(account_id) {
+        ledger.insert(account_id.clone(), UnorderedSet::new(storage_key));
+    }
+
+    ledger
+        .get_mut(account_id)
+        .is_some_and(|id_store| id_store.insert(payment_id))
+}
+
+// removes payment_id from the account record, the record itself is removed when it becomes empty
+pub(crate) fn remove_ledger_payment_id(
+    ledger: &mut UnorderedMap<AccountId, UnorderedSet<u64>>,
+    account_id: &AccountId,
+    payment_id: u64,
+) -> bool {
+    let id_store = match ledger.get_mut(account_id) {
+        Some(value) => value,
+        None => return false,
+    };
+
+    if !id_store.remove(&payment_id) {
+        return false;
+    }
+
+    if id_store.is_empty() {
+        // the free slots of the set stay in storage until it is cleared, the same prefix is
+        // reused when the record of the account is created again
+        id_store.clear();
+        ledger.remove(account_id);
+    }
+
+    true
+}
+
+// returns the page of payment ids from the account record, empty when the account has no record
+pub(crate) fn ledger_payment_ids(
+    ledger: &UnorderedMap<AccountId, UnorderedSet<u64>>,
+    account_id: &AccountId,
+    from_index: u64,
+    limit: u64,
+) -> Vec<U64> {
+    ledger
+        .get(account_id)
+        .map(|id_store| {
+            id_store
+                .iter()
+                .skip(from_index as usize)
+                .take(limit as usize)
+                .map(|payment_id| U64(*payment_id))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 #[cfg(test)]
 pub(crate) mod tests {
     use near_sdk::{
         json_types::{U128, U64},
-        test_utils::accounts,
+        test_utils::{accounts, get_logs},
         testing_env, AccountId, VMContext,
     };
 
     use crate::contract::PaymentContract;
+    use crate::error::ContractError;
+    use crate::public::completed_payment::CompletionReason;
 
     pub fn contract_acc() -> AccountId {
         accounts(0)
@@ -114,6 +496,15 @@ pub(crate) mod tests {
             .contains(&payment_id));
     }
 
+    // the account records are removed together with the last payment of the account
+    pub fn check_all_account_data_removed(contract: &PaymentContract, payment_id: u64) {
+        let payment = contract.payment_info_ledger.get(&payment_id);
+        assert!(payment.is_none());
+
+        assert!(contract.issuer_ledger.get(&issuer_acc()).is_none());
+        assert!(contract.receiver_ledger.get(&receiver_acc()).is_none());
+    }
+
     // helper function to create a payment
     pub fn create_payment(
         contract: &mut PaymentContract,
@@ -123,8 +514,9 @@ pub(crate) mod tests {
         let context = get_context(issuer_acc(), attached_deposit);
         testing_env!(context.clone());
         contract
-            .create_payment(U64(1), U128(amount), receiver_acc())
+            .create_payment(U64(1), U128(amount), receiver_acc(), None)
             .unwrap()
+            .0
     }
 
     pub fn set_block_timestamp(timestamp: u64) -> u64 {
@@ -157,4 +549,250 @@ pub(crate) mod tests {
             view_config: None,
         }
     }
+
+    // the usage of the account is kept between the calls on the chain, so that a call could
+    // free more storage than it writes, e.g. when the emptied ledger records are cleared
+    pub fn get_context_with_storage_usage(
+        predecessor_account_id: AccountId,
+        attached_deposit: u128,
+    ) -> VMContext {
+        VMContext {
+            storage_usage: 1024 * 300,
+            ..get_context(predecessor_account_id, attached_deposit)
+        }
+    }
+
+    #[test]
+    fn test_remove_last_payment_removes_account_records() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let first_payment_id = create_payment(&mut contract, 10, 1);
+        let second_payment_id = create_payment(&mut contract, 10, 1);
+
+        // the records are kept while there are active payments
+        contract
+            .remove_payment_related_data(
+                &issuer_acc(),
+                &receiver_acc(),
+                first_payment_id,
+                CompletionReason::Claimed,
+                10,
+            )
+            .unwrap();
+        assert!(contract.issuer_ledger.get(&issuer_acc()).is_some());
+        assert!(contract.receiver_ledger.get(&receiver_acc()).is_some());
+
+        contract
+            .remove_payment_related_data(
+                &issuer_acc(),
+                &receiver_acc(),
+                second_payment_id,
+                CompletionReason::Claimed,
+                10,
+            )
+            .unwrap();
+        assert!(contract.issuer_ledger.get(&issuer_acc()).is_none());
+        assert!(contract.receiver_ledger.get(&receiver_acc()).is_none());
+
+        // a new payment for the same accounts creates the records again
+        let payment_id = create_payment(&mut contract, 10, 1);
+        let issuer_id_store = contract.issuer_ledger.get(&issuer_acc()).unwrap();
+        assert_eq!(issuer_id_store.len(), 1);
+        assert!(issuer_id_store.contains(&payment_id));
+        assert_eq!(
+            issuer_id_store.iter().copied().collect::<Vec<_>>(),
+            vec![payment_id]
+        );
+
+        let receiver_id_store = contract.receiver_ledger.get(&receiver_acc()).unwrap();
+        assert_eq!(receiver_id_store.len(), 1);
+        assert!(receiver_id_store.contains(&payment_id));
+        assert_eq!(
+            receiver_id_store.iter().copied().collect::<Vec<_>>(),
+            vec![payment_id]
+        );
+    }
+
+    #[test]
+    fn test_remove_payment_with_partial_state() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let payment_id = create_payment(&mut contract, 10, 1);
+        let other_payment_id = create_payment(&mut contract, 10, 1);
+
+        // corrupt the issuer record of the first payment and the receiver record of the second one
+        contract
+            .issuer_ledger
+            .get_mut(&issuer_acc())
+            .unwrap()
+            .remove(&payment_id);
+        contract
+            .receiver_ledger
+            .get_mut(&receiver_acc())
+            .unwrap()
+            .remove(&other_payment_id);
+
+        contract
+            .remove_payment_related_data(
+                &issuer_acc(),
+                &receiver_acc(),
+                payment_id,
+                CompletionReason::Claimed,
+                10,
+            )
+            .unwrap();
+        // the receiver record is emptied, the issuer record keeps the other payment
+        assert!(contract.payment_info_ledger.get(&payment_id).is_none());
+        assert!(!contract.issuer_ledger[&issuer_acc()].contains(&payment_id));
+        assert!(contract.receiver_ledger.get(&receiver_acc()).is_none());
+        assert!(contract.get_completed_payment(U64(payment_id)).is_some());
+        assert_eq!(
+            get_logs(),
+            vec![format!(
+                "Payment id {} is missing in the issuers store of the account {}",
+                payment_id,
+                issuer_acc()
+            )]
+        );
+
+        contract
+            .remove_payment_related_data(
+                &issuer_acc(),
+                &receiver_acc(),
+                other_payment_id,
+                CompletionReason::Claimed,
+                10,
+            )
+            .unwrap();
+        check_all_account_data_removed(&contract, other_payment_id);
+        assert_eq!(contract.get_total_locked_near(), U128(0));
+
+        // the removed payment is not released twice
+        contract
+            .remove_payment_related_data(
+                &issuer_acc(),
+                &receiver_acc(),
+                payment_id,
+                CompletionReason::Claimed,
+                10,
+            )
+            .unwrap();
+        assert_eq!(contract.get_total_locked_near(), U128(0));
+    }
+
+    #[test]
+    fn test_remove_orphan_ledger_records() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let payment_id = create_payment(&mut contract, 10, 1);
+        let other_payment_id = create_payment(&mut contract, 10, 1);
+
+        // corrupt the state by removing the receipt, the ledger records are left without it
+        contract.payment_info_ledger.remove(&payment_id);
+
+        contract
+            .remove_payment_related_data(
+                &issuer_acc(),
+                &receiver_acc(),
+                payment_id,
+                CompletionReason::Claimed,
+                10,
+            )
+            .unwrap();
+        check_all_data_removed(&contract, payment_id);
+        assert!(contract.get_completed_payment(U64(payment_id)).is_none());
+
+        // the repeated call is a no-op
+        contract
+            .remove_payment_related_data(
+                &issuer_acc(),
+                &receiver_acc(),
+                payment_id,
+                CompletionReason::Claimed,
+                10,
+            )
+            .unwrap();
+        check_all_data_removed(&contract, payment_id);
+
+        // the deposit of the orphan payment is not released, the other payment is kept
+        assert_eq!(contract.get_total_locked_near(), U128(20));
+        let issuer_payment_ids = contract.issuer_ledger.get(&issuer_acc()).unwrap();
+        assert_eq!(issuer_payment_ids.len(), 1);
+        assert!(issuer_payment_ids.contains(&other_payment_id));
+        assert!(contract.payment_exists(U64(other_payment_id)));
+    }
+
+    #[test]
+    fn test_check_payment_id_ownership() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        // the payment between other accounts
+        testing_env!(get_context(accounts(3), 10));
+        let foreign_payment_id = contract
+            .create_payment(U64(1), U128(1), accounts(4), None)
+            .unwrap()
+            .0;
+
+        assert_eq!(
+            contract.check_receiver_payment_id(&receiver_acc(), payment_id),
+            Ok(())
+        );
+        assert_eq!(
+            contract.check_receiver_payment_id(&receiver_acc(), foreign_payment_id),
+            Err(ContractError::NotAuthorizedForPayment(
+                receiver_acc(),
+                foreign_payment_id
+            ))
+        );
+        assert_eq!(
+            contract.check_receiver_payment_id(&receiver_acc(), foreign_payment_id + 1),
+            Err(ContractError::PaymentIdNotExist(foreign_payment_id + 1))
+        );
+        assert_eq!(
+            contract.check_receiver_payment_id(&accounts(5), payment_id),
+            Err(ContractError::ReceiverAccountNotExist(accounts(5)))
+        );
+
+        assert_eq!(
+            contract.check_issue_payment_id(&issuer_acc(), payment_id),
+            Ok(())
+        );
+        assert_eq!(
+            contract.check_issue_payment_id(&issuer_acc(), foreign_payment_id),
+            Err(ContractError::NotAuthorizedForPayment(
+                issuer_acc(),
+                foreign_payment_id
+            ))
+        );
+        assert_eq!(
+            contract.check_issue_payment_id(&issuer_acc(), foreign_payment_id + 1),
+            Err(ContractError::PaymentIdNotExist(foreign_payment_id + 1))
+        );
+        assert_eq!(
+            contract.check_issue_payment_id(&receiver_acc(), payment_id),
+            Err(ContractError::IssuerAccountNotExist(receiver_acc()))
+        );
+    }
 }
@@ -0,0 +1,180 @@
+use super::PaymentContract;
+use crate::contract::PaymentContractExt;
+use crate::error::{ensure, ContractError};
+use crate::Result;
+use near_sdk::{assert_one_yocto, env, json_types::U64, near_bindgen, AccountId};
+
+#[near_bindgen]
+impl PaymentContract {
+    // the claims and the refunds of the receiver are sent to the payout account,
+    // setting the receiver account itself removes the payout address
+    #[payable]
+    #[handle_result]
+    pub fn set_payout_address(&mut self, payment_id: U64, payout: AccountId) -> Result<()> {
+        assert_one_yocto();
+
+        let caller = env::predecessor_account_id();
+        let payment_id = payment_id.0;
+
+        self.check_receiver_payment_id(&caller, payment_id)?;
+
+        let payment_receipt = self
+            .payment_info_ledger
+            .get_mut(&payment_id)
+            .ok_or(ContractError::PaymentIdNotExist(payment_id))?
+            .into_current_mut();
+
+        // the shares of the other receivers of the split stream are not affected
+        ensure!(
+            payment_receipt.receiver == caller,
+            ContractError::NotPrimaryReceiver(caller.clone(), payment_id),
+        );
+
+        payment_receipt.payout_address = (payout != caller).then_some(payout);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constants::NANOS_IN_DAY,
+        contract::general_impl::tests::{
+            contract_acc, create_payment, get_context, issuer_acc, receiver_acc,
+        },
+        public::{PaymentRole, ProcessStatus},
+    };
+
+    use super::*;
+    use near_sdk::{
+        json_types::U128,
+        test_utils::{accounts, get_created_receipts},
+        testing_env,
+    };
+
+    fn create_approved_payment(contract: &mut PaymentContract) -> u64 {
+        let payment_id = create_payment(contract, 10, 1);
+
+        testing_env!(get_context(receiver_acc(), 0));
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        payment_id
+    }
+
+    #[test]
+    fn test_claim_to_payout_address() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_approved_payment(&mut contract);
+
+        testing_env!(get_context(receiver_acc(), 1));
+        contract
+            .set_payout_address(U64(payment_id), accounts(4))
+            .unwrap();
+        assert_eq!(
+            contract
+                .get_payment(U64(payment_id))
+                .unwrap()
+                .payout_address,
+            Some(accounts(4))
+        );
+
+        let mut context = get_context(receiver_acc(), 1);
+        context.block_timestamp = NANOS_IN_DAY * 3;
+        testing_env!(context.clone());
+
+        assert_eq!(contract.claim_payment(U64(payment_id)), Ok(U128(3)));
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(4)));
+        assert!(get_created_receipts()
+            .iter()
+            .all(|receipt| receipt.receiver_id != receiver_acc()));
+    }
+
+    #[test]
+    fn test_reject_to_payout_address() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_approved_payment(&mut contract);
+
+        testing_env!(get_context(receiver_acc(), 1));
+        contract
+            .set_payout_address(U64(payment_id), accounts(4))
+            .unwrap();
+
+        // the accrued part of the rejected payment goes to the payout address
+        let mut context = get_context(receiver_acc(), 1);
+        context.block_timestamp = NANOS_IN_DAY * 3;
+        testing_env!(context.clone());
+
+        let result = contract
+            .reject_payment_receipt(U64(payment_id), PaymentRole::Receiver)
+            .unwrap();
+        assert_eq!(result.receiver_payout, U128(3));
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == accounts(4)));
+    }
+
+    #[test]
+    fn test_reset_payout_address() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_approved_payment(&mut contract);
+
+        testing_env!(get_context(receiver_acc(), 1));
+        contract
+            .set_payout_address(U64(payment_id), accounts(4))
+            .unwrap();
+        contract
+            .set_payout_address(U64(payment_id), receiver_acc())
+            .unwrap();
+        assert_eq!(
+            contract
+                .get_payment(U64(payment_id))
+                .unwrap()
+                .payout_address,
+            None
+        );
+
+        // only the receiver could set the payout address
+        testing_env!(get_context(issuer_acc(), 1));
+        assert_eq!(
+            contract.set_payout_address(U64(payment_id), accounts(4)),
+            Err(ContractError::ReceiverAccountNotExist(issuer_acc()))
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_set_payout_address_without_yocto() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_approved_payment(&mut contract);
+
+        testing_env!(get_context(receiver_acc(), 0));
+        contract
+            .set_payout_address(U64(payment_id), accounts(4))
+            .unwrap();
+    }
+}
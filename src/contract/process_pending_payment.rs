@@ -1,53 +1,157 @@
 use super::PaymentContract;
+use crate::constants::{MAX_PERIOD_DAYS, NANOS_IN_DAY};
 use crate::contract::PaymentContractExt;
-use crate::error::ContractError;
-use crate::public::ProcessStatus;
+use crate::error::{ensure, ContractError};
+use crate::events::{ContractEvent, PaymentApproved};
+use crate::public::completed_payment::CompletionReason;
+use crate::public::{PaymentRole, ProcessStatus};
 use crate::Result;
-use near_sdk::Promise;
-use near_sdk::{env, near_bindgen};
+use near_sdk::{assert_one_yocto, env, json_types::U64, near_bindgen};
 
 #[near_bindgen]
 impl PaymentContract {
+    #[handle_result]
+    fn approve_payment_impl(
+        &mut self,
+        payment_id: u64,
+        min_total_amount: Option<u128>,
+        start_date: u64,
+    ) -> Result<()> {
+        let caller = env::predecessor_account_id();
+
+        self.check_payment_unlocked(payment_id)?;
+
+        // check whether the caller of the method has particluar record with the payment_id in the receivers list
+        self.check_receiver_payment_id(&caller, payment_id)?;
+
+        let payment_receipt = self
+            .payment_info_ledger
+            .get_mut(&payment_id)
+            .ok_or(ContractError::PaymentIdNotExist(payment_id))?
+            .into_current_mut();
+
+        // only the primary receiver decides on the split stream
+        ensure!(
+            payment_receipt.receiver == caller,
+            ContractError::NotPrimaryReceiver(caller.clone(), payment_id),
+        );
+
+        ensure!(
+            payment_receipt.approval_required,
+            ContractError::ApprovalNotRequired(payment_id),
+        );
+
+        // a repeated approval should not restart the clock of the running payment stream
+        ensure!(
+            payment_receipt.payment_info.initiale_date.is_none(),
+            ContractError::PaymentAlreadyConfirmed(payment_id),
+        );
+
+        if let Some(min_total_amount) = min_total_amount {
+            let total_amount = payment_receipt.payment_info.total_amount;
+
+            ensure!(
+                total_amount >= min_total_amount,
+                ContractError::TotalAmountBelowMinimum(payment_id, total_amount, min_total_amount),
+            );
+        }
+
+        // the final payment could never be calculated for the stream which does not end within the u64 time range
+        let payment_info = &payment_receipt.payment_info;
+        ensure!(
+            payment_info.calculate_end_date(start_date).is_some(),
+            ContractError::PaymentEndDateOverflow(
+                payment_info.period_duration,
+                payment_info
+                    .total_amount
+                    .div_ceil(payment_info.payment_amount),
+            ),
+        );
+
+        // Need to start the clock to start the payment stream
+        payment_receipt.payment_info.initiale_date = Some(start_date);
+        payment_receipt.approved_at = Some(env::block_timestamp());
+
+        ContractEvent::PaymentApproved(vec![PaymentApproved {
+            payment_id: payment_id.into(),
+            created_at: payment_receipt.created_at.into(),
+            approved_at: env::block_timestamp().into(),
+            start_date: start_date.into(),
+        }])
+        .emit();
+
+        Ok(())
+    }
+
+    // the rejection moves the deposit, so it requires the full access key like the other value-moving methods
+    #[payable]
     #[handle_result]
     pub fn process_pending_payment(&mut self, process_status: ProcessStatus) -> Result<()> {
+        self.check_not_paused()?;
+
         match process_status {
             ProcessStatus::Approve(payment_id) => {
+                self.approve_payment_impl(payment_id.0, None, env::block_timestamp())?
+            }
+            ProcessStatus::ApproveWithMin(payment_id, min_total_amount) => self
+                .approve_payment_impl(
+                    payment_id.0,
+                    Some(min_total_amount.0),
+                    env::block_timestamp(),
+                )?,
+            // nothing is vested until the start date, which is bounded like the period duration
+            ProcessStatus::ApproveDelayed(payment_id, start_date) => {
+                let current_time = env::block_timestamp();
+                let max_start_date =
+                    current_time.saturating_add(MAX_PERIOD_DAYS.saturating_mul(NANOS_IN_DAY));
+
+                ensure!(
+                    start_date.0 > current_time && start_date.0 <= max_start_date,
+                    ContractError::InvalidStartDate(start_date.0),
+                );
+
+                self.approve_payment_impl(payment_id.0, None, start_date.0)?
+            }
+            ProcessStatus::Reject(payment_id) => {
+                assert_one_yocto();
+
                 let payment_id = payment_id.0;
                 let caller = env::predecessor_account_id();
 
-                // check whether the caller of the method has particluar record with the payment_id in the receivers list
-                self.check_reciever_payment_id(&caller, payment_id)?;
+                self.check_payment_unlocked(payment_id)?;
 
-                let payment_receipt = self
-                    .payment_info_ledger
-                    .get_mut(&payment_id)
-                    .ok_or_else(|| ContractError::PaymentIdNotExist(payment_id))?
-                    .into_current_mut();
+                let payment_receipt = self.load_payment_receipt(payment_id)?;
 
-                // Need to start the clock to start the payment stream
-                payment_receipt.payment_info.initiale_date = Some(env::block_timestamp());
-            }
-            ProcessStatus::Reject(payment_id) => {
-                let payment_id = payment_id.0;
-                let caller = env::predecessor_account_id();
+                // the stream without the approval or the approved one is already running, so the claimed
+                // and the vested amounts are paid to the receiver and only the unvested remainder is refunded
+                if !payment_receipt.approval_required
+                    || payment_receipt.payment_info.initiale_date.is_some()
+                {
+                    self.reject_payment_receipt(U64(payment_id), PaymentRole::Receiver)?;
+                    return Ok(());
+                }
 
-                let payment_receipt = self
-                    .payment_info_ledger
-                    .get_mut(&payment_id)
-                    .ok_or_else(|| ContractError::PaymentIdNotExist(payment_id))?
-                    .into_current();
+                self.check_receiver_payment_id(&caller, payment_id)?;
+
+                // only the primary receiver decides on the split stream
+                ensure!(
+                    payment_receipt.receiver == caller,
+                    ContractError::NotPrimaryReceiver(caller.clone(), payment_id),
+                );
 
                 let issuer = payment_receipt.issuer.clone();
                 let total_amount = payment_receipt.payment_info.total_amount;
 
-                self.remove_payment_related_data(&issuer, &caller, payment_id)?;
+                self.remove_payment_related_data(
+                    &issuer,
+                    &caller,
+                    payment_id,
+                    CompletionReason::RejectedByReceiver,
+                    total_amount,
+                )?;
 
-                // making the refund
-                // TODO This transaction could possibly fail because issuer account could be deleted at the time of refund, should be additionally handled,
-                // this will require additional logic and fields for the smart-contract struct. As a very simple example we could have additional
-                // mapping for AccountId and the Balance which would represent stuck costs because the account was deleted, but no gurantees that the same user
-                // will restore the access to the account with particular name, so that this issue is rather complex from the business point of view
-                Promise::new(issuer).transfer(total_amount);
+                // making the refund, it is kept for the issuer when the account is deleted
+                self.transfer_with_fallback(issuer, total_amount);
             }
         }
         Ok(())
@@ -57,13 +161,15 @@ impl PaymentContract {
 #[cfg(test)]
 mod tests {
     use crate::contract::general_impl::tests::{
-        contract_acc, create_payment, get_context, issuer_acc, receiver_acc,
+        check_all_account_data_removed, check_all_data_removed, contract_acc, create_payment,
+        get_context, issuer_acc, receiver_acc, set_block_timestamp,
     };
     use crate::error::ContractError;
+    use crate::public::CreatePaymentOptions;
 
     use super::*;
-    use near_sdk::json_types::U64;
-    use near_sdk::testing_env;
+    use near_sdk::json_types::{U128, U64};
+    use near_sdk::{test_utils::get_logs, testing_env};
 
     #[test]
     fn test_approve_payment() {
@@ -92,7 +198,7 @@ mod tests {
     }
 
     #[test]
-    fn test_reject_payment() {
+    fn test_approve_payment_twice() {
         // set contract as an account of contract
         let mut context = get_context(contract_acc(), 1);
         context.current_account_id = contract_acc();
@@ -103,10 +209,210 @@ mod tests {
         // create a payment
         let payment_id = create_payment(&mut contract, 1, 1);
 
+        // set caller to receiver
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+
+        // approve the payment
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        // approve the payment once again later
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 100;
+        testing_env!(context.clone());
+
+        let res = contract.process_pending_payment(ProcessStatus::Approve(U64(payment_id)));
+        assert_eq!(res, Err(ContractError::PaymentAlreadyConfirmed(payment_id)));
+
+        // check that the payment stream clock was not restarted
+        let payment = contract.payment_info_ledger.get(&payment_id).unwrap();
+        assert_eq!(payment.into_current().payment_info.initiale_date, Some(1));
+    }
+
+    #[test]
+    fn test_approve_payment_with_min_total_amount() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // create a payment
+        let payment_id = create_payment(&mut contract, 10, 1);
+
         // set caller to receiver
         let context = get_context(receiver_acc(), 0);
         testing_env!(context.clone());
 
+        // the payment is below the expected minimum
+        let res = contract
+            .process_pending_payment(ProcessStatus::ApproveWithMin(U64(payment_id), U128(11)));
+        assert_eq!(
+            res,
+            Err(ContractError::TotalAmountBelowMinimum(payment_id, 10, 11))
+        );
+
+        let payment = contract.payment_info_ledger.get(&payment_id).unwrap();
+        assert!(payment.into_current().payment_info.initiale_date.is_none());
+
+        // the payment is exactly at the expected minimum
+        contract
+            .process_pending_payment(ProcessStatus::ApproveWithMin(U64(payment_id), U128(10)))
+            .unwrap();
+
+        let payment = contract.payment_info_ledger.get(&payment_id).unwrap();
+        assert!(payment.into_current().payment_info.initiale_date.is_some());
+    }
+
+    #[test]
+    fn test_approve_payment_delayed_unreachable_end_date() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // 5 of the longest periods fit into u64 only when the stream is started soon enough
+        testing_env!(get_context(issuer_acc(), 5));
+        let payment_id = contract
+            .create_payment(U64(MAX_PERIOD_DAYS), U128(1), receiver_acc(), None)
+            .unwrap()
+            .0;
+
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+
+        // the start date far in the future is refused before the end date is checked
+        assert_eq!(
+            contract.process_pending_payment(ProcessStatus::ApproveDelayed(
+                U64(payment_id),
+                U64(u64::MAX - 1),
+            )),
+            Err(ContractError::InvalidStartDate(u64::MAX - 1))
+        );
+
+        let max_start_date = 1 + MAX_PERIOD_DAYS * NANOS_IN_DAY;
+        assert_eq!(
+            contract.process_pending_payment(ProcessStatus::ApproveDelayed(
+                U64(payment_id),
+                U64(max_start_date + 1),
+            )),
+            Err(ContractError::InvalidStartDate(max_start_date + 1))
+        );
+
+        // the allowed start date is refused when the stream could not end within the u64 time range
+        assert_eq!(
+            contract.process_pending_payment(ProcessStatus::ApproveDelayed(
+                U64(payment_id),
+                U64(max_start_date),
+            )),
+            Err(ContractError::PaymentEndDateOverflow(
+                MAX_PERIOD_DAYS * NANOS_IN_DAY,
+                5
+            ))
+        );
+        assert_eq!(
+            contract
+                .payment_info_ledger
+                .get(&payment_id)
+                .unwrap()
+                .into_current()
+                .payment_info
+                .initiale_date,
+            None
+        );
+
+        // the stream started now is reachable
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_approve_payment_delayed() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // create a payment
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        // set caller to receiver
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = NANOS_IN_DAY;
+        testing_env!(context.clone());
+
+        // the start date should be in the future
+        let res = contract.process_pending_payment(ProcessStatus::ApproveDelayed(
+            U64(payment_id),
+            U64(NANOS_IN_DAY),
+        ));
+        assert_eq!(res, Err(ContractError::InvalidStartDate(NANOS_IN_DAY)));
+
+        let start_date = NANOS_IN_DAY * 5;
+        contract
+            .process_pending_payment(ProcessStatus::ApproveDelayed(
+                U64(payment_id),
+                U64(start_date),
+            ))
+            .unwrap();
+
+        let payment = contract.payment_info_ledger.get(&payment_id).unwrap();
+        assert_eq!(
+            payment.into_current().payment_info.initiale_date,
+            Some(start_date)
+        );
+
+        // the approval time is kept separately from the start of the stream
+        assert_eq!(payment.into_current().created_at, 0);
+        assert_eq!(payment.into_current().approved_at, Some(NANOS_IN_DAY));
+        assert_eq!(
+            get_logs().last().unwrap(),
+            &format!(
+                r#"EVENT_JSON:{{"standard":"near_payment_receiver","version":"1.0.0","event":"payment_approved","data":[{{"payment_id":"{}","created_at":"0","approved_at":"{}","start_date":"{}"}}]}}"#,
+                payment_id, NANOS_IN_DAY, start_date
+            )
+        );
+
+        // nothing is vested before the start date
+        set_block_timestamp(NANOS_IN_DAY * 5 - 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(0)
+        );
+
+        set_block_timestamp(start_date + NANOS_IN_DAY * 2);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(2)
+        );
+    }
+
+    #[test]
+    fn test_reject_payment() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // create a payment
+        let payment_id = create_payment(&mut contract, 1, 1);
+
+        // set caller to receiver
+        let context = get_context(receiver_acc(), 1);
+        testing_env!(context.clone());
+
         // reject the payment
         contract
             .process_pending_payment(ProcessStatus::Reject(U64(payment_id)))
@@ -130,7 +436,7 @@ mod tests {
         let payment_id = create_payment(&mut contract, 1, 1);
 
         // set caller to issuer which is not allowed, only receiver can call this method
-        let context = get_context(issuer_acc(), 0);
+        let context = get_context(issuer_acc(), 1);
         testing_env!(context.clone());
 
         // reject the payment
@@ -155,7 +461,7 @@ mod tests {
         let payment_id = create_payment(&mut contract, 1, 1);
 
         // set caller to receiver
-        let context = get_context(receiver_acc(), 0);
+        let context = get_context(receiver_acc(), 1);
         testing_env!(context.clone());
 
         // reject the payment
@@ -172,4 +478,138 @@ mod tests {
 
         assert_eq!(res, Err(ContractError::PaymentIdNotExist(payment_id)));
     }
+
+    #[test]
+    fn test_payment_without_approval() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let mut context = get_context(issuer_acc(), 10);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+
+        // there is no approval step to be limited by the deadline
+        assert_eq!(
+            contract.create_payment_without_approval(
+                U64(1),
+                U128(1),
+                receiver_acc(),
+                Some(CreatePaymentOptions {
+                    approval_deadline: Some(U64(NANOS_IN_DAY)),
+                    ..Default::default()
+                })
+            ),
+            Err(ContractError::CreateOptionNotSupported(
+                "approval_deadline".to_string()
+            ))
+        );
+
+        let payment_id = contract
+            .create_payment_without_approval(U64(1), U128(1), receiver_acc(), None)
+            .unwrap()
+            .0;
+
+        // the stream is started at the creation
+        let payment_receipt = contract.get_payment(U64(payment_id)).unwrap();
+        assert!(!payment_receipt.approval_required);
+        assert_eq!(payment_receipt.created_at, U64(1));
+        assert_eq!(payment_receipt.payment_info.initiale_date, Some(U64(1)));
+
+        let context = get_context(receiver_acc(), 0);
+        testing_env!(context.clone());
+        assert_eq!(
+            contract.process_pending_payment(ProcessStatus::Approve(U64(payment_id))),
+            Err(ContractError::ApprovalNotRequired(payment_id))
+        );
+
+        // the receiver claims without the approval
+        set_block_timestamp(NANOS_IN_DAY * 3 + 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(3)
+        );
+
+        // the rejection pays the vested amount and refunds the rest to the issuer
+        let mut context = get_context(receiver_acc(), 1);
+        context.block_timestamp = NANOS_IN_DAY * 5 + 1;
+        testing_env!(context.clone());
+        contract
+            .process_pending_payment(ProcessStatus::Reject(U64(payment_id)))
+            .unwrap();
+
+        check_all_account_data_removed(&contract, payment_id);
+        assert_eq!(
+            contract
+                .get_completed_payment(U64(payment_id))
+                .unwrap()
+                .completion_reason,
+            CompletionReason::RejectedByReceiver
+        );
+    }
+
+    #[test]
+    fn test_reject_approved_payment_after_claim() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let payment_id = create_payment(&mut contract, 10, 1);
+        let other_payment_id = create_payment(&mut contract, 10, 1);
+
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+        for payment_id in [payment_id, other_payment_id] {
+            contract
+                .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+                .unwrap();
+        }
+
+        set_block_timestamp(NANOS_IN_DAY * 3 + 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(3)
+        );
+        assert_eq!(contract.get_total_locked_near(), U128(17));
+
+        // only the unvested remainder is refunded, the deposit of the other stream stays locked
+        let mut context = get_context(receiver_acc(), 1);
+        context.block_timestamp = NANOS_IN_DAY * 5 + 1;
+        testing_env!(context.clone());
+        contract
+            .process_pending_payment(ProcessStatus::Reject(U64(payment_id)))
+            .unwrap();
+
+        check_all_data_removed(&contract, payment_id);
+        assert_eq!(contract.get_total_locked_near(), U128(10));
+        assert!(contract.is_payment_active(U64(other_payment_id)));
+        assert_eq!(
+            contract
+                .get_completed_payment(U64(payment_id))
+                .unwrap()
+                .completion_reason,
+            CompletionReason::RejectedByReceiver
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_reject_pending_payment_without_yocto() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        testing_env!(get_context(receiver_acc(), 0));
+        let _ = contract.process_pending_payment(ProcessStatus::Reject(U64(1)));
+    }
 }
@@ -0,0 +1,122 @@
+use super::PaymentContract;
+use crate::contract::PaymentContractExt;
+use crate::public::receiver_prefs::{CurrentReceiverPrefs, ReceiverPrefs};
+use near_sdk::{assert_one_yocto, env, near_bindgen, AccountId};
+
+#[near_bindgen]
+impl PaymentContract {
+    // the receiver opts out to stop the unsolicited payments, the existing payments are not affected
+    #[payable]
+    pub fn set_accepting_payments(&mut self, accepting: bool) {
+        assert_one_yocto();
+        let caller = env::predecessor_account_id();
+
+        match self.receiver_prefs.get_mut(&caller) {
+            Some(prefs) => prefs.into_current_mut().accepting_payments = accepting,
+            None => {
+                self.receiver_prefs.insert(
+                    caller,
+                    ReceiverPrefs::from(CurrentReceiverPrefs {
+                        accepting_payments: accepting,
+                    }),
+                );
+            }
+        }
+    }
+
+    pub fn get_receiver_prefs(&self, account_id: AccountId) -> CurrentReceiverPrefs {
+        self.receiver_prefs
+            .get(&account_id)
+            .map(|prefs| prefs.into_current().into_owned())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        contract::general_impl::tests::{
+            contract_acc, create_payment, get_context, issuer_acc, receiver_acc,
+        },
+        error::ContractError,
+    };
+
+    use super::*;
+    use near_sdk::{
+        json_types::{U128, U64},
+        testing_env,
+    };
+
+    #[test]
+    fn test_accepting_payments_by_default() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        assert_eq!(
+            contract.get_receiver_prefs(receiver_acc()),
+            CurrentReceiverPrefs {
+                accepting_payments: true,
+            }
+        );
+
+        create_payment(&mut contract, 10, 1);
+    }
+
+    #[test]
+    fn test_set_accepting_payments() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        // the receiver opts out
+        testing_env!(get_context(receiver_acc(), 1));
+        contract.set_accepting_payments(false);
+        assert!(
+            !contract
+                .get_receiver_prefs(receiver_acc())
+                .accepting_payments
+        );
+
+        testing_env!(get_context(issuer_acc(), 10));
+        assert_eq!(
+            contract.create_payment(U64(1), U128(1), receiver_acc(), None),
+            Err(ContractError::ReceiverNotAcceptingPayments(receiver_acc()))
+        );
+
+        // the existing payment is kept
+        assert!(contract.payment_exists(U64(payment_id)));
+
+        // the receiver opts in again
+        testing_env!(get_context(receiver_acc(), 1));
+        contract.set_accepting_payments(true);
+        assert!(
+            contract
+                .get_receiver_prefs(receiver_acc())
+                .accepting_payments
+        );
+
+        create_payment(&mut contract, 10, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_set_accepting_payments_without_yocto() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        testing_env!(get_context(receiver_acc(), 0));
+        contract.set_accepting_payments(false);
+    }
+}
@@ -1,25 +1,44 @@
 use super::PaymentContract;
+use crate::constants::{
+    BULK_OPERATION_GAS_FLOOR, GAS_FOR_TRANSFER, GAS_FOR_TRANSFER_CALLBACK, NANOS_IN_DAY,
+};
 use crate::contract::PaymentContractExt;
-use crate::error::ContractError;
+use crate::error::{ensure, ContractError};
+use crate::events::{ContractEvent, PaymentForceSettled};
+use crate::public::completed_payment::CompletionReason;
 use crate::public::payment_info::PaymentStatus;
-use crate::public::PaymentRole;
+use crate::public::payment_receipt::{CurrentUserVersion, ReceiptState};
+use crate::public::{PaymentRole, RejectPaymentResult};
 use crate::Result;
-use near_sdk::{env, json_types::U64, near_bindgen};
-use near_sdk::{AccountId, Promise};
+use near_sdk::{
+    assert_one_yocto, env,
+    json_types::{U128, U64},
+    near_bindgen,
+};
+use near_sdk::{AccountId, Gas, Promise, PromiseResult};
 
 #[derive(PartialEq, Debug)]
-struct RepaymentInfo {
+pub(crate) struct RepaymentInfo {
     pub issuer_data: (AccountId, u128),
-    pub receiver_data: (AccountId, u128),
+    pub receiver_data: Vec<(AccountId, u128)>,
 }
 
 impl RepaymentInfo {
     pub fn new(issuer: AccountId, receiver: AccountId) -> Self {
         RepaymentInfo {
             issuer_data: (issuer, 0),
-            receiver_data: (receiver, 0),
+            receiver_data: vec![(receiver, 0)],
         }
     }
+
+    // everything paid out to the issuer and the receivers
+    pub fn total_amount(&self) -> Option<u128> {
+        self.receiver_data
+            .iter()
+            .try_fold(self.issuer_data.1, |total, (_, amount)| {
+                total.checked_add(*amount)
+            })
+    }
 }
 
 #[near_bindgen]
@@ -32,27 +51,52 @@ impl PaymentContract {
         role: PaymentRole,
     ) -> Result<()> {
         match role {
-            PaymentRole::Issuer => self.check_issue_payment_id(&caller, payment_id),
-            PaymentRole::Receiver => self.check_reciever_payment_id(&caller, payment_id),
+            PaymentRole::Issuer => self.check_issue_payment_id(caller, payment_id),
+            PaymentRole::Receiver => self.check_receiver_payment_id(caller, payment_id),
         }
     }
 
     #[handle_result]
-    fn reject_payment_receipt_impl(&mut self, payment_id: u64) -> Result<RepaymentInfo> {
+    pub(crate) fn reject_payment_receipt_impl(
+        &mut self,
+        payment_id: u64,
+        completion_reason: CompletionReason,
+    ) -> Result<RepaymentInfo> {
         let payment_receipt = self
             .payment_info_ledger
             .get_mut(&payment_id)
-            .ok_or_else(|| ContractError::PaymentIdNotExist(payment_id))?
+            .ok_or(ContractError::PaymentIdNotExist(payment_id))?
             .into_current_mut();
 
-        let payment_info = &mut payment_receipt.payment_info;
-
-        let payment_status = payment_info.calculate_payment_status(payment_id)?;
-
         let issuer = payment_receipt.issuer.clone();
         let receiver = payment_receipt.receiver.clone();
+        let settlement_time = payment_receipt.settlement_time(env::block_timestamp());
+
+        if !payment_receipt.receivers.is_empty() {
+            let repayment_info =
+                split_repayment_info(payment_receipt, payment_id, settlement_time)?;
+            let refunded = repayment_info
+                .total_amount()
+                .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+            self.remove_payment_related_data(
+                &issuer,
+                &receiver,
+                payment_id,
+                completion_reason,
+                refunded,
+            )?;
+
+            return Ok(repayment_info);
+        }
+
+        let mut repayment_info =
+            RepaymentInfo::new(issuer.clone(), payment_receipt.payout_account(&receiver));
 
-        let mut repayment_info = RepaymentInfo::new(issuer.clone(), receiver.clone());
+        let payment_info = &mut payment_receipt.payment_info;
+
+        let payment_status =
+            payment_info.calculate_payment_status_impl(payment_id, settlement_time)?;
 
         match payment_status {
             PaymentStatus::Absent => {
@@ -61,60 +105,315 @@ impl PaymentContract {
                 repayment_info.issuer_data.1 = remainder_amount;
             }
             PaymentStatus::PaymentReady(amount) => {
-                repayment_info.receiver_data.1 = amount;
-                repayment_info.issuer_data.1 = payment_info
-                    .total_amount
-                    .checked_sub(amount)
-                    .ok_or_else(|| ContractError::InternalCalculationError(payment_id))?;
+                payment_info.register_claim(payment_id, amount, settlement_time)?;
+                repayment_info.receiver_data[0].1 = amount;
+                repayment_info.issuer_data.1 =
+                    payment_info.calculate_remainder_amount(payment_id)?;
             }
             PaymentStatus::FinalPayment(amount) => {
-                repayment_info.receiver_data.1 = amount;
+                payment_info.register_claim(payment_id, amount, settlement_time)?;
+                repayment_info.receiver_data[0].1 = amount;
             }
         }
 
-        self.remove_payment_related_data(&issuer, &receiver, payment_id)?;
+        let refunded = repayment_info
+            .total_amount()
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        self.remove_payment_related_data(
+            &issuer,
+            &receiver,
+            payment_id,
+            completion_reason,
+            refunded,
+        )?;
 
         Ok(repayment_info)
     }
 
+    #[payable]
     #[handle_result]
-    pub fn reject_payment_receipt(&mut self, payment_id: U64, role: PaymentRole) -> Result<()> {
+    pub fn reject_payment_receipt(
+        &mut self,
+        payment_id: U64,
+        role: PaymentRole,
+    ) -> Result<RejectPaymentResult> {
+        assert_one_yocto();
+        // the issuer refund and the receiver payout are transferred together
+        self.check_prepaid_gas(Gas(GAS_FOR_TRANSFER.0 * 2 + GAS_FOR_TRANSFER_CALLBACK.0))?;
         let caller = env::predecessor_account_id();
         let payment_id = payment_id.0;
 
+        let completion_reason = match role {
+            PaymentRole::Issuer => CompletionReason::RejectedByIssuer,
+            PaymentRole::Receiver => CompletionReason::RejectedByReceiver,
+        };
+
+        self.check_payment_unlocked(payment_id)?;
         self.check_role_exist(&caller, payment_id, role)?;
 
-        // TODO Particular transfers could possibly fail because the transfee account could be deleted, need to be somehow handled
+        // only the primary receiver decides on the split stream
+        if completion_reason == CompletionReason::RejectedByReceiver {
+            ensure!(
+                self.load_payment_receipt(payment_id)?.receiver == caller,
+                ContractError::NotPrimaryReceiver(caller.clone(), payment_id),
+            );
+        }
+
+        // the receiver keeps claiming the started stream until the cancellation is effective
+        if completion_reason == CompletionReason::RejectedByIssuer {
+            let payment_receipt = self.load_payment_receipt(payment_id)?;
+
+            ensure!(
+                payment_receipt.state == ReceiptState::Active,
+                ContractError::CancellationAlreadyPending(payment_id),
+            );
+
+            if self.issuer_cancellation_notice_days > 0
+                && payment_receipt.payment_info.initiale_date.is_some()
+            {
+                return self.request_cancellation(payment_id);
+            }
+        }
+
+        let repayment_info = self.reject_payment_receipt_impl(payment_id, completion_reason)?;
+
+        self.pay_out_repayment(payment_id, repayment_info)
+    }
+
+    // rejects every pending payment of the issuer to the caller, the whole amounts are returned by one transfer.
+    // the payments left over the gas floor are rejected by the next call
+    #[payable]
+    #[handle_result]
+    pub fn reject_all_from(&mut self, issuer: AccountId) -> Result<U64> {
+        assert_one_yocto();
+        let caller = env::predecessor_account_id();
+
+        // collected before the removal, because the receiver ledger is changed by it
+        let pending_payments: Vec<(u64, u128)> = self
+            .receiver_ledger
+            .get(&caller)
+            .map(|payment_ids| {
+                payment_ids
+                    .iter()
+                    .filter_map(|payment_id| {
+                        let payment_receipt =
+                            self.payment_info_ledger.get(payment_id)?.into_current();
+
+                        // only the primary receiver decides on the split stream
+                        (payment_receipt.issuer == issuer
+                            && payment_receipt.receiver == caller
+                            && payment_receipt.payment_info.initiale_date.is_none())
+                        .then(|| (*payment_id, payment_receipt.payment_info.total_amount))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let unprocessed_ids = self.remove_payments_bulk(
+            &issuer,
+            pending_payments.clone(),
+            CompletionReason::RejectedByReceiver,
+            BULK_OPERATION_GAS_FLOOR,
+        )?;
+
+        let rejected_payments = &pending_payments[..pending_payments.len() - unprocessed_ids.len()];
+        let refund_amount: u128 = rejected_payments
+            .iter()
+            .map(|(_, total_amount)| total_amount)
+            .sum();
+
+        self.transfer_with_fallback(issuer, refund_amount);
+
+        Ok(U64(rejected_payments.len() as u64))
+    }
+
+    // settles the stream abandoned by both parties with the same split as the rejection,
+    // allowed only when the grace period after the end date is passed
+    #[payable]
+    #[handle_result]
+    pub fn force_settle(&mut self, payment_id: U64) -> Result<RejectPaymentResult> {
+        assert_one_yocto();
+        self.assert_owner()?;
+
+        let payment_id = payment_id.0;
+        self.check_payment_unlocked(payment_id)?;
+
+        let payment_receipt = self.load_payment_receipt(payment_id)?;
+        let payment_info = &payment_receipt.payment_info;
+
+        let initiale_date = payment_info
+            .initiale_date
+            .ok_or(ContractError::PaymentReceiptNotConfirmed(payment_id))?;
+
+        let settlement_date = payment_info
+            .calculate_end_date(initiale_date)
+            .and_then(|end_date| {
+                self.abandonment_grace_days
+                    .checked_mul(NANOS_IN_DAY)
+                    .and_then(|grace_period| end_date.checked_add(grace_period))
+            })
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        ensure!(
+            env::block_timestamp() >= settlement_date,
+            ContractError::SettlementTooEarly(payment_id, settlement_date),
+        );
+
+        let repayment_info =
+            self.reject_payment_receipt_impl(payment_id, CompletionReason::ForceSettled)?;
+        let result = self.pay_out_repayment(payment_id, repayment_info)?;
+
+        ContractEvent::PaymentForceSettled(vec![PaymentForceSettled {
+            payment_id: payment_id.into(),
+            issuer_refund: result.issuer_refund,
+            receiver_payout: result.receiver_payout,
+        }])
+        .emit();
+
+        Ok(result)
+    }
+
+    #[handle_result]
+    pub(crate) fn pay_out_repayment(
+        &mut self,
+        payment_id: u64,
+        repayment_info: RepaymentInfo,
+    ) -> Result<RejectPaymentResult> {
         let RepaymentInfo {
             issuer_data,
             receiver_data,
-        } = self.reject_payment_receipt_impl(payment_id)?;
+        } = repayment_info;
+
+        let issuer_refund = issuer_data.1;
+        let mut transfers = vec![];
+
+        if issuer_refund > 0 {
+            transfers.push(issuer_data);
+        }
+
+        let mut receiver_payout = 0;
 
-        if issuer_data.1 > 0 {
-            Promise::new(issuer_data.0).transfer(issuer_data.1);
+        for (receiver, amount) in receiver_data {
+            if amount == 0 {
+                continue;
+            }
+
+            let amount = self.charge_payout_fee(payment_id, &receiver, amount)?;
+            receiver_payout += amount;
+
+            if amount > 0 {
+                transfers.push((receiver, amount));
+            }
         }
 
-        if receiver_data.1 > 0 {
-            Promise::new(receiver_data.0).transfer(receiver_data.1);
+        // particular transfers could fail because the account could be deleted, the callback keeps them in the contract
+        let joint_transfer = transfers
+            .iter()
+            .map(|(account_id, amount)| Promise::new(account_id.clone()).transfer(*amount))
+            .reduce(Promise::and);
+
+        if let Some(joint_transfer) = joint_transfer {
+            joint_transfer.then(
+                Self::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_TRANSFER_CALLBACK)
+                    .on_reject_transfer(
+                        U64(payment_id),
+                        transfers
+                            .into_iter()
+                            .map(|(account_id, amount)| (account_id, U128(amount)))
+                            .collect(),
+                    ),
+            );
         }
 
-        Ok(())
+        Ok(RejectPaymentResult {
+            issuer_refund: issuer_refund.into(),
+            receiver_payout: receiver_payout.into(),
+        })
+    }
+
+    // the results of the joint transfer are in the same order as the transfers
+    #[private]
+    pub fn on_reject_transfer(&mut self, payment_id: U64, transfers: Vec<(AccountId, U128)>) {
+        for (index, (account_id, amount)) in transfers.into_iter().enumerate() {
+            if let PromiseResult::Successful(_) = env::promise_result(index as u64) {
+                continue;
+            }
+
+            env::log_str(&format!(
+                "Transfer of {} to {} for the payment id {} is failed",
+                amount.0, account_id, payment_id.0
+            ));
+            self.store_failed_transfer(account_id, amount.0);
+        }
     }
 }
 
+// every receiver of the split stream gets the weighted share of the periods passed since the last own claim,
+// the issuer gets back everything which is not paid to the receivers
+fn split_repayment_info(
+    payment_receipt: &CurrentUserVersion,
+    payment_id: u64,
+    settlement_time: u64,
+) -> Result<RepaymentInfo> {
+    let mut receiver_data = vec![];
+    let mut distributed_amount: u128 = 0;
+
+    for share in payment_receipt.receivers.iter() {
+        let payment_info = share.payment_info(&payment_receipt.payment_info);
+
+        let amount =
+            match payment_info.calculate_payment_status_impl(payment_id, settlement_time)? {
+                PaymentStatus::Absent => 0,
+                PaymentStatus::PaymentReady(amount) | PaymentStatus::FinalPayment(amount) => amount,
+            };
+
+        let share_amount = share
+            .weighted_payout(amount)
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        distributed_amount = share
+            .claimed_amount
+            .checked_add(amount)
+            .and_then(|value| share.weighted_amount(value))
+            .and_then(|value| distributed_amount.checked_add(value))
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        receiver_data.push((
+            payment_receipt.payout_account(&share.receiver),
+            share_amount,
+        ));
+    }
+
+    let issuer_amount = payment_receipt
+        .payment_info
+        .total_amount
+        .checked_sub(distributed_amount)
+        .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+    Ok(RepaymentInfo {
+        issuer_data: (payment_receipt.issuer.clone(), issuer_amount),
+        receiver_data,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{
         constants::NANOS_IN_DAY,
         contract::general_impl::tests::{
-            check_all_data_removed, contract_acc, create_payment, get_context, issuer_acc,
+            check_all_account_data_removed, contract_acc, create_payment, get_context, issuer_acc,
             receiver_acc, set_block_timestamp,
         },
-        public::ProcessStatus,
+        public::{payment_info::VestingMode, CreatePaymentOptions, ProcessStatus},
     };
 
     use super::*;
-    use near_sdk::testing_env;
+    use near_sdk::{
+        test_utils::{accounts, get_logs},
+        testing_env, RuntimeFeesConfig, VMConfig,
+    };
 
     #[test]
     fn test_check_roles_exist() {
@@ -213,14 +512,15 @@ mod tests {
         set_block_timestamp(NANOS_IN_DAY / 2);
 
         // reject payment when payment when payment is absent
-        let result = contract.reject_payment_receipt_impl(payment_id).unwrap();
+        let result = contract
+            .reject_payment_receipt_impl(payment_id, CompletionReason::RejectedByReceiver)
+            .unwrap();
         assert_eq!(result.issuer_data.0, issuer_acc());
         assert_eq!(result.issuer_data.1, 10);
-        assert_eq!(result.receiver_data.0, receiver_acc());
-        assert_eq!(result.receiver_data.1, 0);
+        assert_eq!(result.receiver_data, vec![(receiver_acc(), 0)]);
 
         // check that the payment has been removed from all storages
-        check_all_data_removed(&contract, payment_id);
+        check_all_account_data_removed(&contract, payment_id);
     }
 
     #[test]
@@ -248,14 +548,15 @@ mod tests {
         // we set to the fifth day(period is one day, period_amount is 1token, so we will claim 5 tokens)
         set_block_timestamp(NANOS_IN_DAY * 5 + 1);
         // reject payment when payment when payment is ready
-        let result = contract.reject_payment_receipt_impl(payment_id).unwrap();
+        let result = contract
+            .reject_payment_receipt_impl(payment_id, CompletionReason::RejectedByReceiver)
+            .unwrap();
         assert_eq!(result.issuer_data.0, issuer_acc());
         assert_eq!(result.issuer_data.1, 5);
-        assert_eq!(result.receiver_data.0, receiver_acc());
-        assert_eq!(result.receiver_data.1, 5);
+        assert_eq!(result.receiver_data, vec![(receiver_acc(), 5)]);
 
         // check that the payment has been removed from all storages
-        check_all_data_removed(&contract, payment_id);
+        check_all_account_data_removed(&contract, payment_id);
     }
 
     #[test]
@@ -283,14 +584,15 @@ mod tests {
         // we set to the final 10th day after the start day
         set_block_timestamp(NANOS_IN_DAY * 10 + 1);
         // reject payment when payment when payment is final
-        let result = contract.reject_payment_receipt_impl(payment_id).unwrap();
+        let result = contract
+            .reject_payment_receipt_impl(payment_id, CompletionReason::RejectedByReceiver)
+            .unwrap();
         assert_eq!(result.issuer_data.0, issuer_acc());
         assert_eq!(result.issuer_data.1, 0);
-        assert_eq!(result.receiver_data.0, receiver_acc());
-        assert_eq!(result.receiver_data.1, 10);
+        assert_eq!(result.receiver_data, vec![(receiver_acc(), 10)]);
 
         // check that the payment has been removed from all storages
-        check_all_data_removed(&contract, payment_id);
+        check_all_account_data_removed(&contract, payment_id);
     }
 
     #[test]
@@ -302,7 +604,467 @@ mod tests {
 
         let mut contract = PaymentContract::new().unwrap();
         // reject payment when payment when payment is final
-        let result = contract.reject_payment_receipt_impl(1);
+        let result = contract.reject_payment_receipt_impl(1, CompletionReason::RejectedByReceiver);
         assert_eq!(result, Err(ContractError::PaymentIdNotExist(1)));
     }
+
+    #[test]
+    fn test_reject_payment_receipt_returns_split() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // create a payment
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        // set caller to receiver
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+
+        // approve the payment
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        // we set to the fourth day(period is one day, period_amount is 1token, so receiver gets 4 tokens)
+        let mut context = get_context(receiver_acc(), 1);
+        context.block_timestamp = NANOS_IN_DAY * 4 + 1;
+        testing_env!(context.clone());
+
+        let result = contract
+            .reject_payment_receipt(U64(payment_id), PaymentRole::Receiver)
+            .unwrap();
+        assert_eq!(
+            result,
+            RejectPaymentResult {
+                issuer_refund: U128(6),
+                receiver_payout: U128(4),
+            }
+        );
+
+        // check that the payment has been removed from all storages
+        check_all_account_data_removed(&contract, payment_id);
+    }
+
+    #[test]
+    fn test_reject_payment_receipt_partial_final_period() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // 100 tokens are streamed by 30 tokens per day, the last day pays 10 tokens
+        let payment_id = create_payment(&mut contract, 100, 30);
+
+        // set caller to receiver
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+
+        // approve the payment
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        // the receiver claims 2 periods
+        set_block_timestamp(NANOS_IN_DAY * 2 + 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(60)
+        );
+
+        // reject in the middle of the final period, only the third period is vested
+        set_block_timestamp(NANOS_IN_DAY * 7 / 2);
+        let result = contract
+            .reject_payment_receipt_impl(payment_id, CompletionReason::RejectedByReceiver)
+            .unwrap();
+        assert_eq!(result.issuer_data, (issuer_acc(), 10));
+        assert_eq!(result.receiver_data, vec![(receiver_acc(), 30)]);
+
+        // check that the payment has been removed from all storages
+        check_all_account_data_removed(&contract, payment_id);
+    }
+
+    #[test]
+    fn test_reject_linear_payment_receipt() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // 100 tokens are vested continuously during 10 days
+        testing_env!(get_context(issuer_acc(), 100));
+        let payment_id = contract
+            .create_payment(
+                U64(1),
+                U128(10),
+                receiver_acc(),
+                Some(CreatePaymentOptions {
+                    vesting_mode: VestingMode::Linear,
+                    ..Default::default()
+                }),
+            )
+            .unwrap()
+            .0;
+
+        // set caller to receiver
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+
+        // approve the payment
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        set_block_timestamp(NANOS_IN_DAY * 2 + 1);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(20)
+        );
+
+        // the issuer stops the stream in the middle of the fifth day
+        set_block_timestamp(NANOS_IN_DAY * 9 / 2 + 1);
+        let result = contract
+            .reject_payment_receipt_impl(payment_id, CompletionReason::RejectedByIssuer)
+            .unwrap();
+        assert_eq!(result.issuer_data, (issuer_acc(), 55));
+        assert_eq!(result.receiver_data, vec![(receiver_acc(), 25)]);
+
+        // check that the payment has been removed from all storages
+        check_all_account_data_removed(&contract, payment_id);
+    }
+
+    fn create_split_payment(contract: &mut PaymentContract) -> u64 {
+        let context = get_context(issuer_acc(), 100);
+        testing_env!(context.clone());
+
+        let payment_id = contract
+            .create_split_payment(
+                U64(1),
+                U128(10),
+                vec![(receiver_acc(), 7000), (accounts(3), 3000)],
+                None,
+            )
+            .unwrap()
+            .0;
+
+        // set caller to the primary receiver
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+
+        // approve the payment
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        payment_id
+    }
+
+    #[test]
+    fn test_reject_split_payment_receipt() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let payment_id = create_split_payment(&mut contract);
+
+        // the second receiver claims 2 periods, 30% of 20 tokens
+        let mut context = get_context(accounts(3), 1);
+        context.block_timestamp = NANOS_IN_DAY * 2 + 1;
+        testing_env!(context.clone());
+        assert_eq!(contract.claim_payment(U64(payment_id)), Ok(U128(6)));
+
+        // the issuer stops the stream after 4 periods
+        let mut context = get_context(issuer_acc(), 0);
+        context.block_timestamp = NANOS_IN_DAY * 4 + 1;
+        testing_env!(context.clone());
+
+        let result = contract
+            .reject_payment_receipt_impl(payment_id, CompletionReason::RejectedByIssuer)
+            .unwrap();
+        assert_eq!(result.issuer_data, (issuer_acc(), 60));
+        assert_eq!(
+            result.receiver_data,
+            vec![(receiver_acc(), 28), (accounts(3), 6)]
+        );
+
+        // check that the payment has been removed from all storages
+        check_all_account_data_removed(&contract, payment_id);
+        assert!(contract.receiver_ledger.get(&accounts(3)).is_none());
+    }
+
+    #[test]
+    fn test_reject_split_payment_not_primary_receiver() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let payment_id = create_split_payment(&mut contract);
+
+        let context = get_context(accounts(3), 1);
+        testing_env!(context.clone());
+
+        assert_eq!(
+            contract.reject_payment_receipt(U64(payment_id), PaymentRole::Receiver),
+            Err(ContractError::NotPrimaryReceiver(accounts(3), payment_id))
+        );
+    }
+
+    #[test]
+    fn test_on_reject_transfer() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // the issuer refund is successful, the receiver payout is failed
+        testing_env!(
+            get_context(contract_acc(), 0),
+            VMConfig::test(),
+            RuntimeFeesConfig::test(),
+            Default::default(),
+            vec![PromiseResult::Successful(vec![]), PromiseResult::Failed]
+        );
+
+        contract.on_reject_transfer(
+            U64(1),
+            vec![(issuer_acc(), U128(70)), (receiver_acc(), U128(30))],
+        );
+
+        assert_eq!(contract.get_failed_transfer_balance(issuer_acc()), U128(0));
+        assert_eq!(
+            contract.get_failed_transfer_balance(receiver_acc()),
+            U128(30)
+        );
+
+        // only the failed transfer is reported
+        let logs = get_logs();
+        assert_eq!(
+            logs.iter()
+                .filter(|log| log.starts_with("EVENT_JSON:"))
+                .collect::<Vec<_>>(),
+            vec![&format!(
+                r#"EVENT_JSON:{{"standard":"near_payment_receiver","version":"1.0.0","event":"transfer_failed","data":[{{"account_id":"{}","amount":"30"}}]}}"#,
+                receiver_acc()
+            )]
+        );
+    }
+
+    #[test]
+    fn test_force_settle() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        // the unconfirmed payment is handled by the expiration
+        testing_env!(get_context(contract_acc(), 1));
+        assert_eq!(
+            contract.force_settle(U64(payment_id)),
+            Err(ContractError::PaymentReceiptNotConfirmed(payment_id))
+        );
+
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        // 10 periods of one day and 30 days of the grace period
+        let settlement_date = NANOS_IN_DAY * 40 + 1;
+
+        let mut context = get_context(contract_acc(), 1);
+        context.block_timestamp = settlement_date - 1;
+        testing_env!(context.clone());
+        assert_eq!(
+            contract.force_settle(U64(payment_id)),
+            Err(ContractError::SettlementTooEarly(
+                payment_id,
+                settlement_date
+            ))
+        );
+
+        let mut context = get_context(issuer_acc(), 1);
+        context.block_timestamp = settlement_date;
+        testing_env!(context.clone());
+        assert_eq!(
+            contract.force_settle(U64(payment_id)),
+            Err(ContractError::NotContractOwner(issuer_acc()))
+        );
+
+        // the whole stream is vested, so everything is paid to the receiver
+        let mut context = get_context(contract_acc(), 1);
+        context.block_timestamp = settlement_date;
+        testing_env!(context.clone());
+        assert_eq!(
+            contract.force_settle(U64(payment_id)),
+            Ok(RejectPaymentResult {
+                issuer_refund: U128(0),
+                receiver_payout: U128(10),
+            })
+        );
+
+        assert_eq!(
+            get_logs().last().unwrap(),
+            r#"EVENT_JSON:{"standard":"near_payment_receiver","version":"1.0.0","event":"payment_force_settled","data":[{"payment_id":"1","issuer_refund":"0","receiver_payout":"10"}]}"#
+        );
+        assert_eq!(
+            contract
+                .get_completed_payment(U64(payment_id))
+                .unwrap()
+                .completion_reason,
+            CompletionReason::ForceSettled
+        );
+        check_all_account_data_removed(&contract, payment_id);
+    }
+
+    #[test]
+    fn test_force_settle_with_grace_days() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        contract.set_abandonment_grace_days(U64(0)).unwrap();
+        assert_eq!(contract.get_abandonment_grace_days(), U64(0));
+
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        // the stream could be settled right after the end date
+        let mut context = get_context(contract_acc(), 1);
+        context.block_timestamp = NANOS_IN_DAY * 10 + 1;
+        testing_env!(context.clone());
+        assert!(contract.force_settle(U64(payment_id)).is_ok());
+        check_all_account_data_removed(&contract, payment_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_force_settle_without_yocto() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        testing_env!(get_context(contract_acc(), 0));
+        let _ = contract.force_settle(U64(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Requires attached deposit of exactly 1 yoctoNEAR")]
+    fn test_reject_payment_without_yocto() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        testing_env!(get_context(issuer_acc(), 0));
+        let _ = contract.reject_payment_receipt(U64(1), PaymentRole::Issuer);
+    }
+
+    #[test]
+    fn test_reject_payment_insufficient_gas() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        let mut context = get_context(issuer_acc(), 1);
+        context.prepaid_gas = Gas(GAS_FOR_TRANSFER.0 + GAS_FOR_TRANSFER_CALLBACK.0);
+        testing_env!(context);
+
+        assert_eq!(
+            contract.reject_payment_receipt(U64(payment_id), PaymentRole::Issuer),
+            Err(ContractError::InsufficientGas(
+                GAS_FOR_TRANSFER.0 * 2 + GAS_FOR_TRANSFER_CALLBACK.0,
+                GAS_FOR_TRANSFER.0 + GAS_FOR_TRANSFER_CALLBACK.0
+            ))
+        );
+        assert!(contract.payment_info_ledger.get(&payment_id).is_some());
+    }
+
+    #[test]
+    fn test_reject_all_from() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let pending_ids: Vec<u64> = (0..3)
+            .map(|_| create_payment(&mut contract, 10, 1))
+            .collect();
+
+        // the approved payment of the same issuer is kept
+        let approved_id = create_payment(&mut contract, 10, 1);
+        testing_env!(get_context(receiver_acc(), 0));
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(approved_id)))
+            .unwrap();
+
+        // the pending payment of the other issuer is kept
+        testing_env!(get_context(accounts(4), 10));
+        let other_id = contract
+            .create_payment(U64(1), U128(1), receiver_acc(), None)
+            .unwrap()
+            .0;
+
+        testing_env!(get_context(receiver_acc(), 1));
+        assert_eq!(contract.reject_all_from(issuer_acc()), Ok(U64(3)));
+
+        for payment_id in pending_ids {
+            assert!(contract.payment_info_ledger.get(&payment_id).is_none());
+            assert_eq!(
+                contract
+                    .get_completed_payment(U64(payment_id))
+                    .unwrap()
+                    .completion_reason,
+                CompletionReason::RejectedByReceiver
+            );
+        }
+        assert!(contract.payment_info_ledger.get(&approved_id).is_some());
+        assert!(contract.payment_info_ledger.get(&other_id).is_some());
+        assert_eq!(contract.get_total_locked_near(), U128(20));
+
+        // nothing is left to reject
+        assert_eq!(contract.reject_all_from(issuer_acc()), Ok(U64(0)));
+    }
 }
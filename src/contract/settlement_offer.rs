@@ -0,0 +1,365 @@
+use super::PaymentContract;
+use crate::constants::{GAS_FOR_TRANSFER, GAS_FOR_TRANSFER_CALLBACK, NANOS_IN_DAY};
+use crate::contract::reject_payment::RepaymentInfo;
+use crate::contract::PaymentContractExt;
+use crate::error::{ensure, ContractError};
+use crate::events::{ContractEvent, SettlementOffered, SettlementResolved};
+use crate::public::completed_payment::CompletionReason;
+use crate::public::payment_receipt::SettlementOffer;
+use crate::public::RejectPaymentResult;
+use crate::Result;
+use near_sdk::{
+    assert_one_yocto, env,
+    json_types::{U128, U64},
+    near_bindgen, Gas,
+};
+
+#[near_bindgen]
+impl PaymentContract {
+    // the receiver gets receiver_amount instead of the vested split, the issuer gets the rest of the deposit.
+    // the expired or invalidated offer could be replaced by the new one
+    #[handle_result]
+    pub fn offer_settlement(&mut self, payment_id: U64, receiver_amount: U128) -> Result<()> {
+        self.check_not_paused()?;
+
+        let issuer = env::predecessor_account_id();
+        let payment_id = payment_id.0;
+        let receiver_amount = receiver_amount.0;
+        let current_time = env::block_timestamp();
+
+        self.check_payment_unlocked(payment_id)?;
+        self.check_issue_payment_id(&issuer, payment_id)?;
+
+        let expires_at = self
+            .settlement_offer_days
+            .checked_mul(NANOS_IN_DAY)
+            .and_then(|offer_duration| current_time.checked_add(offer_duration))
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        let payment_receipt = self
+            .payment_info_ledger
+            .get_mut(&payment_id)
+            .ok_or(ContractError::PaymentIdNotExist(payment_id))?
+            .into_current_mut();
+
+        ensure!(
+            payment_receipt.receivers.is_empty(),
+            ContractError::SettlementOfferNotSupported(payment_id),
+        );
+
+        let payment_info = &payment_receipt.payment_info;
+
+        if let Some(offer) = &payment_receipt.settlement_offer {
+            ensure!(
+                current_time > offer.expires_at
+                    || offer.claimed_amount != payment_info.claimed_amount,
+                ContractError::SettlementOfferAlreadyPending(payment_id),
+            );
+        }
+
+        let unclaimed_amount = payment_info
+            .total_amount
+            .checked_sub(payment_info.claimed_amount)
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        ensure!(
+            receiver_amount <= unclaimed_amount,
+            ContractError::SettlementOfferTooHigh(payment_id, receiver_amount, unclaimed_amount),
+        );
+
+        payment_receipt.settlement_offer = Some(SettlementOffer {
+            receiver_amount,
+            expires_at,
+            claimed_amount: payment_info.claimed_amount,
+        });
+
+        ContractEvent::SettlementOffered(vec![SettlementOffered {
+            payment_id: payment_id.into(),
+            receiver_amount: receiver_amount.into(),
+            expires_at: expires_at.into(),
+        }])
+        .emit();
+
+        Ok(())
+    }
+
+    // pays out the offered amounts and removes the payment
+    #[payable]
+    #[handle_result]
+    pub fn accept_settlement(&mut self, payment_id: U64) -> Result<RejectPaymentResult> {
+        assert_one_yocto();
+        self.check_prepaid_gas(Gas(GAS_FOR_TRANSFER.0 * 2 + GAS_FOR_TRANSFER_CALLBACK.0))?;
+        self.check_not_paused()?;
+
+        let receiver = env::predecessor_account_id();
+        let payment_id = payment_id.0;
+
+        self.check_payment_unlocked(payment_id)?;
+        self.check_receiver_payment_id(&receiver, payment_id)?;
+
+        let payment_receipt = self.load_payment_receipt(payment_id)?;
+        let payment_info = &payment_receipt.payment_info;
+
+        let offer = payment_receipt
+            .settlement_offer
+            .as_ref()
+            .ok_or(ContractError::NoSettlementOffer(payment_id))?;
+
+        ensure!(
+            env::block_timestamp() <= offer.expires_at,
+            ContractError::SettlementOfferExpired(payment_id, offer.expires_at),
+        );
+
+        // the split of the offer is not valid anymore when a part of the stream is claimed after it
+        ensure!(
+            offer.claimed_amount == payment_info.claimed_amount,
+            ContractError::SettlementOfferInvalidated(payment_id),
+        );
+
+        let unclaimed_amount = payment_info
+            .total_amount
+            .checked_sub(payment_info.claimed_amount)
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        let issuer = payment_receipt.issuer.clone();
+        let mut repayment_info =
+            RepaymentInfo::new(issuer.clone(), payment_receipt.payout_account(&receiver));
+        repayment_info.receiver_data[0].1 = offer.receiver_amount;
+        repayment_info.issuer_data.1 = unclaimed_amount
+            .checked_sub(offer.receiver_amount)
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        self.remove_payment_related_data(
+            &issuer,
+            &receiver,
+            payment_id,
+            CompletionReason::SettlementAccepted,
+            unclaimed_amount,
+        )?;
+
+        ContractEvent::SettlementResolved(vec![SettlementResolved {
+            payment_id: payment_id.into(),
+            accepted: true,
+        }])
+        .emit();
+
+        self.pay_out_repayment(payment_id, repayment_info)
+    }
+
+    // the stream continues under its terms
+    #[handle_result]
+    pub fn decline_settlement(&mut self, payment_id: U64) -> Result<()> {
+        let receiver = env::predecessor_account_id();
+        let payment_id = payment_id.0;
+
+        self.check_receiver_payment_id(&receiver, payment_id)?;
+
+        self.payment_info_ledger
+            .get_mut(&payment_id)
+            .ok_or(ContractError::PaymentIdNotExist(payment_id))?
+            .into_current_mut()
+            .settlement_offer
+            .take()
+            .ok_or(ContractError::NoSettlementOffer(payment_id))?;
+
+        ContractEvent::SettlementResolved(vec![SettlementResolved {
+            payment_id: payment_id.into(),
+            accepted: false,
+        }])
+        .emit();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constants::DEFAULT_SETTLEMENT_OFFER_DAYS,
+        contract::general_impl::tests::{
+            check_all_account_data_removed, contract_acc, create_payment, get_context, issuer_acc,
+            receiver_acc, set_block_timestamp,
+        },
+        public::{completed_payment::CompletionReason, ProcessStatus},
+    };
+
+    use super::*;
+    use near_sdk::{
+        test_utils::{get_created_receipts, get_logs},
+        testing_env, AccountId,
+    };
+
+    fn get_contract_with_payment() -> (PaymentContract, u64) {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        testing_env!(get_context(receiver_acc(), 0));
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        (contract, payment_id)
+    }
+
+    fn set_caller_at(account_id: AccountId, block_timestamp: u64) {
+        let mut context = get_context(account_id, 1);
+        context.block_timestamp = block_timestamp;
+        testing_env!(context);
+    }
+
+    #[test]
+    fn test_accept_settlement() {
+        let (mut contract, payment_id) = get_contract_with_payment();
+
+        set_caller_at(issuer_acc(), NANOS_IN_DAY);
+        contract.offer_settlement(U64(payment_id), U128(6)).unwrap();
+        assert_eq!(
+            get_logs(),
+            vec![format!(
+                r#"EVENT_JSON:{{"standard":"near_payment_receiver","version":"1.0.0","event":"settlement_offered","data":[{{"payment_id":"{}","receiver_amount":"6","expires_at":"{}"}}]}}"#,
+                payment_id,
+                NANOS_IN_DAY * (DEFAULT_SETTLEMENT_OFFER_DAYS + 1)
+            )]
+        );
+
+        // the pending offer is not replaced
+        assert_eq!(
+            contract.offer_settlement(U64(payment_id), U128(7)),
+            Err(ContractError::SettlementOfferAlreadyPending(payment_id))
+        );
+
+        set_caller_at(receiver_acc(), NANOS_IN_DAY * 2);
+        assert_eq!(
+            contract.accept_settlement(U64(payment_id)),
+            Ok(RejectPaymentResult {
+                issuer_refund: U128(4),
+                receiver_payout: U128(6),
+            })
+        );
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == receiver_acc()));
+        assert!(get_created_receipts()
+            .iter()
+            .any(|receipt| receipt.receiver_id == issuer_acc()));
+
+        check_all_account_data_removed(&contract, payment_id);
+        assert_eq!(
+            contract
+                .get_completed_payment(U64(payment_id))
+                .unwrap()
+                .completion_reason,
+            CompletionReason::SettlementAccepted
+        );
+        assert_eq!(contract.get_total_locked_near(), U128(0));
+    }
+
+    #[test]
+    fn test_offer_settlement_over_unclaimed_amount() {
+        let (mut contract, payment_id) = get_contract_with_payment();
+
+        set_block_timestamp(NANOS_IN_DAY * 3);
+        contract
+            .claim_payment_impl(&receiver_acc(), payment_id)
+            .unwrap();
+
+        testing_env!(get_context(issuer_acc(), 0));
+        assert_eq!(
+            contract.offer_settlement(U64(payment_id), U128(8)),
+            Err(ContractError::SettlementOfferTooHigh(payment_id, 8, 7))
+        );
+        assert_eq!(contract.offer_settlement(U64(payment_id), U128(7)), Ok(()));
+
+        // only the issuer offers the settlement
+        testing_env!(get_context(receiver_acc(), 0));
+        assert_eq!(
+            contract.offer_settlement(U64(payment_id), U128(7)),
+            Err(ContractError::IssuerAccountNotExist(receiver_acc()))
+        );
+    }
+
+    #[test]
+    fn test_claim_invalidates_settlement_offer() {
+        let (mut contract, payment_id) = get_contract_with_payment();
+
+        testing_env!(get_context(issuer_acc(), 0));
+        contract.offer_settlement(U64(payment_id), U128(5)).unwrap();
+
+        // the claim changes the baseline of the offer
+        set_block_timestamp(NANOS_IN_DAY * 2);
+        contract
+            .claim_payment_impl(&receiver_acc(), payment_id)
+            .unwrap();
+
+        set_caller_at(receiver_acc(), NANOS_IN_DAY * 2);
+        assert_eq!(
+            contract.accept_settlement(U64(payment_id)),
+            Err(ContractError::SettlementOfferInvalidated(payment_id))
+        );
+
+        // the invalidated offer could be replaced
+        set_caller_at(issuer_acc(), NANOS_IN_DAY * 2);
+        assert_eq!(contract.offer_settlement(U64(payment_id), U128(5)), Ok(()));
+
+        set_caller_at(receiver_acc(), NANOS_IN_DAY * 2);
+        assert_eq!(
+            contract.accept_settlement(U64(payment_id)),
+            Ok(RejectPaymentResult {
+                issuer_refund: U128(3),
+                receiver_payout: U128(5),
+            })
+        );
+    }
+
+    #[test]
+    fn test_settlement_offer_expired() {
+        let (mut contract, payment_id) = get_contract_with_payment();
+
+        testing_env!(get_context(issuer_acc(), 0));
+        contract.offer_settlement(U64(payment_id), U128(5)).unwrap();
+
+        let expires_at = NANOS_IN_DAY * DEFAULT_SETTLEMENT_OFFER_DAYS;
+        set_caller_at(receiver_acc(), expires_at + 1);
+        assert_eq!(
+            contract.accept_settlement(U64(payment_id)),
+            Err(ContractError::SettlementOfferExpired(
+                payment_id, expires_at
+            ))
+        );
+    }
+
+    #[test]
+    fn test_decline_settlement() {
+        let (mut contract, payment_id) = get_contract_with_payment();
+
+        testing_env!(get_context(receiver_acc(), 0));
+        assert_eq!(
+            contract.decline_settlement(U64(payment_id)),
+            Err(ContractError::NoSettlementOffer(payment_id))
+        );
+
+        testing_env!(get_context(issuer_acc(), 0));
+        contract.offer_settlement(U64(payment_id), U128(5)).unwrap();
+
+        testing_env!(get_context(receiver_acc(), 0));
+        contract.decline_settlement(U64(payment_id)).unwrap();
+        assert!(contract
+            .get_payment(U64(payment_id))
+            .unwrap()
+            .settlement_offer
+            .is_none());
+
+        // the stream continues under its terms
+        set_caller_at(receiver_acc(), NANOS_IN_DAY * 2);
+        assert_eq!(
+            contract.accept_settlement(U64(payment_id)),
+            Err(ContractError::NoSettlementOffer(payment_id))
+        );
+        assert!(contract.payment_info_ledger.get(&payment_id).is_some());
+    }
+}
@@ -0,0 +1,2346 @@
+use super::PaymentContract;
+use crate::constants::{
+    CONTRACT_VERSION, DEFAULT_PAGINATION_LIMIT, EXPORT_STATE_VERSION, MAX_BATCH_SIZE,
+    MAX_PAGINATION_LIMIT, NANOS_IN_SECOND,
+};
+use crate::contract::general_impl::ledger_payment_ids;
+use crate::contract::PaymentContractExt;
+use crate::error::{ensure, ContractError};
+use crate::events::{EVENT_STANDARD, EVENT_TYPES, EVENT_VERSION};
+use crate::public::claim_history::ClaimRecord;
+use crate::public::completed_payment::CompletedPaymentRecord;
+use crate::public::payment_info::{PaymentProgress, PaymentStatus, PublicPaymentStatus};
+use crate::public::payment_receipt::{CurrentUserVersion, PaymentReceiptView};
+use crate::public::{
+    AccountExport, AccountPayments, ContractLimits, ContractMetadata, ContractSourceMetadata,
+    ContractStats, ExportedPayment, FeeConfig, IntegrityReport, PaginatedPaymentIds,
+    PaymentCountByStatus, PaymentRole,
+};
+use crate::Result;
+use near_sdk::{
+    env,
+    json_types::{U128, U64},
+    near_bindgen,
+    store::{UnorderedMap, UnorderedSet},
+    AccountId,
+};
+
+#[near_bindgen]
+impl PaymentContract {
+    // the same view is returned by the batch lookup, so that the clients need only one deserializer
+    #[handle_result]
+    pub fn get_payment(&self, payment_id: U64) -> Result<PaymentReceiptView> {
+        Ok(PaymentReceiptView::from(
+            &*self.load_payment_receipt(payment_id.0)?,
+        ))
+    }
+
+    // the description is the memo of the payment
+    #[handle_result]
+    pub fn get_payment_description(&self, payment_id: U64) -> Result<Option<String>> {
+        Ok(self.load_payment_receipt(payment_id.0)?.memo.clone())
+    }
+
+    #[handle_result]
+    pub fn get_payment_metadata(&self, payment_id: U64) -> Result<Option<String>> {
+        Ok(self.load_payment_receipt(payment_id.0)?.metadata.clone())
+    }
+
+    // missing payments are returned as None, so that the whole batch is not failed
+    #[handle_result]
+    pub fn get_multiple_payment_receipts(
+        &self,
+        payment_ids: Vec<U64>,
+    ) -> Result<Vec<Option<PaymentReceiptView>>> {
+        ensure!(
+            payment_ids.len() <= MAX_BATCH_SIZE,
+            ContractError::BatchSizeTooLarge(payment_ids.len(), MAX_BATCH_SIZE),
+        );
+
+        Ok(payment_ids
+            .into_iter()
+            .map(|payment_id| {
+                self.payment_info_ledger
+                    .get(&payment_id.0)
+                    .map(|payment_receipt| {
+                        PaymentReceiptView::from(&*payment_receipt.into_current())
+                    })
+            })
+            .collect())
+    }
+
+    // every account is allowed while the allowlist is disabled
+    pub fn is_receiver_allowed(&self, account_id: AccountId) -> bool {
+        !self.allowlist_enabled || self.allowed_receivers.contains(&account_id)
+    }
+
+    // the cursor is the payment id, so the page is not shifted by the payments closed between the calls.
+    // the empty page is returned when the cursor payment itself is closed, the paging should be restarted then
+    pub fn get_all_payment_ids_paginated(
+        &self,
+        cursor: Option<U64>,
+        limit: U64,
+    ) -> PaginatedPaymentIds {
+        let limit = limit.0.min(MAX_PAGINATION_LIMIT);
+
+        let mut payment_ids = self
+            .payment_info_ledger
+            .keys()
+            .skip_while(|payment_id| cursor.is_some_and(|cursor| **payment_id != cursor.0))
+            .map(|payment_id| U64(*payment_id));
+
+        let ids = payment_ids.by_ref().take(limit as usize).collect();
+
+        PaginatedPaymentIds {
+            ids,
+            next_cursor: payment_ids.next(),
+        }
+    }
+
+    pub fn get_allowed_receivers(
+        &self,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<AccountId> {
+        let from_index = from_index.map_or(0, |value| value.0);
+        let limit = limit
+            .map_or(DEFAULT_PAGINATION_LIMIT, |value| value.0)
+            .min(MAX_PAGINATION_LIMIT);
+
+        self.allowed_receivers
+            .iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .cloned()
+            .collect()
+    }
+
+    pub fn get_claim_history(
+        &self,
+        payment_id: U64,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<ClaimRecord> {
+        let from_index = from_index.map_or(0, |value| value.0);
+        let limit = limit
+            .map_or(DEFAULT_PAGINATION_LIMIT, |value| value.0)
+            .min(MAX_PAGINATION_LIMIT);
+
+        self.claim_history
+            .get(&payment_id.0)
+            .map(|history| {
+                history
+                    .iter()
+                    .skip(from_index as usize)
+                    .take(limit as usize)
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    pub fn payment_exists(&self, payment_id: U64) -> bool {
+        self.payment_info_ledger.contains_key(&payment_id.0)
+    }
+
+    // the payment is active when it exists and is confirmed by the receiver
+    pub fn is_payment_active(&self, payment_id: U64) -> bool {
+        self.payment_info_ledger
+            .get(&payment_id.0)
+            .is_some_and(|payment_receipt| {
+                payment_receipt
+                    .into_current()
+                    .payment_info
+                    .initiale_date
+                    .is_some()
+            })
+    }
+
+    #[handle_result]
+    pub fn get_payment_status(&self, payment_id: U64) -> Result<PublicPaymentStatus> {
+        let payment_id = payment_id.0;
+        let payment_receipt = self.load_payment_receipt(payment_id)?;
+        let payment_info = &payment_receipt.payment_info;
+
+        // not confirmed payment is a valid state for the frontend, so it is not treated as an error
+        if payment_info.initiale_date.is_none() {
+            return Ok(PublicPaymentStatus::Pending);
+        }
+
+        payment_info
+            .calculate_payment_status_impl(
+                payment_id,
+                payment_receipt.settlement_time(env::block_timestamp()),
+            )
+            .map(Into::into)
+    }
+
+    #[handle_result]
+    pub fn get_next_payment_date(&self, payment_id: U64) -> Result<Option<U64>> {
+        let payment_id = payment_id.0;
+
+        Ok(self
+            .load_payment_receipt(payment_id)?
+            .payment_info
+            .calculate_next_payment_date(payment_id)?
+            .map(U64))
+    }
+
+    #[handle_result]
+    pub fn get_time_until_next_payment(&self, payment_id: U64) -> Result<Option<U64>> {
+        let next_payment_date = self.get_next_payment_date(payment_id)?;
+
+        // zero means that the next period could be already claimed
+        Ok(next_payment_date.map(|next_payment_date| {
+            U64(next_payment_date.0.saturating_sub(env::block_timestamp()) / NANOS_IN_SECOND)
+        }))
+    }
+
+    // None while the payment is pending
+    #[handle_result]
+    pub fn get_payment_age_secs(&self, payment_id: U64) -> Result<Option<U64>> {
+        Ok(self
+            .load_payment_receipt(payment_id.0)?
+            .payment_info
+            .initiale_date
+            .map(|initiale_date| {
+                U64(env::block_timestamp().saturating_sub(initiale_date) / NANOS_IN_SECOND)
+            }))
+    }
+
+    #[handle_result]
+    pub fn get_end_date(&self, payment_id: U64) -> Result<Option<U64>> {
+        let payment_id = payment_id.0;
+        let payment_receipt = self.load_payment_receipt(payment_id)?;
+        let payment_info = &payment_receipt.payment_info;
+
+        payment_info
+            .initiale_date
+            .map(|initiale_date| {
+                payment_info
+                    .calculate_end_date(initiale_date)
+                    .map(U64)
+                    .ok_or(ContractError::InternalCalculationError(payment_id))
+            })
+            .transpose()
+    }
+
+    // unconfirmed payment is not treated as an error, its progress is returned with confirmed = false
+    #[handle_result]
+    pub fn get_payment_progress(&self, payment_id: U64) -> Result<PaymentProgress> {
+        let payment_id = payment_id.0;
+
+        self.load_payment_receipt(payment_id)?
+            .payment_info
+            .calculate_progress(payment_id, env::block_timestamp())
+    }
+
+    // the part of the total amount paid out to the receivers, from 0 to 10000 bps
+    #[handle_result]
+    pub fn get_payment_progress_bps(&self, payment_id: U64) -> Result<U64> {
+        let payment_id = payment_id.0;
+
+        let payment_receipt = self.load_payment_receipt(payment_id)?;
+        let total_amount = payment_receipt.payment_info.total_amount;
+
+        // the shares of the split stream are claimed separately, so its remainder is tracked by the shares
+        let remainder_amount = if payment_receipt.receivers.is_empty() {
+            payment_receipt
+                .payment_info
+                .calculate_remainder_amount(payment_id)?
+        } else {
+            payment_receipt
+                .locked_amount()
+                .ok_or(ContractError::InternalCalculationError(payment_id))?
+        };
+
+        total_amount
+            .checked_sub(remainder_amount)
+            .and_then(|paid_amount| paid_amount.checked_mul(10_000))
+            .and_then(|value| value.checked_div(total_amount))
+            .and_then(|value| u64::try_from(value).ok())
+            .map(U64)
+            .ok_or(ContractError::InternalCalculationError(payment_id))
+    }
+
+    // the periods which are not paid yet, the last period could be partial
+    #[handle_result]
+    pub fn get_payment_periods_remaining(&self, payment_id: U64) -> Result<U64> {
+        let payment_id = payment_id.0;
+
+        let payment_receipt = self.load_payment_receipt(payment_id)?;
+        let payment_info = &payment_receipt.payment_info;
+
+        if payment_info.initiale_date.is_none() {
+            return payment_info
+                .max_payments_number()
+                .map(U64)
+                .ok_or(ContractError::InternalCalculationError(payment_id));
+        }
+
+        // everything left is claimable by the final payment
+        if let PaymentStatus::FinalPayment(_) = payment_info.calculate_payment_status(payment_id)? {
+            return Ok(U64(0));
+        }
+
+        let remainder_amount = payment_info.calculate_remainder_amount(payment_id)?;
+
+        remainder_amount
+            .checked_div(payment_info.payment_amount)
+            .map(|value| value + u128::from(remainder_amount % payment_info.payment_amount != 0))
+            .and_then(|value| u64::try_from(value).ok())
+            .map(U64)
+            .ok_or(ContractError::InternalCalculationError(payment_id))
+    }
+
+    // the deposited totals of the issued payments, the claimed parts are included
+    #[handle_result]
+    pub fn get_issuer_total_locked(&self, account_id: AccountId) -> Result<U128> {
+        let payment_ids = self
+            .issuer_ledger
+            .get(&account_id)
+            .ok_or_else(|| ContractError::IssuerAccountNotExist(account_id.clone()))?;
+
+        payment_ids
+            .iter()
+            .try_fold(0u128, |total, payment_id| {
+                let total_amount = self
+                    .load_payment_receipt(*payment_id)?
+                    .payment_info
+                    .total_amount;
+
+                total
+                    .checked_add(total_amount)
+                    .ok_or(ContractError::InternalCalculationError(*payment_id))
+            })
+            .map(U128)
+    }
+
+    // the split streams are matched by their primary receiver
+    pub fn find_duplicate_payments(
+        &self,
+        issuer: AccountId,
+        receiver: AccountId,
+        payment_amount: U128,
+        total_amount: U128,
+    ) -> Vec<U64> {
+        self.issuer_ledger
+            .get(&issuer)
+            .map(|payment_ids| {
+                payment_ids
+                    .iter()
+                    .filter(|payment_id| {
+                        self.load_payment_receipt(**payment_id)
+                            .is_ok_and(|payment_receipt| {
+                                payment_receipt.receiver == receiver
+                                    && payment_receipt.payment_info.payment_amount
+                                        == payment_amount.0
+                                    && payment_receipt.payment_info.total_amount == total_amount.0
+                            })
+                    })
+                    .map(|payment_id| U64(*payment_id))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    // the amounts which are not paid out to the receiver yet, its share is taken for the split streams
+    #[handle_result]
+    pub fn get_receiver_total_remaining(&self, account_id: AccountId) -> Result<U128> {
+        let payment_ids = self
+            .receiver_ledger
+            .get(&account_id)
+            .ok_or_else(|| ContractError::ReceiverAccountNotExist(account_id.clone()))?;
+
+        payment_ids
+            .iter()
+            .try_fold(0u128, |total, payment_id| {
+                let payment_id = *payment_id;
+                let payment_receipt = self.load_payment_receipt(payment_id)?;
+
+                let remainder_amount = match payment_receipt
+                    .receivers
+                    .iter()
+                    .find(|share| share.receiver == account_id)
+                {
+                    Some(share) => share
+                        .payment_info(&payment_receipt.payment_info)
+                        .calculate_remainder_amount(payment_id)
+                        .and_then(|amount| {
+                            share
+                                .weighted_amount(amount)
+                                .ok_or(ContractError::InternalCalculationError(payment_id))
+                        })?,
+                    None => payment_receipt
+                        .payment_info
+                        .calculate_remainder_amount(payment_id)?,
+                };
+
+                total
+                    .checked_add(remainder_amount)
+                    .ok_or(ContractError::InternalCalculationError(payment_id))
+            })
+            .map(U128)
+    }
+
+    pub fn get_failed_transfer_balance(&self, account_id: AccountId) -> U128 {
+        self.failed_transfers
+            .get(&account_id)
+            .copied()
+            .unwrap_or(0)
+            .into()
+    }
+
+    pub fn get_completed_payment(&self, payment_id: U64) -> Option<CompletedPaymentRecord> {
+        self.completed_payment_ledger.get(&payment_id.0).cloned()
+    }
+
+    pub fn get_completed_payment_count(&self) -> U64 {
+        u64::from(self.completed_payment_ledger.len()).into()
+    }
+
+    pub fn get_version(&self) -> String {
+        CONTRACT_VERSION.to_string()
+    }
+
+    // claim_payment, reject_payment_receipt and process_pending_payment(Reject) require exactly 1 yoctoNEAR
+    pub fn requires_one_yocto(&self) -> bool {
+        true
+    }
+
+    pub fn get_contract_metadata(&self) -> ContractMetadata {
+        ContractMetadata {
+            standard: EVENT_STANDARD.to_string(),
+            version: EVENT_VERSION.to_string(),
+            event_types: EVENT_TYPES.iter().map(|event| event.to_string()).collect(),
+        }
+    }
+
+    // NEP-330
+    pub fn contract_source_metadata(&self) -> ContractSourceMetadata {
+        self.source_metadata.clone()
+    }
+
+    pub fn get_owner(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
+    pub fn are_self_payments_allowed(&self) -> bool {
+        self.self_payments_allowed
+    }
+
+    pub fn get_settlement_offer_days(&self) -> U64 {
+        self.settlement_offer_days.into()
+    }
+
+    pub fn get_issuer_cancellation_notice_days(&self) -> U64 {
+        self.issuer_cancellation_notice_days.into()
+    }
+
+    pub fn get_abandonment_grace_days(&self) -> U64 {
+        self.abandonment_grace_days.into()
+    }
+
+    pub fn get_fee_config(&self) -> FeeConfig {
+        FeeConfig {
+            fee_bps: self.fee_bps,
+            fee_recipient: self.fee_recipient.clone(),
+        }
+    }
+
+    pub fn get_collected_fees(&self) -> U128 {
+        self.collected_fees.into()
+    }
+
+    pub fn get_max_active_payments_per_issuer(&self) -> Option<u32> {
+        self.max_active_payments_per_issuer
+    }
+
+    pub fn get_total_locked_near(&self) -> U128 {
+        self.total_locked_near.into()
+    }
+
+    pub fn get_limits(&self) -> ContractLimits {
+        ContractLimits {
+            max_payments_per_account: self.max_payments_per_account,
+            min_payment_amount: self.min_payment_amount.into(),
+            min_period_duration: self.min_period_duration.into(),
+        }
+    }
+
+    pub fn get_stats(&self) -> ContractStats {
+        ContractStats {
+            // payment ids are started from 1
+            total_payments_created: self.payment_id_counter.saturating_sub(1).into(),
+            active_payments: u64::from(self.payment_info_ledger.len()).into(),
+            unique_issuers: u64::from(self.issuer_ledger.len()).into(),
+            unique_receivers: u64::from(self.receiver_ledger.len()).into(),
+        }
+    }
+
+    // analytics only, O(n) over all the active payments, so it could run out of gas on the large state
+    pub fn get_payment_count_by_status(&self) -> PaymentCountByStatus {
+        let current_time = env::block_timestamp();
+        let (mut pending, mut active, mut final_period, mut errored) = (0u64, 0u64, 0u64, 0u64);
+
+        for (payment_id, payment_receipt) in self.payment_info_ledger.iter() {
+            let payment_receipt = payment_receipt.into_current();
+
+            if payment_receipt.payment_info.initiale_date.is_none() {
+                pending += 1;
+                continue;
+            }
+
+            match payment_receipt.payment_info.calculate_payment_status_impl(
+                *payment_id,
+                payment_receipt.settlement_time(current_time),
+            ) {
+                Ok(PaymentStatus::FinalPayment(_)) => final_period += 1,
+                Ok(_) => active += 1,
+                Err(_) => errored += 1,
+            }
+        }
+
+        PaymentCountByStatus {
+            pending: pending.into(),
+            active: active.into(),
+            final_period: final_period.into(),
+            errored: errored.into(),
+        }
+    }
+
+    // the same page is applied to the issuer and the receiver records of the account
+    pub fn get_all_payments_for_account(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> AccountPayments {
+        let from_index = from_index.map_or(0, |value| value.0);
+        let limit = limit
+            .map_or(DEFAULT_PAGINATION_LIMIT, |value| value.0)
+            .min(MAX_PAGINATION_LIMIT);
+
+        AccountPayments {
+            as_issuer: ledger_payment_ids(&self.issuer_ledger, &account_id, from_index, limit),
+            as_receiver: ledger_payment_ids(&self.receiver_ledger, &account_id, from_index, limit),
+        }
+    }
+
+    // diagnostics only, O(n) over all the ledgers, so it could run out of gas on the large state
+    pub fn verify_payment_integrity(&self) -> IntegrityReport {
+        let missing_receipts = self
+            .payment_info_ledger
+            .iter()
+            .filter(|(payment_id, payment_receipt)| {
+                !self.has_ledger_records(**payment_id, &payment_receipt.into_current())
+            })
+            .map(|(payment_id, _)| U64(*payment_id))
+            .collect();
+
+        IntegrityReport {
+            orphaned_issuer_ids: self.orphaned_payment_ids(&self.issuer_ledger),
+            orphaned_receiver_ids: self.orphaned_payment_ids(&self.receiver_ledger),
+            missing_receipts,
+        }
+    }
+
+    fn has_ledger_records(&self, payment_id: u64, payment_receipt: &CurrentUserVersion) -> bool {
+        let is_recorded = |ledger: &UnorderedMap<AccountId, UnorderedSet<u64>>,
+                           account_id: &AccountId| {
+            ledger
+                .get(account_id)
+                .is_some_and(|payment_ids| payment_ids.contains(&payment_id))
+        };
+
+        is_recorded(&self.issuer_ledger, &payment_receipt.issuer)
+            && payment_receipt
+                .receiver_accounts()
+                .iter()
+                .all(|receiver| is_recorded(&self.receiver_ledger, receiver))
+    }
+
+    fn orphaned_payment_ids(
+        &self,
+        ledger: &UnorderedMap<AccountId, UnorderedSet<u64>>,
+    ) -> Vec<U64> {
+        ledger
+            .values()
+            .flat_map(|payment_ids| payment_ids.iter())
+            .filter(|payment_id| !self.payment_info_ledger.contains_key(*payment_id))
+            .map(|payment_id| U64(*payment_id))
+            .collect()
+    }
+
+    // hydrated receipts of the account in the given role, so that the clients don't fetch them one by one
+    pub fn get_payments_detailed(
+        &self,
+        role: PaymentRole,
+        account_id: AccountId,
+        from_index: U64,
+        limit: U64,
+    ) -> Vec<PaymentReceiptView> {
+        let ledger = match role {
+            PaymentRole::Issuer => &self.issuer_ledger,
+            PaymentRole::Receiver => &self.receiver_ledger,
+        };
+        let limit = limit.0.min(MAX_PAGINATION_LIMIT);
+
+        ledger_payment_ids(ledger, &account_id, from_index.0, limit)
+            .into_iter()
+            .filter_map(|payment_id| {
+                self.payment_info_ledger
+                    .get(&payment_id.0)
+                    .map(|payment_receipt| {
+                        PaymentReceiptView::from(&*payment_receipt.into_current())
+                    })
+            })
+            .collect()
+    }
+
+    // the same page is applied to the issuer and the receiver records of the account
+    pub fn export_account_state(
+        &self,
+        account_id: AccountId,
+        from: U64,
+        limit: U64,
+    ) -> AccountExport {
+        let limit = limit.0.min(MAX_PAGINATION_LIMIT);
+        let as_issuer = ledger_payment_ids(&self.issuer_ledger, &account_id, from.0, limit);
+        let as_receiver = ledger_payment_ids(&self.receiver_ledger, &account_id, from.0, limit);
+
+        AccountExport {
+            state_version: EXPORT_STATE_VERSION,
+            as_issuer: self.export_payments(as_issuer),
+            as_receiver: self.export_payments(as_receiver),
+            account_id,
+        }
+    }
+
+    fn export_payments(&self, payment_ids: Vec<U64>) -> Vec<ExportedPayment> {
+        payment_ids
+            .into_iter()
+            .filter_map(|payment_id| {
+                let payment_id = payment_id.0;
+                let payment_receipt = self.payment_info_ledger.get(&payment_id)?.into_current();
+
+                let claimable_amount = payment_receipt
+                    .receiver_accounts()
+                    .iter()
+                    .filter_map(|receiver| self.claimable_amount(receiver, payment_id))
+                    .sum();
+
+                Some(ExportedPayment {
+                    payment_id: U64(payment_id),
+                    receipt: PaymentReceiptView::from(&*payment_receipt),
+                    claimable_amount: U128(claimable_amount),
+                    remainder_amount: U128(payment_receipt.locked_amount()?),
+                })
+            })
+            .collect()
+    }
+
+    pub fn get_issued_count(&self, account_id: AccountId) -> U64 {
+        self.issuer_ledger
+            .get(&account_id)
+            .map_or(0, |payment_ids| u64::from(payment_ids.len()))
+            .into()
+    }
+
+    pub fn get_received_count(&self, account_id: AccountId) -> U64 {
+        self.receiver_ledger
+            .get(&account_id)
+            .map_or(0, |payment_ids| u64::from(payment_ids.len()))
+            .into()
+    }
+
+    pub fn get_payments_between(
+        &self,
+        issuer: AccountId,
+        receiver: AccountId,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<U64> {
+        let from_index = from_index.map_or(0, |value| value.0);
+        let limit = limit
+            .map_or(DEFAULT_PAGINATION_LIMIT, |value| value.0)
+            .min(MAX_PAGINATION_LIMIT);
+
+        let (issuer_id_store, receiver_id_store) = match (
+            self.issuer_ledger.get(&issuer),
+            self.receiver_ledger.get(&receiver),
+        ) {
+            (Some(issuer_id_store), Some(receiver_id_store)) => {
+                (issuer_id_store, receiver_id_store)
+            }
+            _ => return vec![],
+        };
+
+        issuer_id_store
+            .iter()
+            .filter(|payment_id| receiver_id_store.contains(*payment_id))
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .map(|payment_id| U64(*payment_id))
+            .collect()
+    }
+
+    // O(n) over all the active payments, could run out of gas on the large contract,
+    // get_total_obligations_paginated should be used in this case
+    #[handle_result]
+    pub fn get_total_obligations(&self) -> Result<U128> {
+        self.sum_obligations(0, usize::MAX).map(U128)
+    }
+
+    // the sums of the pages are added up off-chain
+    #[handle_result]
+    pub fn get_total_obligations_paginated(
+        &self,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Result<U128> {
+        let from_index = from_index.map_or(0, |value| value.0);
+        let limit = limit
+            .map_or(DEFAULT_PAGINATION_LIMIT, |value| value.0)
+            .min(MAX_PAGINATION_LIMIT);
+
+        self.sum_obligations(from_index as usize, limit as usize)
+            .map(U128)
+    }
+
+    // the amount held by the contract for the payments which are not paid out yet
+    #[handle_result]
+    pub(crate) fn sum_obligations(&self, from_index: usize, limit: usize) -> Result<u128> {
+        let mut total_obligations: u128 = 0;
+
+        for (payment_id, payment_receipt) in
+            self.payment_info_ledger.iter().skip(from_index).take(limit)
+        {
+            total_obligations = payment_receipt
+                .into_current()
+                .locked_amount()
+                .and_then(|amount| total_obligations.checked_add(amount))
+                .ok_or(ContractError::InternalCalculationError(*payment_id))?;
+        }
+
+        Ok(total_obligations)
+    }
+
+    // payments which are still waiting for the approval of the receiver
+    pub fn get_pending_payments_for_receiver(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<U64> {
+        self.filter_ledger_payments(&self.receiver_ledger, &account_id, false, from_index, limit)
+    }
+
+    // payments which are approved by the receiver and running
+    pub fn get_active_payments_for_receiver(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<U64> {
+        self.filter_ledger_payments(&self.receiver_ledger, &account_id, true, from_index, limit)
+    }
+
+    pub fn get_pending_payments_for_issuer(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<U64> {
+        self.filter_ledger_payments(&self.issuer_ledger, &account_id, false, from_index, limit)
+    }
+
+    pub fn get_active_payments_for_issuer(
+        &self,
+        account_id: AccountId,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<U64> {
+        self.filter_ledger_payments(&self.issuer_ledger, &account_id, true, from_index, limit)
+    }
+
+    // the page is applied to the filtered payments, O(n) over all the payments of the account
+    fn filter_ledger_payments(
+        &self,
+        ledger: &UnorderedMap<AccountId, UnorderedSet<u64>>,
+        account_id: &AccountId,
+        confirmed: bool,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<U64> {
+        let from_index = from_index.map_or(0, |value| value.0);
+        let limit = limit
+            .map_or(DEFAULT_PAGINATION_LIMIT, |value| value.0)
+            .min(MAX_PAGINATION_LIMIT);
+
+        let id_store = match ledger.get(account_id) {
+            Some(id_store) => id_store,
+            None => return vec![],
+        };
+
+        id_store
+            .iter()
+            .filter(|payment_id| {
+                self.payment_info_ledger
+                    .get(*payment_id)
+                    .is_some_and(|payment_receipt| {
+                        payment_receipt
+                            .into_current()
+                            .payment_info
+                            .initiale_date
+                            .is_some()
+                            == confirmed
+                    })
+            })
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .map(|payment_id| U64(*payment_id))
+            .collect()
+    }
+
+    // the amount claimable at the given timestamp by all the receivers, assuming nothing is claimed before it
+    #[handle_result]
+    pub fn project_claimable_at(&self, payment_id: U64, at_timestamp: U64) -> Result<U128> {
+        let payment_id = payment_id.0;
+        let at_timestamp = at_timestamp.0;
+
+        let payment_receipt = self.load_payment_receipt(payment_id)?;
+
+        let initiale_date = payment_receipt
+            .payment_info
+            .initiale_date
+            .ok_or(ContractError::PaymentReceiptNotConfirmed(payment_id))?;
+
+        ensure!(
+            at_timestamp >= initiale_date,
+            ContractError::ProjectionBeforeStart(at_timestamp, initiale_date),
+        );
+
+        let claim_time = payment_receipt.settlement_time(at_timestamp);
+
+        let projected_amount = payment_receipt
+            .receiver_accounts()
+            .iter()
+            .filter_map(|receiver| {
+                Self::projected_amount(&payment_receipt, receiver, payment_id, claim_time)
+            })
+            .sum();
+
+        Ok(U128(projected_amount))
+    }
+
+    // the final payments are included, pending and locked payments have nothing to claim
+    pub fn get_claimable_payments(
+        &self,
+        receiver: AccountId,
+        from_index: Option<U64>,
+        limit: Option<U64>,
+    ) -> Vec<(U64, U128)> {
+        let from_index = from_index.map_or(0, |value| value.0);
+        let limit = limit
+            .map_or(DEFAULT_PAGINATION_LIMIT, |value| value.0)
+            .min(MAX_PAGINATION_LIMIT);
+
+        let receiver_id_store = match self.receiver_ledger.get(&receiver) {
+            Some(receiver_id_store) => receiver_id_store,
+            None => return vec![],
+        };
+
+        receiver_id_store
+            .iter()
+            .filter_map(|payment_id| {
+                self.claimable_amount(&receiver, *payment_id)
+                    .map(|amount| (U64(*payment_id), U128(amount)))
+            })
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+
+    // the same status computation as for the claim, but without changing the payment
+    fn claimable_amount(&self, receiver: &AccountId, payment_id: u64) -> Option<u128> {
+        let payment_receipt = self.payment_info_ledger.get(&payment_id)?.into_current();
+
+        if payment_receipt.payment_info.initiale_date.is_none() || payment_receipt.locked {
+            return None;
+        }
+
+        let claim_time = payment_receipt.settlement_time(env::block_timestamp());
+
+        Self::projected_amount(&payment_receipt, receiver, payment_id, claim_time)
+    }
+
+    // the amount the receiver could claim at the claim time, based on the claims made so far
+    fn projected_amount(
+        payment_receipt: &CurrentUserVersion,
+        receiver: &AccountId,
+        payment_id: u64,
+        claim_time: u64,
+    ) -> Option<u128> {
+        if payment_receipt.receivers.is_empty() {
+            return match payment_receipt
+                .payment_info
+                .calculate_payment_status_impl(payment_id, claim_time)
+                .ok()?
+            {
+                PaymentStatus::Absent => None,
+                PaymentStatus::PaymentReady(amount) | PaymentStatus::FinalPayment(amount) => {
+                    Some(amount)
+                }
+            };
+        }
+
+        let share = payment_receipt
+            .receivers
+            .iter()
+            .find(|share| &share.receiver == receiver)?;
+
+        match share
+            .payment_info(&payment_receipt.payment_info)
+            .calculate_payment_status_impl(payment_id, claim_time)
+            .ok()?
+        {
+            PaymentStatus::Absent => None,
+            PaymentStatus::PaymentReady(amount) | PaymentStatus::FinalPayment(amount) => {
+                share.weighted_payout(amount)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        constants::NANOS_IN_DAY,
+        contract::general_impl::tests::{
+            contract_acc, create_payment, get_context, get_context_with_storage_usage, issuer_acc,
+            receiver_acc, set_block_timestamp,
+        },
+        public::{completed_payment::CompletionReason, PaymentRole, ProcessStatus},
+    };
+
+    use super::*;
+    use near_sdk::{serde_json, test_utils::accounts, testing_env};
+
+    fn create_approved_payment(contract: &mut PaymentContract) -> u64 {
+        // create a payment
+        let payment_id = create_payment(contract, 10, 1);
+
+        // set caller to receiver
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+
+        // approve the payment
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        payment_id
+    }
+
+    #[test]
+    fn test_get_payment_status_pending() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // create a payment without approval
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        assert_eq!(
+            contract.get_payment_status(U64(payment_id)),
+            Ok(PublicPaymentStatus::Pending)
+        );
+    }
+
+    #[test]
+    fn test_get_payment_status_absent() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_approved_payment(&mut contract);
+
+        set_block_timestamp(NANOS_IN_DAY / 2);
+        assert_eq!(
+            contract.get_payment_status(U64(payment_id)),
+            Ok(PublicPaymentStatus::Absent)
+        );
+    }
+
+    #[test]
+    fn test_get_payment_status_payment_ready() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_approved_payment(&mut contract);
+
+        // we set to the fifth day(period is one day, period_amount is 1token, so 5 tokens are ready)
+        set_block_timestamp(NANOS_IN_DAY * 5 + 1);
+        assert_eq!(
+            contract.get_payment_status(U64(payment_id)),
+            Ok(PublicPaymentStatus::PaymentReady(U128(5)))
+        );
+    }
+
+    #[test]
+    fn test_get_payment_status_final_payment() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_approved_payment(&mut contract);
+
+        // we set to the final 10th day after the start day
+        set_block_timestamp(NANOS_IN_DAY * 10 + 1);
+        assert_eq!(
+            contract.get_payment_status(U64(payment_id)),
+            Ok(PublicPaymentStatus::FinalPayment(U128(10)))
+        );
+    }
+
+    #[test]
+    fn test_get_payment_status_not_exist() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let contract = PaymentContract::new().unwrap();
+
+        assert_eq!(
+            contract.get_payment_status(U64(1)),
+            Err(ContractError::PaymentIdNotExist(1))
+        );
+    }
+
+    #[test]
+    fn test_get_next_payment_date() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // create a payment without approval
+        let payment_id = create_payment(&mut contract, 3, 1);
+        assert_eq!(contract.get_next_payment_date(U64(payment_id)), Ok(None));
+
+        // set caller to receiver
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+
+        // approve the payment
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        assert_eq!(
+            contract.get_next_payment_date(U64(payment_id)),
+            Ok(Some(U64(NANOS_IN_DAY + 1)))
+        );
+
+        // claim the first two periods
+        set_block_timestamp(NANOS_IN_DAY * 2 + 1);
+        contract
+            .claim_payment_impl(&receiver_acc(), payment_id)
+            .unwrap();
+
+        // the last period is still ahead
+        assert_eq!(
+            contract.get_next_payment_date(U64(payment_id)),
+            Ok(Some(U64(NANOS_IN_DAY * 3 + 1)))
+        );
+    }
+
+    #[test]
+    fn test_get_next_payment_date_after_end_date() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_approved_payment(&mut contract);
+
+        // simulate the claim made after the end date which was not removed yet
+        contract
+            .payment_info_ledger
+            .get_mut(&payment_id)
+            .unwrap()
+            .into_current_mut()
+            .payment_info
+            .last_payment_date = Some(NANOS_IN_DAY * 10 + 1);
+
+        assert_eq!(contract.get_next_payment_date(U64(payment_id)), Ok(None));
+    }
+
+    #[test]
+    fn test_get_stats() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        assert_eq!(
+            contract.get_stats(),
+            ContractStats {
+                total_payments_created: U64(0),
+                active_payments: U64(0),
+                unique_issuers: U64(0),
+                unique_receivers: U64(0),
+            }
+        );
+
+        // two payments from the default issuer to the default receiver
+        create_payment(&mut contract, 10, 1);
+        let payment_id = create_payment(&mut contract, 10, 1);
+
+        // one more payment from another issuer to another receiver
+        testing_env!(get_context(accounts(3), 10));
+        contract
+            .create_payment(U64(1), U128(1), accounts(4), None)
+            .unwrap();
+
+        // close one of the payments
+        contract
+            .remove_payment_related_data(
+                &issuer_acc(),
+                &receiver_acc(),
+                payment_id,
+                CompletionReason::Claimed,
+                10,
+            )
+            .unwrap();
+
+        assert_eq!(
+            contract.get_stats(),
+            ContractStats {
+                total_payments_created: U64(3),
+                active_payments: U64(2),
+                unique_issuers: U64(2),
+                unique_receivers: U64(2),
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_payment_count_by_status() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        assert_eq!(
+            contract.get_payment_count_by_status(),
+            PaymentCountByStatus {
+                pending: U64(0),
+                active: U64(0),
+                final_period: U64(0),
+                errored: U64(0),
+            }
+        );
+
+        // 10 periods
+        create_approved_payment(&mut contract);
+
+        // 2 periods
+        let short_payment_id = create_payment(&mut contract, 10, 5);
+        testing_env!(get_context(receiver_acc(), 0));
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(short_payment_id)))
+            .unwrap();
+
+        create_payment(&mut contract, 10, 1);
+
+        set_block_timestamp(NANOS_IN_DAY);
+        assert_eq!(
+            contract.get_payment_count_by_status(),
+            PaymentCountByStatus {
+                pending: U64(1),
+                active: U64(2),
+                final_period: U64(0),
+                errored: U64(0),
+            }
+        );
+
+        set_block_timestamp(NANOS_IN_DAY * 2 + 1);
+        assert_eq!(
+            contract.get_payment_count_by_status(),
+            PaymentCountByStatus {
+                pending: U64(1),
+                active: U64(1),
+                final_period: U64(1),
+                errored: U64(0),
+            }
+        );
+
+        // the end date of the broken receipt overflows, it is not counted as active
+        contract
+            .payment_info_ledger
+            .get_mut(&short_payment_id)
+            .unwrap()
+            .into_current_mut()
+            .payment_info
+            .initiale_date = Some(u64::MAX);
+
+        assert_eq!(
+            contract.get_payment_count_by_status(),
+            PaymentCountByStatus {
+                pending: U64(1),
+                active: U64(1),
+                final_period: U64(0),
+                errored: U64(1),
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_total_locked_near() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        assert_eq!(contract.get_total_locked_near(), U128(0));
+
+        let payment_id = create_payment(&mut contract, 10, 1);
+        create_payment(&mut contract, 7, 1);
+        assert_eq!(contract.get_total_locked_near(), U128(17));
+
+        // closed payments are not counted
+        contract
+            .remove_payment_related_data(
+                &issuer_acc(),
+                &receiver_acc(),
+                payment_id,
+                CompletionReason::Claimed,
+                10,
+            )
+            .unwrap();
+        assert_eq!(contract.get_total_locked_near(), U128(7));
+    }
+
+    #[test]
+    fn test_get_total_obligations() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // a pending stream, a partially claimed stream and a stream claimed to the end
+        create_payment(&mut contract, 10, 1);
+        let claimed_payment_id = create_payment(&mut contract, 6, 2);
+        let final_payment_id = create_payment(&mut contract, 2, 1);
+
+        testing_env!(get_context(receiver_acc(), 0));
+        for payment_id in [claimed_payment_id, final_payment_id] {
+            contract
+                .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+                .unwrap();
+        }
+
+        assert_eq!(contract.get_total_obligations(), Ok(U128(18)));
+
+        set_block_timestamp(NANOS_IN_DAY * 2);
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), claimed_payment_id),
+            Ok(4)
+        );
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), final_payment_id),
+            Ok(2)
+        );
+
+        assert_eq!(contract.get_total_obligations(), Ok(U128(12)));
+
+        // the pages add up to the total
+        assert_eq!(
+            contract.get_total_obligations_paginated(None, Some(U64(1))),
+            Ok(U128(10))
+        );
+        assert_eq!(
+            contract.get_total_obligations_paginated(Some(U64(1)), None),
+            Ok(U128(2))
+        );
+        assert_eq!(
+            contract.get_total_obligations(),
+            Ok(contract.get_total_locked_near())
+        );
+    }
+
+    #[test]
+    fn test_get_payments_by_confirmation() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let payment_ids: Vec<u64> = (0..4)
+            .map(|_| create_payment(&mut contract, 10, 1))
+            .collect();
+
+        testing_env!(get_context(receiver_acc(), 0));
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_ids[1])))
+            .unwrap();
+
+        assert_eq!(
+            contract.get_pending_payments_for_receiver(receiver_acc(), None, None),
+            vec![
+                U64(payment_ids[0]),
+                U64(payment_ids[2]),
+                U64(payment_ids[3])
+            ]
+        );
+
+        // the page is applied to the pending payments
+        assert_eq!(
+            contract.get_pending_payments_for_receiver(receiver_acc(), Some(U64(1)), Some(U64(1))),
+            vec![U64(payment_ids[2])]
+        );
+
+        assert_eq!(
+            contract.get_pending_payments_for_receiver(issuer_acc(), None, None),
+            vec![]
+        );
+
+        assert_eq!(
+            contract.get_active_payments_for_receiver(receiver_acc(), None, None),
+            vec![U64(payment_ids[1])]
+        );
+
+        // the issuer sees the same split of the payments
+        assert_eq!(
+            contract.get_pending_payments_for_issuer(issuer_acc(), Some(U64(2)), None),
+            vec![U64(payment_ids[3])]
+        );
+        assert_eq!(
+            contract.get_active_payments_for_issuer(issuer_acc(), None, None),
+            vec![U64(payment_ids[1])]
+        );
+        assert_eq!(
+            contract.get_active_payments_for_issuer(receiver_acc(), None, None),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_get_claimable_payments() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // daily streams of 10 and 2 periods, a weekly stream and a pending one
+        let ready_payment_id = create_payment(&mut contract, 10, 1);
+        let final_payment_id = create_payment(&mut contract, 2, 1);
+        testing_env!(get_context(issuer_acc(), 10));
+        let absent_payment_id = contract
+            .create_payment(U64(7), U128(5), receiver_acc(), None)
+            .unwrap()
+            .0;
+        create_payment(&mut contract, 10, 1);
+
+        let mut context = get_context(receiver_acc(), 0);
+        context.block_timestamp = 1;
+        testing_env!(context.clone());
+        for payment_id in [ready_payment_id, final_payment_id, absent_payment_id] {
+            contract
+                .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+                .unwrap();
+        }
+
+        set_block_timestamp(NANOS_IN_DAY * 3 + 1);
+        assert_eq!(
+            contract.get_claimable_payments(receiver_acc(), None, None),
+            vec![
+                (U64(ready_payment_id), U128(3)),
+                (U64(final_payment_id), U128(2))
+            ]
+        );
+
+        // the page is applied to the claimable payments
+        assert_eq!(
+            contract.get_claimable_payments(receiver_acc(), Some(U64(1)), Some(U64(10))),
+            vec![(U64(final_payment_id), U128(2))]
+        );
+
+        assert_eq!(
+            contract.get_claimable_payments(accounts(4), None, None),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_get_version() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let contract = PaymentContract::new().unwrap();
+
+        assert_eq!(contract.get_version(), CONTRACT_VERSION);
+        assert_eq!(contract.get_version(), "1.1.0");
+    }
+
+    #[test]
+    fn test_get_all_payment_ids_paginated() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        for _ in 0..5 {
+            create_payment(&mut contract, 10, 1);
+        }
+
+        assert_eq!(
+            contract.get_all_payment_ids_paginated(None, U64(2)),
+            PaginatedPaymentIds {
+                ids: vec![U64(1), U64(2)],
+                next_cursor: Some(U64(3)),
+            }
+        );
+        assert_eq!(
+            contract.get_all_payment_ids_paginated(Some(U64(3)), U64(2)),
+            PaginatedPaymentIds {
+                ids: vec![U64(3), U64(4)],
+                next_cursor: Some(U64(5)),
+            }
+        );
+        assert_eq!(
+            contract.get_all_payment_ids_paginated(Some(U64(5)), U64(2)),
+            PaginatedPaymentIds {
+                ids: vec![U64(5)],
+                next_cursor: None,
+            }
+        );
+
+        // the limit is capped
+        assert_eq!(
+            contract
+                .get_all_payment_ids_paginated(None, U64(u64::MAX))
+                .ids
+                .len(),
+            5
+        );
+
+        // the paging is restarted when the cursor payment is closed
+        testing_env!(get_context(receiver_acc(), 1));
+        contract
+            .process_pending_payment(ProcessStatus::Reject(U64(3)))
+            .unwrap();
+        assert_eq!(
+            contract.get_all_payment_ids_paginated(Some(U64(3)), U64(2)),
+            PaginatedPaymentIds {
+                ids: vec![],
+                next_cursor: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_payments_detailed() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        create_payment(&mut contract, 10, 1);
+        create_payment(&mut contract, 20, 2);
+        create_payment(&mut contract, 30, 3);
+
+        let payments =
+            contract.get_payments_detailed(PaymentRole::Issuer, issuer_acc(), U64(0), U64(10));
+        assert_eq!(payments.len(), 3);
+        assert_eq!(
+            payments
+                .iter()
+                .map(|payment| payment.payment_info.total_amount)
+                .collect::<Vec<_>>(),
+            vec![U128(10), U128(20), U128(30)]
+        );
+        assert!(payments
+            .iter()
+            .all(|payment| payment.issuer == issuer_acc() && payment.receiver == receiver_acc()));
+
+        let payments =
+            contract.get_payments_detailed(PaymentRole::Receiver, receiver_acc(), U64(1), U64(1));
+        assert_eq!(payments.len(), 1);
+        assert_eq!(payments[0].payment_info.payment_amount, U128(2));
+
+        // out of bounds page and unknown account
+        assert!(contract
+            .get_payments_detailed(PaymentRole::Receiver, receiver_acc(), U64(3), U64(10))
+            .is_empty());
+        assert!(contract
+            .get_payments_detailed(PaymentRole::Issuer, receiver_acc(), U64(0), U64(10))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_get_account_totals() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        assert_eq!(
+            contract.get_issuer_total_locked(issuer_acc()),
+            Err(ContractError::IssuerAccountNotExist(issuer_acc()))
+        );
+        assert_eq!(
+            contract.get_receiver_total_remaining(receiver_acc()),
+            Err(ContractError::ReceiverAccountNotExist(receiver_acc()))
+        );
+
+        create_payment(&mut contract, 20, 2);
+        let payment_id = create_approved_payment(&mut contract);
+
+        assert_eq!(contract.get_issuer_total_locked(issuer_acc()), Ok(U128(30)));
+        assert_eq!(
+            contract.get_receiver_total_remaining(receiver_acc()),
+            Ok(U128(30))
+        );
+
+        // the claimed amount is not remaining anymore, but the issued total is kept
+        set_block_timestamp(NANOS_IN_DAY * 3 + 1);
+        contract
+            .claim_payment_impl(&receiver_acc(), payment_id)
+            .unwrap();
+
+        assert_eq!(contract.get_issuer_total_locked(issuer_acc()), Ok(U128(30)));
+        assert_eq!(
+            contract.get_receiver_total_remaining(receiver_acc()),
+            Ok(U128(27))
+        );
+    }
+
+    #[test]
+    fn test_verify_payment_integrity() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        for _ in 0..3 {
+            create_payment(&mut contract, 10, 1);
+        }
+
+        assert_eq!(
+            contract.verify_payment_integrity(),
+            IntegrityReport {
+                orphaned_issuer_ids: vec![],
+                orphaned_receiver_ids: vec![],
+                missing_receipts: vec![],
+            }
+        );
+
+        // the receipt is removed without the ledger records
+        contract.payment_info_ledger.remove(&1);
+        // the ledger record is removed without the receipt
+        contract
+            .receiver_ledger
+            .get_mut(&receiver_acc())
+            .unwrap()
+            .remove(&3);
+
+        assert_eq!(
+            contract.verify_payment_integrity(),
+            IntegrityReport {
+                orphaned_issuer_ids: vec![U64(1)],
+                orphaned_receiver_ids: vec![U64(1)],
+                missing_receipts: vec![U64(3)],
+            }
+        );
+    }
+
+    #[test]
+    fn test_requires_one_yocto() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let contract = PaymentContract::new().unwrap();
+
+        assert!(contract.requires_one_yocto());
+    }
+
+    #[test]
+    fn test_contract_source_metadata() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let contract = PaymentContract::new().unwrap();
+        let metadata = contract.contract_source_metadata();
+
+        // the compiled-in version is the one of the crate manifest
+        let manifest_version = include_str!("../../Cargo.toml")
+            .lines()
+            .find_map(|line| line.strip_prefix("version = "))
+            .map(|version| version.trim_matches('"'));
+        assert_eq!(metadata.version.as_deref(), manifest_version);
+        assert_eq!(metadata.version, Some(contract.get_version()));
+
+        assert_eq!(
+            metadata.link.as_deref(),
+            Some("https://github.com/yra1029/near_payment_receiver")
+        );
+        assert_eq!(
+            metadata
+                .standards
+                .iter()
+                .map(|standard| standard.standard.as_str())
+                .collect::<Vec<_>>(),
+            vec!["nep330", "nep297", EVENT_STANDARD]
+        );
+    }
+
+    #[test]
+    fn test_get_contract_metadata() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let contract = PaymentContract::new().unwrap();
+
+        assert_eq!(
+            contract.get_contract_metadata(),
+            ContractMetadata {
+                standard: "near_payment_receiver".to_string(),
+                version: "1.0.0".to_string(),
+                event_types: vec![
+                    "minimums_updated".to_string(),
+                    "receiver_payout".to_string(),
+                    "owner_changed".to_string(),
+                    "payment_force_settled".to_string(),
+                    "amendment_proposed".to_string(),
+                    "amendment_resolved".to_string(),
+                    "payment_approved".to_string(),
+                    "settlement_offered".to_string(),
+                    "settlement_resolved".to_string(),
+                    "payment_claimed".to_string(),
+                    "transfer_failed".to_string(),
+                    "transfer_recovered".to_string(),
+                    "cancellation_requested".to_string(),
+                    "cancellation_finalized".to_string(),
+                    "pending_receiver_changed".to_string(),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_export_account_state() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_approved_payment(&mut contract);
+
+        set_block_timestamp(NANOS_IN_DAY * 3 + 1);
+        let export = contract.export_account_state(receiver_acc(), U64(0), U64(10));
+
+        // the field names are pinned, so that a rename breaks the backend reconciliation
+        let value = serde_json::to_value(&export).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "state_version": 11,
+                "account_id": "charlie",
+                "as_issuer": [],
+                "as_receiver": [{
+                    "payment_id": payment_id.to_string(),
+                    "receipt": {
+                        "payment_info": {
+                            "initiale_date": "1",
+                            "period_duration": NANOS_IN_DAY.to_string(),
+                            "payment_amount": "1",
+                            "total_amount": "10",
+                            "last_payment_date": null,
+                            "vesting_mode": "Periodic",
+                            "claimed_amount": "0",
+                            "min_claim_interval": null,
+                            "lifetime_claimed_amount": "0",
+                        },
+                        "issuer": "bob",
+                        "receiver": "charlie",
+                        "memo": null,
+                        "approval_deadline": null,
+                        "receivers": [],
+                        "created_at": "0",
+                        "approved_at": "1",
+                        "approval_required": true,
+                        "pending_amendment": null,
+                        "period_unit": "Days",
+                        "period_value": "1",
+                        "payout_address": null,
+                        "claim_delegate": null,
+                        "settlement_offer": null,
+                        "metadata": null,
+                        "state": "Active",
+                    },
+                    "claimable_amount": "3",
+                    "remainder_amount": "10",
+                }],
+            })
+        );
+
+        // the export is read back without losing anything
+        assert_eq!(
+            serde_json::from_value::<AccountExport>(value).unwrap(),
+            export
+        );
+
+        // the issuer sees the same payment on its side
+        let export = contract.export_account_state(issuer_acc(), U64(0), U64(10));
+        assert_eq!(export.as_issuer.len(), 1);
+        assert!(export.as_receiver.is_empty());
+    }
+
+    #[test]
+    fn test_get_end_date() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // create a payment without approval
+        let payment_id = create_payment(&mut contract, 10, 1);
+        assert_eq!(contract.get_end_date(U64(payment_id)), Ok(None));
+
+        // approve the payment at timestamp 1
+        let payment_id = create_approved_payment(&mut contract);
+
+        // 10 periods of one day started from the approval date
+        assert_eq!(
+            contract.get_end_date(U64(payment_id)),
+            Ok(Some(U64(NANOS_IN_DAY * 10 + 1)))
+        );
+
+        assert_eq!(
+            contract.get_end_date(U64(payment_id + 1)),
+            Err(ContractError::PaymentIdNotExist(payment_id + 1))
+        );
+    }
+
+    #[test]
+    fn test_get_payment_periods_remaining() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // the periods of 3, 3, 3 and 1
+        let payment_id = create_payment(&mut contract, 10, 3);
+        assert_eq!(
+            contract.get_payment_periods_remaining(U64(payment_id)),
+            Ok(U64(4))
+        );
+
+        testing_env!(get_context(receiver_acc(), 0));
+        contract
+            .process_pending_payment(ProcessStatus::Approve(U64(payment_id)))
+            .unwrap();
+
+        // the accrued period is remaining until it is claimed
+        set_block_timestamp(NANOS_IN_DAY + NANOS_IN_DAY / 2);
+        assert_eq!(
+            contract.get_payment_periods_remaining(U64(payment_id)),
+            Ok(U64(4))
+        );
+
+        assert_eq!(
+            contract.claim_payment_impl(&receiver_acc(), payment_id),
+            Ok(3)
+        );
+        assert_eq!(
+            contract.get_payment_periods_remaining(U64(payment_id)),
+            Ok(U64(3))
+        );
+
+        set_block_timestamp(NANOS_IN_DAY * 4);
+        assert_eq!(
+            contract.get_payment_periods_remaining(U64(payment_id)),
+            Ok(U64(0))
+        );
+
+        assert_eq!(
+            contract.get_payment_periods_remaining(U64(payment_id + 1)),
+            Err(ContractError::PaymentIdNotExist(payment_id + 1))
+        );
+    }
+
+    #[test]
+    fn test_get_payment_progress() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // unconfirmed payment is not an error
+        let payment_id = create_payment(&mut contract, 10, 1);
+        let progress = contract.get_payment_progress(U64(payment_id)).unwrap();
+        assert!(!progress.confirmed);
+        assert_eq!(progress.end_date, U64(0));
+        assert_eq!(progress.periods_available, U64(0));
+
+        // 10 periods of one day approved at timestamp 1
+        let payment_id = create_approved_payment(&mut contract);
+
+        set_block_timestamp(NANOS_IN_DAY * 3 + 1);
+        let progress = contract.get_payment_progress(U64(payment_id)).unwrap();
+        assert!(progress.confirmed);
+        assert_eq!(progress.end_date, U64(NANOS_IN_DAY * 10 + 1));
+        assert_eq!(progress.periods_total, U64(10));
+        assert_eq!(progress.periods_claimed, U64(0));
+        assert_eq!(progress.periods_available, U64(3));
+
+        contract
+            .claim_payment_impl(&receiver_acc(), payment_id)
+            .unwrap();
+
+        set_block_timestamp(NANOS_IN_DAY * 7 + 1);
+        assert_eq!(
+            contract.get_payment_progress(U64(payment_id)),
+            Ok(PaymentProgress {
+                confirmed: true,
+                end_date: U64(NANOS_IN_DAY * 10 + 1),
+                periods_total: U64(10),
+                periods_claimed: U64(3),
+                periods_available: U64(4),
+                amount_claimed: U128(3),
+                amount_remaining: U128(7),
+            })
+        );
+
+        assert_eq!(
+            contract.get_payment_progress(U64(payment_id + 1)),
+            Err(ContractError::PaymentIdNotExist(payment_id + 1))
+        );
+    }
+
+    #[test]
+    fn test_project_claimable_at() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let pending_payment_id = create_payment(&mut contract, 10, 1);
+        assert_eq!(
+            contract.project_claimable_at(U64(pending_payment_id), U64(NANOS_IN_DAY)),
+            Err(ContractError::PaymentReceiptNotConfirmed(
+                pending_payment_id
+            ))
+        );
+
+        // 10 periods of one day approved at timestamp 1
+        let payment_id = create_approved_payment(&mut contract);
+
+        assert_eq!(
+            contract.project_claimable_at(U64(payment_id), U64(0)),
+            Err(ContractError::ProjectionBeforeStart(0, 1))
+        );
+        assert_eq!(
+            contract.project_claimable_at(U64(payment_id), U64(1)),
+            Ok(U128(0))
+        );
+
+        // period boundaries
+        assert_eq!(
+            contract.project_claimable_at(U64(payment_id), U64(NANOS_IN_DAY)),
+            Ok(U128(0))
+        );
+        assert_eq!(
+            contract.project_claimable_at(U64(payment_id), U64(NANOS_IN_DAY + 1)),
+            Ok(U128(1))
+        );
+        assert_eq!(
+            contract.project_claimable_at(U64(payment_id), U64(NANOS_IN_DAY * 3 + 1)),
+            Ok(U128(3))
+        );
+
+        // the whole amount is claimable at the end date and beyond it
+        assert_eq!(
+            contract.project_claimable_at(U64(payment_id), U64(NANOS_IN_DAY * 10 + 1)),
+            Ok(U128(10))
+        );
+        assert_eq!(
+            contract.project_claimable_at(U64(payment_id), U64(NANOS_IN_DAY * 20)),
+            Ok(U128(10))
+        );
+
+        // the projection only counts the periods which are not claimed yet
+        set_block_timestamp(NANOS_IN_DAY * 3 + 1);
+        contract
+            .claim_payment_impl(&receiver_acc(), payment_id)
+            .unwrap();
+        assert_eq!(
+            contract.project_claimable_at(U64(payment_id), U64(NANOS_IN_DAY * 5 + 1)),
+            Ok(U128(2))
+        );
+        assert_eq!(
+            contract.project_claimable_at(U64(payment_id), U64(NANOS_IN_DAY * 20)),
+            Ok(U128(7))
+        );
+
+        assert_eq!(
+            contract.project_claimable_at(U64(payment_id + 1), U64(NANOS_IN_DAY)),
+            Err(ContractError::PaymentIdNotExist(payment_id + 1))
+        );
+    }
+
+    #[test]
+    fn test_get_payment_progress_bps() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let pending_payment_id = create_payment(&mut contract, 10, 1);
+        assert_eq!(
+            contract.get_payment_progress_bps(U64(pending_payment_id)),
+            Ok(U64(0))
+        );
+
+        // 10 periods of one day approved at timestamp 1
+        let payment_id = create_approved_payment(&mut contract);
+
+        set_block_timestamp(NANOS_IN_DAY * 3 + 1);
+        // the vested amount is not paid out until it is claimed
+        assert_eq!(
+            contract.get_payment_progress_bps(U64(payment_id)),
+            Ok(U64(0))
+        );
+
+        contract
+            .claim_payment_impl(&receiver_acc(), payment_id)
+            .unwrap();
+        assert_eq!(
+            contract.get_payment_progress_bps(U64(payment_id)),
+            Ok(U64(3000))
+        );
+
+        set_block_timestamp(NANOS_IN_DAY * 9 + 1);
+        contract
+            .claim_payment_impl(&receiver_acc(), payment_id)
+            .unwrap();
+        assert_eq!(
+            contract.get_payment_progress_bps(U64(payment_id)),
+            Ok(U64(9000))
+        );
+
+        assert_eq!(
+            contract.get_payment_progress_bps(U64(payment_id + 1)),
+            Err(ContractError::PaymentIdNotExist(payment_id + 1))
+        );
+    }
+
+    #[test]
+    fn test_get_payment_age_secs() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // pending payment has no age
+        let payment_id = create_payment(&mut contract, 10, 1);
+        assert_eq!(contract.get_payment_age_secs(U64(payment_id)), Ok(None));
+
+        // approve the payment at timestamp 1
+        let payment_id = create_approved_payment(&mut contract);
+        assert_eq!(
+            contract.get_payment_age_secs(U64(payment_id)),
+            Ok(Some(U64(0)))
+        );
+
+        set_block_timestamp(NANOS_IN_DAY * 45 + 1);
+        assert_eq!(
+            contract.get_payment_age_secs(U64(payment_id)),
+            Ok(Some(U64(45 * 86400)))
+        );
+
+        assert_eq!(
+            contract.get_payment_age_secs(U64(payment_id + 1)),
+            Err(ContractError::PaymentIdNotExist(payment_id + 1))
+        );
+    }
+
+    #[test]
+    fn test_get_time_until_next_payment() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        // create a payment without approval
+        let payment_id = create_payment(&mut contract, 10, 1);
+        assert_eq!(
+            contract.get_time_until_next_payment(U64(payment_id)),
+            Ok(None)
+        );
+
+        // approve the payment at timestamp 1
+        let payment_id = create_approved_payment(&mut contract);
+
+        // a quarter of the day is passed
+        set_block_timestamp(NANOS_IN_DAY / 4 + 1);
+        assert_eq!(
+            contract.get_time_until_next_payment(U64(payment_id)),
+            Ok(Some(U64(NANOS_IN_DAY * 3 / 4 / NANOS_IN_SECOND)))
+        );
+
+        // the first period could be claimed
+        set_block_timestamp(NANOS_IN_DAY * 2);
+        assert_eq!(
+            contract.get_time_until_next_payment(U64(payment_id)),
+            Ok(Some(U64(0)))
+        );
+    }
+
+    #[test]
+    fn test_get_completed_payment() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        assert_eq!(contract.get_completed_payment_count(), U64(0));
+
+        // claim the whole payment
+        let claimed_payment_id = create_approved_payment(&mut contract);
+        set_block_timestamp(NANOS_IN_DAY * 10 + 1);
+        contract
+            .claim_payment_impl(&receiver_acc(), claimed_payment_id)
+            .unwrap();
+
+        // reject another payment by the issuer
+        let rejected_payment_id = create_approved_payment(&mut contract);
+        let mut context = get_context_with_storage_usage(issuer_acc(), 1);
+        context.block_timestamp = NANOS_IN_DAY * 12;
+        testing_env!(context.clone());
+        contract
+            .reject_payment_receipt(U64(rejected_payment_id), PaymentRole::Issuer)
+            .unwrap();
+
+        assert_eq!(contract.get_completed_payment_count(), U64(2));
+        assert_eq!(
+            contract.get_completed_payment(U64(claimed_payment_id)),
+            Some(CompletedPaymentRecord {
+                issuer: issuer_acc(),
+                receiver: receiver_acc(),
+                total_amount: 10,
+                completed_at: NANOS_IN_DAY * 10 + 1,
+                completion_reason: CompletionReason::Claimed,
+            })
+        );
+        assert_eq!(
+            contract.get_completed_payment(U64(rejected_payment_id)),
+            Some(CompletedPaymentRecord {
+                issuer: issuer_acc(),
+                receiver: receiver_acc(),
+                total_amount: 10,
+                completed_at: NANOS_IN_DAY * 12,
+                completion_reason: CompletionReason::RejectedByIssuer,
+            })
+        );
+
+        // active payments are not in the archive
+        assert_eq!(
+            contract.get_completed_payment(U64(rejected_payment_id + 1)),
+            None
+        );
+    }
+
+    #[test]
+    fn test_get_all_payments_for_account() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let first_payment_id = create_payment(&mut contract, 10, 1);
+        let second_payment_id = create_payment(&mut contract, 10, 1);
+
+        // the receiver issues a payment as well
+        testing_env!(get_context(receiver_acc(), 10));
+        let issued_payment_id = contract
+            .create_payment(U64(1), U128(1), accounts(3), None)
+            .unwrap();
+
+        assert_eq!(
+            contract.get_all_payments_for_account(receiver_acc(), None, None),
+            AccountPayments {
+                as_issuer: vec![issued_payment_id],
+                as_receiver: vec![U64(first_payment_id), U64(second_payment_id)],
+            }
+        );
+
+        // the same page is applied to both records
+        assert_eq!(
+            contract.get_all_payments_for_account(receiver_acc(), Some(U64(1)), Some(U64(1))),
+            AccountPayments {
+                as_issuer: vec![],
+                as_receiver: vec![U64(second_payment_id)],
+            }
+        );
+
+        // unknown account has no payments
+        assert_eq!(
+            contract.get_all_payments_for_account(accounts(4), None, None),
+            AccountPayments {
+                as_issuer: vec![],
+                as_receiver: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_payment_counts() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        for _ in 0..3 {
+            create_payment(&mut contract, 10, 1);
+        }
+
+        assert_eq!(contract.get_issued_count(issuer_acc()), U64(3));
+        assert_eq!(contract.get_received_count(issuer_acc()), U64(0));
+        assert_eq!(contract.get_issued_count(receiver_acc()), U64(0));
+        assert_eq!(contract.get_received_count(receiver_acc()), U64(3));
+
+        // unknown account has no payments
+        assert_eq!(contract.get_issued_count(accounts(4)), U64(0));
+        assert_eq!(contract.get_received_count(accounts(4)), U64(0));
+    }
+
+    #[test]
+    fn test_get_payments_between() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let first_payment_id = create_payment(&mut contract, 10, 1);
+
+        // unrelated payment of the issuer
+        testing_env!(get_context(issuer_acc(), 10));
+        contract
+            .create_payment(U64(1), U128(1), accounts(3), None)
+            .unwrap();
+
+        let second_payment_id = create_payment(&mut contract, 10, 1);
+
+        // unrelated payment of the receiver
+        testing_env!(get_context(accounts(4), 10));
+        contract
+            .create_payment(U64(1), U128(1), receiver_acc(), None)
+            .unwrap();
+
+        assert_eq!(
+            contract.get_payments_between(issuer_acc(), receiver_acc(), None, None),
+            vec![U64(first_payment_id), U64(second_payment_id)]
+        );
+        assert_eq!(
+            contract.get_payments_between(issuer_acc(), receiver_acc(), Some(U64(1)), None),
+            vec![U64(second_payment_id)]
+        );
+        assert_eq!(
+            contract.get_payments_between(issuer_acc(), receiver_acc(), None, Some(U64(1))),
+            vec![U64(first_payment_id)]
+        );
+
+        // accounts without the ledger record
+        assert_eq!(
+            contract.get_payments_between(accounts(5), receiver_acc(), None, None),
+            vec![]
+        );
+        assert_eq!(
+            contract.get_payments_between(issuer_acc(), accounts(5), None, None),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_payment_exists_and_active() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let pending_payment_id = create_payment(&mut contract, 10, 1);
+        assert!(contract.payment_exists(U64(pending_payment_id)));
+        assert!(!contract.is_payment_active(U64(pending_payment_id)));
+
+        let payment_id = create_approved_payment(&mut contract);
+        assert!(contract.payment_exists(U64(payment_id)));
+        assert!(contract.is_payment_active(U64(payment_id)));
+
+        assert!(!contract.payment_exists(U64(payment_id + 1)));
+        assert!(!contract.is_payment_active(U64(payment_id + 1)));
+    }
+
+    #[test]
+    fn test_get_multiple_payment_receipts() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+
+        let pending_payment_id = create_payment(&mut contract, 10, 1);
+        let payment_id = create_approved_payment(&mut contract);
+
+        // the deleted payment is reported the same way as the one which never existed
+        let deleted_payment_id = create_payment(&mut contract, 10, 1);
+        contract
+            .remove_payment_related_data(
+                &issuer_acc(),
+                &receiver_acc(),
+                deleted_payment_id,
+                CompletionReason::Claimed,
+                10,
+            )
+            .unwrap();
+
+        let receipts = contract
+            .get_multiple_payment_receipts(vec![
+                U64(payment_id),
+                U64(deleted_payment_id + 1),
+                U64(pending_payment_id),
+                U64(deleted_payment_id),
+            ])
+            .unwrap();
+        assert_eq!(receipts.len(), 4);
+        assert!(receipts[1].is_none());
+        assert!(receipts[3].is_none());
+
+        let receipt = receipts[0].as_ref().unwrap();
+        assert_eq!(receipt.issuer, issuer_acc());
+        assert_eq!(receipt.receiver, receiver_acc());
+        assert_eq!(receipt.payment_info.initiale_date, Some(U64(1)));
+        assert_eq!(receipt.payment_info.period_duration, U64(NANOS_IN_DAY));
+        assert_eq!(receipt.payment_info.total_amount, U128(10));
+
+        let receipt = receipts[2].as_ref().unwrap();
+        assert_eq!(receipt.payment_info.initiale_date, None);
+
+        assert_eq!(
+            contract
+                .get_multiple_payment_receipts(vec![U64(payment_id); MAX_BATCH_SIZE])
+                .unwrap()
+                .len(),
+            MAX_BATCH_SIZE
+        );
+        assert_eq!(
+            contract.get_multiple_payment_receipts(vec![U64(payment_id); MAX_BATCH_SIZE + 1]),
+            Err(ContractError::BatchSizeTooLarge(
+                MAX_BATCH_SIZE + 1,
+                MAX_BATCH_SIZE
+            ))
+        );
+    }
+
+    #[test]
+    fn test_single_and_batch_payment_views_match() {
+        // set contract as an account of contract
+        let mut context = get_context(contract_acc(), 1);
+        context.current_account_id = contract_acc();
+        testing_env!(context.clone());
+
+        let mut contract = PaymentContract::new().unwrap();
+        let payment_id = create_approved_payment(&mut contract);
+
+        let single_view =
+            serde_json::to_value(contract.get_payment(U64(payment_id)).unwrap()).unwrap();
+        let batch_view = serde_json::to_value(
+            contract
+                .get_multiple_payment_receipts(vec![U64(payment_id)])
+                .unwrap()
+                .remove(0)
+                .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(single_view, batch_view);
+
+        // the amounts and the timestamps are passed as strings, so that the js clients don't lose the precision
+        assert_eq!(single_view["payment_info"]["total_amount"], "10");
+        assert_eq!(
+            single_view["payment_info"]["period_duration"],
+            NANOS_IN_DAY.to_string()
+        );
+    }
+}
@@ -1,31 +1,36 @@
-use crate::Result;
+use crate::constants::{MAX_METADATA_LENGTH, MAX_PERIOD_DAYS};
 use near_sdk::{
     borsh::{self, BorshSerialize},
-    AccountId, FunctionError,
+    env, serde_json, AccountId, FunctionError,
 };
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-pub(crate) fn require(cond: bool, err: ContractError) -> Result<()> {
-    match cond {
-        true => Ok(()),
-        false => Err(err),
-    }
+// returns the error from the current function when the condition is not met,
+// the error expression is evaluated only in this case
+macro_rules! ensure {
+    ($cond:expr, $err:expr $(,)?) => {
+        if !($cond) {
+            return Err($err);
+        }
+    };
 }
 
-#[derive(BorshSerialize, Debug, Error, FunctionError, Deserialize, PartialEq)]
+pub(crate) use ensure;
+
+#[derive(BorshSerialize, Debug, Error, PartialEq, Clone)]
 pub enum ContractError {
     #[error("Only contract account itself is possible to initialize the contract")]
     InitializeError,
     #[error(
-        "attached_deposit = {}, payment_amount = {}, days_period_duration = {} should be not 0",
+        "attached_deposit = {}, payment_amount = {}, period_duration = {} should be not 0",
         _0,
         _1,
         _2
     )]
     ZeroPaymentCreationParams(u128, u128, u64),
     #[error(
-        "attached_deposit({}) should be equally devided by the payment_amount({})",
+        "attached_deposit({}) should be not less than the payment_amount({})",
         _0,
         _1
     )]
@@ -42,4 +47,720 @@ pub enum ContractError {
     InternalCalculationError(u64),
     #[error("Payment id {} already exists", _0)]
     PaymentIdAlreadyExists(u64),
+    #[error(
+        "Payment with period_duration = {} and {} periods could not be completed within the u64 time range",
+        _0,
+        _1
+    )]
+    PaymentEndDateOverflow(u64, u128),
+    #[error(
+        "Period duration {} seconds is less than the minimal allowed {} seconds",
+        _0,
+        _1
+    )]
+    PeriodDurationTooShort(u64, u64),
+    #[error("Payment receipt with the payment id {} is already confirmed", _0)]
+    PaymentAlreadyConfirmed(u64),
+    #[error("Memo length {} exceeds the maximal allowed {} bytes", _0, _1)]
+    MemoTooLong(usize, usize),
+    #[error("Account {} is not the owner of the contract", _0)]
+    NotContractOwner(AccountId),
+    #[error(
+        "Account {} has {} active payments, the maximal allowed number is {}",
+        _0,
+        _1,
+        _2
+    )]
+    TooManyActivePayments(AccountId, u32, u32),
+    #[error(
+        "Payment id {} total amount {} is less than the expected minimum {}",
+        _0,
+        _1,
+        _2
+    )]
+    TotalAmountBelowMinimum(u64, u128, u128),
+    #[error("Payment amount {} is less than the minimal allowed {}", _0, _1)]
+    PaymentAmountBelowMinimum(u128, u128),
+    #[error("Fee {} bps exceeds the maximal allowed {} bps", _0, _1)]
+    FeeTooHigh(u16, u16),
+    #[error("Account {} is not the fee recipient", _0)]
+    NotFeeRecipient(AccountId),
+    #[error("Approval deadline {} should be in the future", _0)]
+    InvalidApprovalDeadline(u64),
+    #[error("Payment id {} does not have an approval deadline", _0)]
+    NoApprovalDeadline(u64),
+    #[error("Approval deadline {} of the payment id {} is not reached yet", _1, _0)]
+    ApprovalDeadlineNotReached(u64, u64),
+    #[error(
+        "Receiver weights should be positive and sum up to {} bps, got {} bps",
+        _1,
+        _0
+    )]
+    InvalidReceiverWeights(u32, u16),
+    #[error("Account {} is listed among the receivers more than once", _0)]
+    DuplicateReceiver(AccountId),
+    #[error(
+        "Share of the account {} with the weight {} bps is not an integer part of the payment amount {}",
+        _0,
+        _2,
+        _1
+    )]
+    ReceiverShareNotDivisible(AccountId, u128, u16),
+    #[error("Account {} is not the primary receiver of the payment id {}", _0, _1)]
+    NotPrimaryReceiver(AccountId, u64),
+    #[error("Payment id space is exhausted")]
+    PaymentIdSpaceExhausted,
+    #[error("Batch size {} exceeds the maximal allowed {}", _0, _1)]
+    BatchSizeTooLarge(usize, usize),
+    #[error("Account {} is not allowed to be a receiver", _0)]
+    ReceiverNotAllowed(AccountId),
+    #[error("Payment id {} is already started", _0)]
+    PaymentAlreadyStarted(u64),
+    #[error("Contract is paused")]
+    ContractPaused,
+    #[error("Payment id {} does not require the approval", _0)]
+    ApprovalNotRequired(u64),
+    #[error("Account {} does not have failed transfers", _0)]
+    NoFailedTransfer(AccountId),
+    #[error("Payment id {} does not have an amount ready for the withdrawal", _0)]
+    NothingToWithdraw(u64),
+    #[error("Client nonce length {} exceeds the maximal allowed {} bytes", _0, _1)]
+    ClientNonceTooLong(usize, usize),
+    #[error("Payment id {} is locked until the pending transfer is resolved", _0)]
+    PaymentLocked(u64),
+    #[error("Account {} does not accept the payments", _0)]
+    ReceiverNotAcceptingPayments(AccountId),
+    #[error("Payment id {} could not be force settled before {}", _0, _1)]
+    SettlementTooEarly(u64, u64),
+    #[error(
+        "Start date {} should be in the future and at most {} days ahead",
+        _0,
+        MAX_PERIOD_DAYS
+    )]
+    InvalidStartDate(u64),
+    #[error("Claim is too early, {} seconds remaining", _0)]
+    ClaimTooEarly(u64),
+    #[error("Payment id {} already has a pending amendment", _0)]
+    AmendmentAlreadyPending(u64),
+    #[error("Payment id {} does not have a pending amendment", _0)]
+    NoPendingAmendment(u64),
+    #[error(
+        "Payment id {} has {} to fund the new terms, at least {} is required",
+        _0,
+        _1,
+        _2
+    )]
+    InsufficientTopUp(u64, u128, u128),
+    #[error("Amendment of the payment id {} does not change anything", _0)]
+    EmptyAmendment(u64),
+    #[error("Amendment of the split payment id {} is not supported", _0)]
+    AmendmentNotSupported(u64),
+    #[error("Account {} is not authorized for the payment id {}", _0, _1)]
+    NotAuthorizedForPayment(AccountId, u64),
+    #[error("Issuer {} reached the limit of {} active payments", _0, _1)]
+    IssuerPaymentLimitReached(AccountId, u32),
+    #[error("Account {} is not allowed to create a payment to itself", _0)]
+    SelfPaymentNotAllowed(AccountId),
+    #[error("Required gas {} is more than the prepaid gas {}", _0, _1)]
+    InsufficientGas(u64, u64),
+    #[error(
+        "Period duration {} is longer than the maximum of {} in the same units",
+        _0,
+        _1
+    )]
+    PeriodDurationTooLong(u64, u64),
+    #[error("Settlement offer of the payment id {} is already pending", _0)]
+    SettlementOfferAlreadyPending(u64),
+    #[error("There is no settlement offer for the payment id {}", _0)]
+    NoSettlementOffer(u64),
+    #[error(
+        "Settlement offer of the payment id {} is {}, but only {} is not claimed",
+        _0,
+        _1,
+        _2
+    )]
+    SettlementOfferTooHigh(u64, u128, u128),
+    #[error("Settlement offer of the payment id {} expired at {}", _0, _1)]
+    SettlementOfferExpired(u64, u64),
+    #[error("Settlement offer of the payment id {} is invalidated by a claim", _0)]
+    SettlementOfferInvalidated(u64),
+    #[error("Settlement offer for the split payment id {} is not supported", _0)]
+    SettlementOfferNotSupported(u64),
+    #[error(
+        "Metadata length {} exceeds the maximal allowed {} bytes",
+        _0,
+        MAX_METADATA_LENGTH
+    )]
+    MetadataTooLong(usize),
+    #[error("Cancellation of the payment id {} is already pending", _0)]
+    CancellationAlreadyPending(u64),
+    #[error("There is no pending cancellation of the payment id {}", _0)]
+    NoPendingCancellation(u64),
+    #[error("Cancellation of the payment id {} is effective only at {}", _0, _1)]
+    CancellationNotEffective(u64, u64),
+    #[error("Payment id {} has the same receiver and amounts", _0)]
+    DuplicatePaymentDetected(u64),
+    #[error(
+        "Timestamp {} is earlier than the start date {} of the payment",
+        _0,
+        _1
+    )]
+    ProjectionBeforeStart(u64, u64),
+    #[error("Contract state is not initialized")]
+    StateNotInitialized,
+    #[error("Option {} is not supported by this create method", _0)]
+    CreateOptionNotSupported(String),
+}
+
+// defines the json representation of the contract error variants with the named fields,
+// the fields are listed in the order of the variant values
+macro_rules! error_fields {
+    ($($variant:ident $({ $($field:ident: $ty:ty),* $(,)? })?),* $(,)?) => {
+        #[derive(Serialize, Deserialize)]
+        #[serde(crate = "near_sdk::serde", tag = "error")]
+        enum ContractErrorFields {
+            $($variant $({ $(#[serde(with = "string_field")] $field: $ty),* })?,)*
+        }
+
+        impl From<ContractError> for ContractErrorFields {
+            fn from(error: ContractError) -> Self {
+                match error {
+                    $(ContractError::$variant $(($($field),*))? => {
+                        ContractErrorFields::$variant $({ $($field),* })?
+                    })*
+                }
+            }
+        }
+
+        impl From<ContractErrorFields> for ContractError {
+            fn from(fields: ContractErrorFields) -> Self {
+                match fields {
+                    $(ContractErrorFields::$variant $({ $($field),* })? => {
+                        ContractError::$variant $(($($field),*))?
+                    })*
+                }
+            }
+        }
+    };
+}
+
+error_fields! {
+    InitializeError,
+    ZeroPaymentCreationParams {
+        attached_deposit: u128,
+        payment_amount: u128,
+        period_duration: u64,
+    },
+    IncorrectAmountRelatedParams {
+        attached_deposit: u128,
+        payment_amount: u128,
+    },
+    ReceiverAccountNotExist { account_id: AccountId },
+    IssuerAccountNotExist { account_id: AccountId },
+    PaymentIdNotExist { payment_id: u64 },
+    PaymentReceiptNotConfirmed { payment_id: u64 },
+    InternalCalculationError { payment_id: u64 },
+    PaymentIdAlreadyExists { payment_id: u64 },
+    PaymentEndDateOverflow {
+        period_duration: u64,
+        periods: u128,
+    },
+    PeriodDurationTooShort {
+        period_duration: u64,
+        min_period_duration: u64,
+    },
+    PaymentAlreadyConfirmed { payment_id: u64 },
+    MemoTooLong {
+        length: usize,
+        max_length: usize,
+    },
+    NotContractOwner { account_id: AccountId },
+    TooManyActivePayments {
+        account_id: AccountId,
+        active_payments: u32,
+        max_active_payments: u32,
+    },
+    TotalAmountBelowMinimum {
+        payment_id: u64,
+        total_amount: u128,
+        min_total_amount: u128,
+    },
+    PaymentAmountBelowMinimum {
+        payment_amount: u128,
+        min_payment_amount: u128,
+    },
+    FeeTooHigh {
+        fee_bps: u16,
+        max_fee_bps: u16,
+    },
+    NotFeeRecipient { account_id: AccountId },
+    InvalidApprovalDeadline { approval_deadline: u64 },
+    NoApprovalDeadline { payment_id: u64 },
+    ApprovalDeadlineNotReached {
+        payment_id: u64,
+        approval_deadline: u64,
+    },
+    InvalidReceiverWeights {
+        total_weight_bps: u32,
+        required_weight_bps: u16,
+    },
+    DuplicateReceiver { account_id: AccountId },
+    ReceiverShareNotDivisible {
+        account_id: AccountId,
+        payment_amount: u128,
+        weight_bps: u16,
+    },
+    NotPrimaryReceiver {
+        account_id: AccountId,
+        payment_id: u64,
+    },
+    PaymentIdSpaceExhausted,
+    BatchSizeTooLarge {
+        batch_size: usize,
+        max_batch_size: usize,
+    },
+    ReceiverNotAllowed { account_id: AccountId },
+    PaymentAlreadyStarted { payment_id: u64 },
+    ContractPaused,
+    ApprovalNotRequired { payment_id: u64 },
+    NoFailedTransfer { account_id: AccountId },
+    NothingToWithdraw { payment_id: u64 },
+    ClientNonceTooLong {
+        length: usize,
+        max_length: usize,
+    },
+    PaymentLocked { payment_id: u64 },
+    ReceiverNotAcceptingPayments { account_id: AccountId },
+    SettlementTooEarly {
+        payment_id: u64,
+        settlement_date: u64,
+    },
+    InvalidStartDate { start_date: u64 },
+    ClaimTooEarly { remaining_seconds: u64 },
+    AmendmentAlreadyPending { payment_id: u64 },
+    NoPendingAmendment { payment_id: u64 },
+    InsufficientTopUp {
+        payment_id: u64,
+        available_amount: u128,
+        required_amount: u128,
+    },
+    EmptyAmendment { payment_id: u64 },
+    AmendmentNotSupported { payment_id: u64 },
+    NotAuthorizedForPayment {
+        account_id: AccountId,
+        payment_id: u64,
+    },
+    IssuerPaymentLimitReached {
+        account_id: AccountId,
+        max_active_payments: u32,
+    },
+    SelfPaymentNotAllowed { account_id: AccountId },
+    InsufficientGas {
+        required_gas: u64,
+        prepaid_gas: u64,
+    },
+    PeriodDurationTooLong {
+        period_duration: u64,
+        max_period_duration: u64,
+    },
+    SettlementOfferAlreadyPending { payment_id: u64 },
+    NoSettlementOffer { payment_id: u64 },
+    SettlementOfferTooHigh {
+        payment_id: u64,
+        offered_amount: u128,
+        unclaimed_amount: u128,
+    },
+    SettlementOfferExpired {
+        payment_id: u64,
+        expiration_date: u64,
+    },
+    SettlementOfferInvalidated { payment_id: u64 },
+    SettlementOfferNotSupported { payment_id: u64 },
+    MetadataTooLong { length: usize },
+    CancellationAlreadyPending { payment_id: u64 },
+    NoPendingCancellation { payment_id: u64 },
+    CancellationNotEffective {
+        payment_id: u64,
+        effective_date: u64,
+    },
+    DuplicatePaymentDetected { payment_id: u64 },
+    ProjectionBeforeStart {
+        timestamp: u64,
+        start_date: u64,
+    },
+    StateNotInitialized,
+    CreateOptionNotSupported { option: String },
+}
+
+// the values are serialized as the strings, like U64 and U128, so that the clients do not lose the precision
+mod string_field {
+    use near_sdk::serde::{de, Deserialize, Deserializer, Serializer};
+    use std::fmt::Display;
+    use std::str::FromStr;
+
+    pub fn serialize<T: Display, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromStr,
+        T::Err: Display,
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for ContractError {
+    fn serialize<S: near_sdk::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ContractErrorFields::from(self.clone()).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ContractError {
+    fn deserialize<D: near_sdk::serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        ContractErrorFields::deserialize(deserializer).map(Into::into)
+    }
+}
+
+// json body of the contract error, so that the clients could branch on the code or the tagged error
+// instead of the message, e.g. {"code":6,"error":"PaymentIdNotExist","payment_id":"5","message":"..."}
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractErrorBody {
+    pub code: u32,
+    #[serde(flatten)]
+    pub error: ContractError,
+    pub message: String,
+}
+
+impl ContractError {
+    // the codes are stable, a new variant gets the next free code
+    pub fn code(&self) -> u32 {
+        match self {
+            ContractError::InitializeError => 1,
+            ContractError::ZeroPaymentCreationParams(..) => 2,
+            ContractError::IncorrectAmountRelatedParams(..) => 3,
+            ContractError::ReceiverAccountNotExist(..) => 4,
+            ContractError::IssuerAccountNotExist(..) => 5,
+            ContractError::PaymentIdNotExist(..) => 6,
+            ContractError::PaymentReceiptNotConfirmed(..) => 7,
+            ContractError::InternalCalculationError(..) => 8,
+            ContractError::PaymentIdAlreadyExists(..) => 9,
+            ContractError::PaymentEndDateOverflow(..) => 10,
+            ContractError::PeriodDurationTooShort(..) => 11,
+            ContractError::PaymentAlreadyConfirmed(..) => 12,
+            ContractError::MemoTooLong(..) => 13,
+            ContractError::NotContractOwner(..) => 14,
+            ContractError::TooManyActivePayments(..) => 15,
+            ContractError::TotalAmountBelowMinimum(..) => 16,
+            ContractError::PaymentAmountBelowMinimum(..) => 17,
+            // 18 belonged to the removed PeriodDurationBelowMinimum and is not reused
+            ContractError::FeeTooHigh(..) => 19,
+            ContractError::NotFeeRecipient(..) => 20,
+            ContractError::InvalidApprovalDeadline(..) => 21,
+            ContractError::NoApprovalDeadline(..) => 22,
+            ContractError::ApprovalDeadlineNotReached(..) => 23,
+            ContractError::InvalidReceiverWeights(..) => 24,
+            ContractError::DuplicateReceiver(..) => 25,
+            ContractError::ReceiverShareNotDivisible(..) => 26,
+            ContractError::NotPrimaryReceiver(..) => 27,
+            ContractError::PaymentIdSpaceExhausted => 28,
+            ContractError::BatchSizeTooLarge(..) => 29,
+            ContractError::ReceiverNotAllowed(..) => 30,
+            ContractError::PaymentAlreadyStarted(..) => 31,
+            ContractError::ContractPaused => 32,
+            ContractError::ApprovalNotRequired(..) => 33,
+            ContractError::NoFailedTransfer(..) => 34,
+            ContractError::NothingToWithdraw(..) => 35,
+            ContractError::ClientNonceTooLong(..) => 36,
+            ContractError::PaymentLocked(..) => 37,
+            ContractError::ReceiverNotAcceptingPayments(..) => 38,
+            ContractError::SettlementTooEarly(..) => 39,
+            ContractError::InvalidStartDate(..) => 40,
+            ContractError::ClaimTooEarly(..) => 41,
+            ContractError::AmendmentAlreadyPending(..) => 42,
+            ContractError::NoPendingAmendment(..) => 43,
+            ContractError::InsufficientTopUp(..) => 44,
+            ContractError::EmptyAmendment(..) => 45,
+            ContractError::AmendmentNotSupported(..) => 46,
+            ContractError::NotAuthorizedForPayment(..) => 47,
+            ContractError::IssuerPaymentLimitReached(..) => 48,
+            ContractError::SelfPaymentNotAllowed(..) => 49,
+            ContractError::InsufficientGas(..) => 50,
+            ContractError::PeriodDurationTooLong(..) => 51,
+            ContractError::SettlementOfferAlreadyPending(..) => 52,
+            ContractError::NoSettlementOffer(..) => 53,
+            ContractError::SettlementOfferTooHigh(..) => 54,
+            ContractError::SettlementOfferExpired(..) => 55,
+            ContractError::SettlementOfferInvalidated(..) => 56,
+            ContractError::SettlementOfferNotSupported(..) => 57,
+            ContractError::MetadataTooLong(..) => 58,
+            ContractError::CancellationAlreadyPending(..) => 59,
+            ContractError::NoPendingCancellation(..) => 60,
+            ContractError::CancellationNotEffective(..) => 61,
+            ContractError::DuplicatePaymentDetected(..) => 62,
+            ContractError::ProjectionBeforeStart(..) => 63,
+            ContractError::StateNotInitialized => 64,
+            ContractError::CreateOptionNotSupported(..) => 65,
+        }
+    }
+}
+
+impl From<&ContractError> for ContractErrorBody {
+    fn from(error: &ContractError) -> Self {
+        ContractErrorBody {
+            code: error.code(),
+            error: error.clone(),
+            message: error.to_string(),
+        }
+    }
+}
+
+impl FunctionError for ContractError {
+    fn panic(&self) -> ! {
+        env::panic_str(
+            &serde_json::to_string(&ContractErrorBody::from(self)).unwrap_or_else(|_| env::abort()),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Result;
+    use near_sdk::test_utils::accounts;
+
+    fn check_amount(amount: u128, evaluated: &mut bool) -> Result<u128> {
+        ensure!(amount > 0, {
+            *evaluated = true;
+            ContractError::PaymentAmountBelowMinimum(amount, 1)
+        });
+
+        Ok(amount)
+    }
+
+    #[test]
+    fn test_ensure() {
+        let mut evaluated = false;
+        assert_eq!(check_amount(1, &mut evaluated), Ok(1));
+        assert!(!evaluated);
+
+        assert_eq!(
+            check_amount(0, &mut evaluated),
+            Err(ContractError::PaymentAmountBelowMinimum(0, 1))
+        );
+        assert!(evaluated);
+    }
+
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(
+            ContractError::ApprovalDeadlineNotReached(1, 100).to_string(),
+            "Approval deadline 100 of the payment id 1 is not reached yet"
+        );
+        assert_eq!(
+            ContractError::PaymentAlreadyStarted(1).to_string(),
+            "Payment id 1 is already started"
+        );
+        assert_eq!(
+            ContractError::ContractPaused.to_string(),
+            "Contract is paused"
+        );
+        assert_eq!(
+            ContractError::BatchSizeTooLarge(51, 50).to_string(),
+            "Batch size 51 exceeds the maximal allowed 50"
+        );
+    }
+
+    #[test]
+    fn test_error_codes() {
+        let errors = vec![
+            (ContractError::InitializeError, 1),
+            (ContractError::ZeroPaymentCreationParams(1, 1, 1), 2),
+            (ContractError::IncorrectAmountRelatedParams(1, 1), 3),
+            (ContractError::ReceiverAccountNotExist(accounts(1)), 4),
+            (ContractError::IssuerAccountNotExist(accounts(1)), 5),
+            (ContractError::PaymentIdNotExist(1), 6),
+            (ContractError::PaymentReceiptNotConfirmed(1), 7),
+            (ContractError::InternalCalculationError(1), 8),
+            (ContractError::PaymentIdAlreadyExists(1), 9),
+            (ContractError::PaymentEndDateOverflow(1, 1), 10),
+            (ContractError::PeriodDurationTooShort(1, 1), 11),
+            (ContractError::PaymentAlreadyConfirmed(1), 12),
+            (ContractError::MemoTooLong(1, 1), 13),
+            (ContractError::NotContractOwner(accounts(1)), 14),
+            (ContractError::TooManyActivePayments(accounts(1), 1, 1), 15),
+            (ContractError::TotalAmountBelowMinimum(1, 1, 1), 16),
+            (ContractError::PaymentAmountBelowMinimum(1, 1), 17),
+            (ContractError::FeeTooHigh(1, 1), 19),
+            (ContractError::NotFeeRecipient(accounts(1)), 20),
+            (ContractError::InvalidApprovalDeadline(1), 21),
+            (ContractError::NoApprovalDeadline(1), 22),
+            (ContractError::ApprovalDeadlineNotReached(1, 1), 23),
+            (ContractError::InvalidReceiverWeights(1, 1), 24),
+            (ContractError::DuplicateReceiver(accounts(1)), 25),
+            (
+                ContractError::ReceiverShareNotDivisible(accounts(1), 1, 1),
+                26,
+            ),
+            (ContractError::NotPrimaryReceiver(accounts(1), 1), 27),
+            (ContractError::PaymentIdSpaceExhausted, 28),
+            (ContractError::BatchSizeTooLarge(1, 1), 29),
+            (ContractError::ReceiverNotAllowed(accounts(1)), 30),
+            (ContractError::PaymentAlreadyStarted(1), 31),
+            (ContractError::ContractPaused, 32),
+            (ContractError::ApprovalNotRequired(1), 33),
+            (ContractError::NoFailedTransfer(accounts(1)), 34),
+            (ContractError::NothingToWithdraw(1), 35),
+            (ContractError::ClientNonceTooLong(1, 2), 36),
+            (ContractError::PaymentLocked(1), 37),
+            (ContractError::ReceiverNotAcceptingPayments(accounts(1)), 38),
+            (ContractError::SettlementTooEarly(1, 2), 39),
+            (ContractError::InvalidStartDate(1), 40),
+            (ContractError::ClaimTooEarly(1), 41),
+            (ContractError::AmendmentAlreadyPending(1), 42),
+            (ContractError::NoPendingAmendment(1), 43),
+            (ContractError::InsufficientTopUp(1, 1, 1), 44),
+            (ContractError::EmptyAmendment(1), 45),
+            (ContractError::AmendmentNotSupported(1), 46),
+            (ContractError::NotAuthorizedForPayment(accounts(1), 1), 47),
+            (ContractError::IssuerPaymentLimitReached(accounts(1), 1), 48),
+            (ContractError::SelfPaymentNotAllowed(accounts(1)), 49),
+            (ContractError::InsufficientGas(1, 1), 50),
+            (ContractError::PeriodDurationTooLong(1, 1), 51),
+            (ContractError::SettlementOfferAlreadyPending(1), 52),
+            (ContractError::NoSettlementOffer(1), 53),
+            (ContractError::SettlementOfferTooHigh(1, 1, 1), 54),
+            (ContractError::SettlementOfferExpired(1, 1), 55),
+            (ContractError::SettlementOfferInvalidated(1), 56),
+            (ContractError::SettlementOfferNotSupported(1), 57),
+            (ContractError::MetadataTooLong(1), 58),
+            (ContractError::CancellationAlreadyPending(1), 59),
+            (ContractError::NoPendingCancellation(1), 60),
+            (ContractError::CancellationNotEffective(1, 1), 61),
+            (ContractError::DuplicatePaymentDetected(1), 62),
+            (ContractError::ProjectionBeforeStart(1, 1), 63),
+            (ContractError::StateNotInitialized, 64),
+            (
+                ContractError::CreateOptionNotSupported("period_unit".to_string()),
+                65,
+            ),
+        ];
+
+        for (error, code) in errors {
+            assert_eq!(error.code(), code, "unexpected code of {:?}", error);
+        }
+    }
+
+    #[test]
+    fn test_error_body() {
+        let error = ContractError::PaymentIdNotExist(7);
+
+        assert_eq!(
+            serde_json::to_string(&ContractErrorBody::from(&error)).unwrap(),
+            r#"{"code":6,"error":"PaymentIdNotExist","payment_id":"7","message":"Payment Id 7 does not exist in particular store"}"#
+        );
+    }
+
+    // every variant with the distinct values of the fields, in the order of the codes
+    fn all_errors() -> Vec<ContractError> {
+        vec![
+            ContractError::InitializeError,
+            ContractError::ZeroPaymentCreationParams(1, 2, 3),
+            ContractError::IncorrectAmountRelatedParams(1, 2),
+            ContractError::ReceiverAccountNotExist(accounts(1)),
+            ContractError::IssuerAccountNotExist(accounts(1)),
+            ContractError::PaymentIdNotExist(1),
+            ContractError::PaymentReceiptNotConfirmed(1),
+            ContractError::InternalCalculationError(1),
+            ContractError::PaymentIdAlreadyExists(1),
+            ContractError::PaymentEndDateOverflow(1, 2),
+            ContractError::PeriodDurationTooShort(1, 2),
+            ContractError::PaymentAlreadyConfirmed(1),
+            ContractError::MemoTooLong(1, 2),
+            ContractError::NotContractOwner(accounts(1)),
+            ContractError::TooManyActivePayments(accounts(1), 2, 3),
+            ContractError::TotalAmountBelowMinimum(1, 2, 3),
+            ContractError::PaymentAmountBelowMinimum(1, 2),
+            ContractError::FeeTooHigh(1, 2),
+            ContractError::NotFeeRecipient(accounts(1)),
+            ContractError::InvalidApprovalDeadline(1),
+            ContractError::NoApprovalDeadline(1),
+            ContractError::ApprovalDeadlineNotReached(1, 2),
+            ContractError::InvalidReceiverWeights(1, 2),
+            ContractError::DuplicateReceiver(accounts(1)),
+            ContractError::ReceiverShareNotDivisible(accounts(1), 2, 3),
+            ContractError::NotPrimaryReceiver(accounts(1), 2),
+            ContractError::PaymentIdSpaceExhausted,
+            ContractError::BatchSizeTooLarge(1, 2),
+            ContractError::ReceiverNotAllowed(accounts(1)),
+            ContractError::PaymentAlreadyStarted(1),
+            ContractError::ContractPaused,
+            ContractError::ApprovalNotRequired(1),
+            ContractError::NoFailedTransfer(accounts(1)),
+            ContractError::NothingToWithdraw(1),
+            ContractError::ClientNonceTooLong(1, 2),
+            ContractError::PaymentLocked(1),
+            ContractError::ReceiverNotAcceptingPayments(accounts(1)),
+            ContractError::SettlementTooEarly(1, 2),
+            ContractError::InvalidStartDate(1),
+            ContractError::ClaimTooEarly(1),
+            ContractError::AmendmentAlreadyPending(1),
+            ContractError::NoPendingAmendment(1),
+            ContractError::InsufficientTopUp(1, 2, 3),
+            ContractError::EmptyAmendment(1),
+            ContractError::AmendmentNotSupported(1),
+            ContractError::NotAuthorizedForPayment(accounts(1), 2),
+            ContractError::IssuerPaymentLimitReached(accounts(1), 2),
+            ContractError::SelfPaymentNotAllowed(accounts(1)),
+            ContractError::InsufficientGas(1, 2),
+            ContractError::PeriodDurationTooLong(1, 2),
+            ContractError::SettlementOfferAlreadyPending(1),
+            ContractError::NoSettlementOffer(1),
+            ContractError::SettlementOfferTooHigh(1, 2, 3),
+            ContractError::SettlementOfferExpired(1, 2),
+            ContractError::SettlementOfferInvalidated(1),
+            ContractError::SettlementOfferNotSupported(1),
+            ContractError::MetadataTooLong(1),
+            ContractError::CancellationAlreadyPending(1),
+            ContractError::NoPendingCancellation(1),
+            ContractError::CancellationNotEffective(1, 2),
+            ContractError::DuplicatePaymentDetected(1),
+            ContractError::ProjectionBeforeStart(1, 2),
+            ContractError::StateNotInitialized,
+            ContractError::CreateOptionNotSupported("period_unit".to_string()),
+        ]
+    }
+
+    // the golden file pins the json representation, so that a renamed variant or field breaks this test
+    #[test]
+    fn test_error_bodies_golden() {
+        let golden: serde_json::Value =
+            serde_json::from_str(include_str!("../tests/golden/contract_errors.json")).unwrap();
+
+        let bodies: Vec<ContractErrorBody> = all_errors().iter().map(Into::into).collect();
+
+        assert_eq!(serde_json::to_value(&bodies).unwrap(), golden);
+    }
+
+    #[test]
+    fn test_error_bodies_deserialize() {
+        for error in all_errors() {
+            let json = serde_json::to_string(&ContractErrorBody::from(&error)).unwrap();
+
+            let body: ContractErrorBody = serde_json::from_str(&json).unwrap();
+            assert_eq!(body.error, error);
+            assert_eq!(body.code, error.code());
+            assert_eq!(body.message, error.to_string());
+        }
+    }
 }
@@ -0,0 +1,210 @@
+use near_sdk::{env, json_types::U128, json_types::U64, serde_json, AccountId};
+use serde::Serialize;
+
+pub const EVENT_STANDARD: &str = "near_payment_receiver";
+pub const EVENT_VERSION: &str = "1.0.0";
+// names of the ContractEvent variants as they are logged
+pub const EVENT_TYPES: [&str; 15] = [
+    "minimums_updated",
+    "receiver_payout",
+    "owner_changed",
+    "payment_force_settled",
+    "amendment_proposed",
+    "amendment_resolved",
+    "payment_approved",
+    "settlement_offered",
+    "settlement_resolved",
+    "payment_claimed",
+    "transfer_failed",
+    "transfer_recovered",
+    "cancellation_requested",
+    "cancellation_finalized",
+    "pending_receiver_changed",
+];
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MinimumsUpdated {
+    pub min_payment_amount: U128,
+    pub min_period_duration: U64,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReceiverPayout {
+    pub payment_id: U64,
+    pub receiver: AccountId,
+    pub gross: U128,
+    pub fee: U128,
+    pub net: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OwnerChanged {
+    pub old_owner: AccountId,
+    pub new_owner: AccountId,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PaymentForceSettled {
+    pub payment_id: U64,
+    pub issuer_refund: U128,
+    pub receiver_payout: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AmendmentProposed {
+    pub payment_id: U64,
+    pub payment_amount: U128,
+    pub period_duration: U64,
+    pub top_up: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AmendmentResolved {
+    pub payment_id: U64,
+    pub accepted: bool,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PaymentApproved {
+    pub payment_id: U64,
+    pub created_at: U64,
+    pub approved_at: U64,
+    pub start_date: U64,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SettlementOffered {
+    pub payment_id: U64,
+    pub receiver_amount: U128,
+    pub expires_at: U64,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SettlementResolved {
+    pub payment_id: U64,
+    pub accepted: bool,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PaymentClaimed {
+    pub payment_id: U64,
+    pub receiver: AccountId,
+    pub claimed_amount: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TransferFailed {
+    pub account_id: AccountId,
+    pub amount: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TransferRecovered {
+    pub account_id: AccountId,
+    pub amount: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CancellationRequested {
+    pub payment_id: U64,
+    pub effective_at: U64,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CancellationFinalized {
+    pub payment_id: U64,
+    pub issuer_refund: U128,
+    pub receiver_payout: U128,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingReceiverChanged {
+    pub payment_id: U64,
+    pub old_receiver: AccountId,
+    pub new_receiver: AccountId,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+pub enum ContractEvent {
+    MinimumsUpdated(Vec<MinimumsUpdated>),
+    ReceiverPayout(Vec<ReceiverPayout>),
+    OwnerChanged(Vec<OwnerChanged>),
+    PaymentForceSettled(Vec<PaymentForceSettled>),
+    AmendmentProposed(Vec<AmendmentProposed>),
+    AmendmentResolved(Vec<AmendmentResolved>),
+    PaymentApproved(Vec<PaymentApproved>),
+    SettlementOffered(Vec<SettlementOffered>),
+    SettlementResolved(Vec<SettlementResolved>),
+    PaymentClaimed(Vec<PaymentClaimed>),
+    TransferFailed(Vec<TransferFailed>),
+    TransferRecovered(Vec<TransferRecovered>),
+    CancellationRequested(Vec<CancellationRequested>),
+    CancellationFinalized(Vec<CancellationFinalized>),
+    PendingReceiverChanged(Vec<PendingReceiverChanged>),
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct EventLog<'a> {
+    standard: &'static str,
+    version: &'static str,
+    #[serde(flatten)]
+    event: &'a ContractEvent,
+}
+
+impl ContractEvent {
+    pub fn emit(&self) {
+        let log = EventLog {
+            standard: EVENT_STANDARD,
+            version: EVENT_VERSION,
+            event: self,
+        };
+
+        // NEP-297 event format
+        env::log_str(&format!(
+            "EVENT_JSON:{}",
+            serde_json::to_string(&log).unwrap_or_else(|_| env::abort())
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::get_logs;
+
+    use super::*;
+
+    #[test]
+    fn test_emit_event() {
+        ContractEvent::MinimumsUpdated(vec![MinimumsUpdated {
+            min_payment_amount: U128(10),
+            min_period_duration: U64(1),
+        }])
+        .emit();
+
+        assert_eq!(
+            get_logs(),
+            vec![
+                r#"EVENT_JSON:{"standard":"near_payment_receiver","version":"1.0.0","event":"minimums_updated","data":[{"min_payment_amount":"10","min_period_duration":"1"}]}"#
+            ]
+        );
+    }
+}
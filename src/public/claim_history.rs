@@ -0,0 +1,74 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    store::Vector,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ClaimRecord {
+    pub timestamp: u64,
+    pub amount: u128,
+}
+
+// merges each pair of the neighbouring records into one, so the history keeps the whole claimed amount with less details
+pub(crate) fn compact_claim_history(history: &mut Vector<ClaimRecord>) {
+    let len = history.len();
+
+    for index in 0..len / 2 {
+        let first = &history[2 * index];
+        let second = &history[2 * index + 1];
+
+        let merged = ClaimRecord {
+            timestamp: second.timestamp,
+            amount: first.amount.saturating_add(second.amount),
+        };
+
+        history.set(index, merged);
+    }
+
+    if len % 2 == 1 {
+        let last = history[len - 1].clone();
+        history.set(len / 2, last);
+    }
+
+    while history.len() > len.div_ceil(2) {
+        history.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compact_claim_history() {
+        let mut history = Vector::new(b"h".to_vec());
+        for timestamp in 1..=5 {
+            history.push(ClaimRecord {
+                timestamp,
+                amount: timestamp as u128 * 10,
+            });
+        }
+
+        compact_claim_history(&mut history);
+
+        assert_eq!(
+            history.iter().cloned().collect::<Vec<_>>(),
+            vec![
+                ClaimRecord {
+                    timestamp: 2,
+                    amount: 30
+                },
+                ClaimRecord {
+                    timestamp: 4,
+                    amount: 70
+                },
+                ClaimRecord {
+                    timestamp: 5,
+                    amount: 50
+                },
+            ]
+        );
+    }
+}
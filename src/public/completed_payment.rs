@@ -0,0 +1,28 @@
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    AccountId,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum CompletionReason {
+    Claimed,
+    RejectedByIssuer,
+    RejectedByReceiver,
+    Expired,
+    ForceSettled,
+    SettlementAccepted,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CompletedPaymentRecord {
+    pub issuer: AccountId,
+    pub receiver: AccountId,
+    pub total_amount: u128,
+    pub completed_at: u64,
+    pub completion_reason: CompletionReason,
+}
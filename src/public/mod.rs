@@ -1,12 +1,20 @@
 use near_sdk::{
-    borsh::{self, BorshSerialize},
-    json_types::U64,
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    json_types::{U128, U64},
     AccountId, BorshStorageKey,
 };
 use serde::{Deserialize, Serialize};
 
+use crate::constants::{CONTRACT_SOURCE_LINK, CONTRACT_VERSION};
+use crate::events::{EVENT_STANDARD, EVENT_VERSION};
+use payment_info::{PeriodUnit, VestingMode};
+use payment_receipt::PaymentReceiptView;
+
+pub mod claim_history;
+pub mod completed_payment;
 pub mod payment_info;
 pub mod payment_receipt;
+pub mod receiver_prefs;
 
 #[derive(Debug, BorshStorageKey, BorshSerialize, PartialEq, Eq)]
 pub enum StorageKey {
@@ -15,6 +23,13 @@ pub enum StorageKey {
     PaymentReceiptLedger,
     IssuerLedgerRecord { user: AccountId },
     ReceiverLedgerRecord { user: AccountId },
+    CompletedPaymentLedger,
+    AllowedReceivers,
+    ClaimHistoryLedger,
+    ClaimHistory { payment_id: u64 },
+    FailedTransfers,
+    ClientNonces,
+    ReceiverPrefs,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -29,4 +44,182 @@ pub enum PaymentRole {
 pub enum ProcessStatus {
     Approve(U64),
     Reject(U64),
+    ApproveWithMin(U64, U128),
+    // the payment id and the future timestamp in nanoseconds when the stream starts
+    ApproveDelayed(U64, U64),
+}
+
+// optional settings of the new payment, the omitted fields get the defaults
+#[derive(Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde", default)]
+pub struct CreatePaymentOptions {
+    pub memo: Option<String>,
+    pub approval_deadline: Option<U64>,
+    pub vesting_mode: VestingMode,
+    pub client_nonce: Option<String>,
+    pub min_claim_interval: Option<U64>,
+    // days when omitted
+    pub period_unit: Option<PeriodUnit>,
+    pub metadata: Option<String>,
+    // fails the creation when the issuer has the same stream to the same receiver
+    pub require_unique: bool,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RejectPaymentResult {
+    pub issuer_refund: U128,
+    pub receiver_payout: U128,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractStats {
+    pub total_payments_created: U64,
+    pub active_payments: U64,
+    pub unique_issuers: U64,
+    pub unique_receivers: U64,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PaymentCountByStatus {
+    // not approved by the receiver yet
+    pub pending: U64,
+    pub active: U64,
+    // the final payment is claimable, the payment is closed by the next claim
+    pub final_period: U64,
+    // the status of the payment could not be calculated, e.g. because of an overflow
+    pub errored: U64,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractLimits {
+    pub max_payments_per_account: u32,
+    pub min_payment_amount: U128,
+    pub min_period_duration: U64,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FeeConfig {
+    pub fee_bps: u16,
+    pub fee_recipient: AccountId,
+}
+
+// lets the indexers configure themselves for the events of the contract
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractMetadata {
+    pub standard: String,
+    pub version: String,
+    pub event_types: Vec<String>,
+}
+
+// NEP-330 source metadata, lets the wallets and explorers verify the code of the contract
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractSourceMetadata {
+    pub version: Option<String>,
+    pub link: Option<String>,
+    pub standards: Vec<Standard>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Standard {
+    pub standard: String,
+    pub version: String,
+}
+
+impl Default for ContractSourceMetadata {
+    fn default() -> Self {
+        ContractSourceMetadata {
+            version: Some(CONTRACT_VERSION.to_string()),
+            link: Some(CONTRACT_SOURCE_LINK.to_string()),
+            standards: vec![
+                Standard {
+                    standard: "nep330".to_string(),
+                    version: "1.1.0".to_string(),
+                },
+                Standard {
+                    standard: "nep297".to_string(),
+                    version: "1.0.0".to_string(),
+                },
+                Standard {
+                    standard: EVENT_STANDARD.to_string(),
+                    version: EVENT_VERSION.to_string(),
+                },
+            ],
+        }
+    }
+}
+
+// continuation is set when the rest of the streams should be claimed by the next call
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ClaimAllResult {
+    pub claims: Vec<(U64, U128)>,
+    pub continuation: bool,
+}
+
+// remaining is the number of the receipts which are not checked by the migration yet
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MigrationResult {
+    pub migrated: U64,
+    pub remaining: U64,
+}
+
+// total_locked is the sum of the receipts checked so far, it is applied when nothing remains
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LockedRecalculationResult {
+    pub total_locked: U128,
+    pub remaining: U64,
+}
+
+// orphaned ids are kept in the account ledgers without the receipt,
+// missing receipts are stored but absent in the ledger of the issuer or of one of the receivers
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct IntegrityReport {
+    pub orphaned_issuer_ids: Vec<U64>,
+    pub orphaned_receiver_ids: Vec<U64>,
+    pub missing_receipts: Vec<U64>,
+}
+
+// next_cursor is the first payment id of the next page, None when the last page is returned
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PaginatedPaymentIds {
+    pub ids: Vec<U64>,
+    pub next_cursor: Option<U64>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountPayments {
+    pub as_issuer: Vec<U64>,
+    pub as_receiver: Vec<U64>,
+}
+
+// claimable_amount is claimable by all the receivers now, remainder_amount is not paid out yet
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ExportedPayment {
+    pub payment_id: U64,
+    pub receipt: PaymentReceiptView,
+    pub claimable_amount: U128,
+    pub remainder_amount: U128,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountExport {
+    pub state_version: u32,
+    pub account_id: AccountId,
+    pub as_issuer: Vec<ExportedPayment>,
+    pub as_receiver: Vec<ExportedPayment>,
 }
@@ -1,9 +1,13 @@
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
     env,
+    json_types::{U128, U64},
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
+use crate::constants::{
+    NANOS_IN_DAY, NANOS_IN_HOUR, NANOS_IN_MINUTE, NANOS_IN_SECOND, NANOS_IN_WEEK,
+};
 use crate::error::ContractError;
 
 #[derive(PartialEq, Debug)]
@@ -13,6 +17,89 @@ pub(crate) enum PaymentStatus {
     FinalPayment(u128),
 }
 
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum PublicPaymentStatus {
+    Pending,
+    Absent,
+    PaymentReady(U128),
+    FinalPayment(U128),
+}
+
+impl From<PaymentStatus> for PublicPaymentStatus {
+    fn from(status: PaymentStatus) -> Self {
+        match status {
+            PaymentStatus::Absent => PublicPaymentStatus::Absent,
+            PaymentStatus::PaymentReady(amount) => PublicPaymentStatus::PaymentReady(amount.into()),
+            PaymentStatus::FinalPayment(amount) => PublicPaymentStatus::FinalPayment(amount.into()),
+        }
+    }
+}
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum VestingMode {
+    // the payment amount is unlocked at the end of each period
+    #[default]
+    Periodic,
+    // the total amount is unlocked continuously from the start until the end date
+    Linear,
+}
+
+// unit of the period duration passed on the payment creation
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug, Default,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum PeriodUnit {
+    Seconds,
+    Minutes,
+    Hours,
+    #[default]
+    Days,
+    Weeks,
+}
+
+impl PeriodUnit {
+    pub(crate) fn nanos(self) -> u64 {
+        match self {
+            PeriodUnit::Seconds => NANOS_IN_SECOND,
+            PeriodUnit::Minutes => NANOS_IN_MINUTE,
+            PeriodUnit::Hours => NANOS_IN_HOUR,
+            PeriodUnit::Days => NANOS_IN_DAY,
+            PeriodUnit::Weeks => NANOS_IN_WEEK,
+        }
+    }
+
+    // the largest unit which divides the duration, used when the original unit is unknown
+    pub(crate) fn from_duration(period_duration: u64) -> (PeriodUnit, u64) {
+        [
+            PeriodUnit::Weeks,
+            PeriodUnit::Days,
+            PeriodUnit::Hours,
+            PeriodUnit::Minutes,
+        ]
+        .into_iter()
+        .find(|unit| period_duration.is_multiple_of(unit.nanos()))
+        .map_or(
+            (PeriodUnit::Seconds, period_duration / NANOS_IN_SECOND),
+            |unit| (unit, period_duration / unit.nanos()),
+        )
+    }
+}
+
+// layout of the payment info stored with PaymentReceiptV1
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+pub struct PaymentInfoV1 {
+    pub initiale_date: Option<u64>,
+    pub period_duration: u64,
+    pub payment_amount: u128,
+    pub total_amount: u128,
+    pub last_payment_date: Option<u64>,
+}
+
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
 pub struct PaymentInfo {
     pub initiale_date: Option<u64>,
@@ -20,6 +107,99 @@ pub struct PaymentInfo {
     pub payment_amount: u128,
     pub total_amount: u128,
     pub last_payment_date: Option<u64>,
+    pub vesting_mode: VestingMode,
+    pub claimed_amount: u128,
+    // the minimal time in nanoseconds between the claims of the receiver
+    pub min_claim_interval: Option<u64>,
+    // the actual time of the last claim, unlike last_payment_date it is not snapped to the period
+    pub last_claim_date: Option<u64>,
+    // the amount paid out over the whole life of the payment, unlike claimed_amount it is not reset by the amendments
+    pub lifetime_claimed_amount: u128,
+}
+
+// json friendly representation of the payment info
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PaymentInfoView {
+    pub initiale_date: Option<U64>,
+    pub period_duration: U64,
+    pub payment_amount: U128,
+    pub total_amount: U128,
+    pub last_payment_date: Option<U64>,
+    pub vesting_mode: VestingMode,
+    pub claimed_amount: U128,
+    pub min_claim_interval: Option<U64>,
+    pub lifetime_claimed_amount: U128,
+}
+
+impl From<&PaymentInfo> for PaymentInfoView {
+    fn from(payment_info: &PaymentInfo) -> Self {
+        PaymentInfoView {
+            initiale_date: payment_info.initiale_date.map(U64),
+            period_duration: payment_info.period_duration.into(),
+            payment_amount: payment_info.payment_amount.into(),
+            total_amount: payment_info.total_amount.into(),
+            last_payment_date: payment_info.last_payment_date.map(U64),
+            vesting_mode: payment_info.vesting_mode,
+            claimed_amount: payment_info.claimed_amount.into(),
+            min_claim_interval: payment_info.min_claim_interval.map(U64),
+            lifetime_claimed_amount: payment_info.lifetime_claimed_amount.into(),
+        }
+    }
+}
+
+// the time related values are zeros while the payment is not confirmed
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PaymentProgress {
+    pub confirmed: bool,
+    pub end_date: U64,
+    pub periods_total: U64,
+    pub periods_claimed: U64,
+    pub periods_available: U64,
+    pub amount_claimed: U128,
+    pub amount_remaining: U128,
+}
+
+impl From<PaymentInfoV1> for PaymentInfo {
+    fn from(payment_info: PaymentInfoV1) -> Self {
+        let mut payment_info = PaymentInfo {
+            initiale_date: payment_info.initiale_date,
+            period_duration: payment_info.period_duration,
+            payment_amount: payment_info.payment_amount,
+            total_amount: payment_info.total_amount,
+            last_payment_date: payment_info.last_payment_date,
+            vesting_mode: VestingMode::Periodic,
+            claimed_amount: 0,
+            min_claim_interval: None,
+            last_claim_date: None,
+            lifetime_claimed_amount: 0,
+        };
+
+        // legacy payments have only the periodic bookkeeping, so the claimed amount is derived from it
+        payment_info.claimed_amount = payment_info.legacy_claimed_amount().unwrap_or(0);
+        payment_info.lifetime_claimed_amount = payment_info.claimed_amount;
+
+        payment_info
+    }
+}
+
+// calculates value * numerator / denominator rounded down without the intermediate overflow, numerator should not exceed denominator
+fn mul_div_floor(value: u128, numerator: u64, denominator: u64) -> Option<u128> {
+    let numerator = u128::from(numerator);
+    let denominator = u128::from(denominator);
+
+    if numerator > denominator {
+        return None;
+    }
+
+    let quotient = value.checked_div(denominator)?;
+    let remainder = value % denominator;
+
+    // remainder and numerator are below 2^64, so their product fits into u128
+    quotient
+        .checked_mul(numerator)?
+        .checked_add(remainder * numerator / denominator)
 }
 
 impl PaymentInfo {
@@ -30,52 +210,120 @@ impl PaymentInfo {
             payment_amount,
             total_amount,
             last_payment_date: None,
+            vesting_mode: VestingMode::Periodic,
+            claimed_amount: 0,
+            min_claim_interval: None,
+            last_claim_date: None,
+            lifetime_claimed_amount: 0,
         }
     }
 
-    fn calculate_payment_status_impl(
-        &mut self,
+    // the last period pays the rest of the total amount, so it could be less than payment_amount
+    pub(crate) fn max_payments_number(&self) -> Option<u64> {
+        self.total_amount
+            .checked_div(self.payment_amount)
+            .map(|value| match self.total_amount % self.payment_amount {
+                0 => value,
+                _ => value + 1,
+            })
+            .and_then(|value| u64::try_from(value).ok())
+    }
+
+    // returns the amount streamed after the particular number of periods
+    pub(crate) fn streamed_amount(&self, number_of_payments: u64) -> Option<u128> {
+        self.payment_amount
+            .checked_mul(number_of_payments as u128)
+            .map(|value| value.min(self.total_amount))
+    }
+
+    // returns the date of the last period for the stream started at initiale_date, None on overflow
+    pub(crate) fn calculate_end_date(&self, initiale_date: u64) -> Option<u64> {
+        self.max_payments_number()
+            .and_then(|value| value.checked_mul(self.period_duration))
+            .and_then(|value| initiale_date.checked_add(value))
+    }
+
+    // returns the number of the periods paid by the previous claims and the number of the periods available now
+    fn calculate_payments_number(
+        &self,
+        payment_id: u64,
+        initiale_date: u64,
+        current_time: u64,
+    ) -> Result<(u64, u64), ContractError> {
+        let last_payment_received = self.last_payment_date.unwrap_or(initiale_date);
+
+        // nothing is available before the start date of the delayed stream
+        let mut number_of_available_payments = current_time
+            .checked_sub(last_payment_received)
+            .and_then(|diff| diff.checked_div(self.period_duration))
+            .unwrap_or(0);
+
+        let number_of_made_payments = last_payment_received
+            .checked_sub(initiale_date)
+            .and_then(|diff| diff.checked_div(self.period_duration))
+            .unwrap_or(0);
+
+        let max_payments_number = self
+            .max_payments_number()
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        if number_of_available_payments + number_of_made_payments > max_payments_number {
+            number_of_available_payments =
+                max_payments_number.saturating_sub(number_of_made_payments);
+        }
+
+        Ok((number_of_made_payments, number_of_available_payments))
+    }
+
+    // the claim of the periodic payment is dated by the boundary of the last paid period instead of the claim time,
+    // so that the part of the period passed since the boundary is not lost by the next claim
+    pub(crate) fn calculate_claim_date(
+        &self,
+        payment_id: u64,
+        current_time: u64,
+    ) -> Result<u64, ContractError> {
+        if self.vesting_mode == VestingMode::Linear {
+            return Ok(current_time);
+        }
+
+        let initiale_date = self
+            .initiale_date
+            .ok_or(ContractError::PaymentReceiptNotConfirmed(payment_id))?;
+
+        let (number_of_made_payments, number_of_available_payments) =
+            self.calculate_payments_number(payment_id, initiale_date, current_time)?;
+
+        number_of_made_payments
+            .checked_add(number_of_available_payments)
+            .and_then(|value| value.checked_mul(self.period_duration))
+            .and_then(|value| initiale_date.checked_add(value))
+            .ok_or(ContractError::InternalCalculationError(payment_id))
+    }
+
+    pub(crate) fn calculate_payment_status_impl(
+        &self,
         payment_id: u64,
         current_time: u64,
     ) -> Result<PaymentStatus, ContractError> {
+        if self.vesting_mode == VestingMode::Linear {
+            return self.calculate_linear_payment_status(payment_id, current_time);
+        }
+
         match self.initiale_date {
             Some(initiale_date) => {
-                let last_payment_received = self.last_payment_date.unwrap_or(initiale_date);
-
-                let mut number_of_available_payments = current_time
-                    .checked_sub(last_payment_received)
-                    .and_then(|diff| diff.checked_div(self.period_duration))
-                    .unwrap_or(0);
-
-                let number_of_made_payments = last_payment_received
-                    .checked_sub(initiale_date)
-                    .and_then(|diff| diff.checked_div(self.period_duration))
-                    .unwrap_or(0);
-
-                let max_payments_number = self
-                    .total_amount
-                    .checked_div(self.payment_amount)
-                    .ok_or_else(|| ContractError::InternalCalculationError(payment_id))?
-                    as u64;
-
-                if number_of_available_payments + number_of_made_payments > max_payments_number {
-                    number_of_available_payments = max_payments_number
-                        .checked_sub(number_of_made_payments)
-                        .unwrap_or(0);
-                }
+                let (number_of_made_payments, number_of_available_payments) =
+                    self.calculate_payments_number(payment_id, initiale_date, current_time)?;
 
-                let end_date = initiale_date
-                    .checked_add(
-                        max_payments_number
-                            .checked_mul(self.period_duration)
-                            .ok_or_else(|| ContractError::InternalCalculationError(payment_id))?,
-                    )
-                    .ok_or_else(|| ContractError::InternalCalculationError(payment_id))?;
+                let end_date = self
+                    .calculate_end_date(initiale_date)
+                    .ok_or(ContractError::InternalCalculationError(payment_id))?;
 
-                let amount = self
-                    .payment_amount
-                    .checked_mul(number_of_available_payments as u128)
-                    .ok_or_else(|| ContractError::InternalCalculationError(payment_id))?;
+                let amount = number_of_made_payments
+                    .checked_add(number_of_available_payments)
+                    .and_then(|value| self.streamed_amount(value))
+                    .zip(self.streamed_amount(number_of_made_payments))
+                    .and_then(|(streamed, paid)| streamed.checked_sub(paid))
+                    .ok_or(ContractError::InternalCalculationError(payment_id))?;
 
                 if amount == 0 {
                     Ok(PaymentStatus::Absent)
@@ -89,8 +337,40 @@ impl PaymentInfo {
         }
     }
 
+    // the vested amount is total_amount * elapsed / full_duration rounded down
+    fn calculate_linear_payment_status(
+        &self,
+        payment_id: u64,
+        current_time: u64,
+    ) -> Result<PaymentStatus, ContractError> {
+        let initiale_date = self
+            .initiale_date
+            .ok_or(ContractError::PaymentReceiptNotConfirmed(payment_id))?;
+
+        let end_date = self
+            .calculate_end_date(initiale_date)
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        let full_duration = end_date - initiale_date;
+        let elapsed = current_time
+            .saturating_sub(initiale_date)
+            .min(full_duration);
+
+        let amount = mul_div_floor(self.total_amount, elapsed, full_duration)
+            .and_then(|vested_amount| vested_amount.checked_sub(self.claimed_amount))
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        if amount == 0 {
+            Ok(PaymentStatus::Absent)
+        } else if current_time >= end_date {
+            Ok(PaymentStatus::FinalPayment(amount))
+        } else {
+            Ok(PaymentStatus::PaymentReady(amount))
+        }
+    }
+
     pub(crate) fn calculate_payment_status(
-        &mut self,
+        &self,
         payment_id: u64,
     ) -> Result<PaymentStatus, ContractError> {
         let current_time = env::block_timestamp();
@@ -98,31 +378,148 @@ impl PaymentInfo {
         self.calculate_payment_status_impl(payment_id, current_time)
     }
 
-    pub(crate) fn calculate_remainder_amount(
+    // returns the date when the next period could be claimed, None when the payment is not confirmed or all periods are passed
+    pub(crate) fn calculate_next_payment_date(
         &self,
         payment_id: u64,
-    ) -> Result<u128, ContractError> {
+    ) -> Result<Option<u64>, ContractError> {
         match self.initiale_date {
-            Some(intiale_date) => match self.last_payment_date {
-                Some(last_payment_date) => {
-                    let number_of_received_payments = last_payment_date
-                        .checked_sub(intiale_date)
-                        .map(|value| value.checked_div(self.period_duration))
-                        .flatten()
-                        .ok_or_else(|| ContractError::InternalCalculationError(payment_id))?;
-
-                    let total_payed = self
-                        .payment_amount
-                        .checked_mul(number_of_received_payments as u128)
-                        .ok_or_else(|| ContractError::InternalCalculationError(payment_id))?;
-
-                    self.total_amount
-                        .checked_sub(total_payed)
-                        .ok_or_else(|| ContractError::InternalCalculationError(payment_id))
+            Some(initiale_date) => {
+                let next_payment_date = self
+                    .last_payment_date
+                    .unwrap_or(initiale_date)
+                    .checked_add(self.period_duration)
+                    .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+                let end_date = self
+                    .calculate_end_date(initiale_date)
+                    .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+                Ok((next_payment_date <= end_date).then_some(next_payment_date))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub(crate) fn calculate_progress(
+        &self,
+        payment_id: u64,
+        current_time: u64,
+    ) -> Result<PaymentProgress, ContractError> {
+        let periods_total = self
+            .max_payments_number()
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        let amount_remaining = self.calculate_remainder_amount(payment_id)?;
+
+        let initiale_date = match self.initiale_date {
+            Some(initiale_date) => initiale_date,
+            None => {
+                return Ok(PaymentProgress {
+                    confirmed: false,
+                    end_date: U64(0),
+                    periods_total: periods_total.into(),
+                    periods_claimed: U64(0),
+                    periods_available: U64(0),
+                    amount_claimed: U128(0),
+                    amount_remaining: amount_remaining.into(),
+                })
+            }
+        };
+
+        let end_date = self
+            .calculate_end_date(initiale_date)
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        let passed_periods = |date: u64| {
+            (date.saturating_sub(initiale_date) / self.period_duration).min(periods_total)
+        };
+
+        let periods_claimed = passed_periods(self.last_payment_date.unwrap_or(initiale_date));
+        let periods_available = passed_periods(current_time).saturating_sub(periods_claimed);
+
+        Ok(PaymentProgress {
+            confirmed: true,
+            end_date: end_date.into(),
+            periods_total: periods_total.into(),
+            periods_claimed: periods_claimed.into(),
+            periods_available: periods_available.into(),
+            amount_claimed: self.claimed_amount.into(),
+            amount_remaining: amount_remaining.into(),
+        })
+    }
+
+    // the interval is counted from the last claim or from the start of the stream
+    pub(crate) fn check_claim_interval(&self, current_time: u64) -> Result<(), ContractError> {
+        let (min_claim_interval, initiale_date) =
+            match (self.min_claim_interval, self.initiale_date) {
+                (Some(min_claim_interval), Some(initiale_date)) => {
+                    (min_claim_interval, initiale_date)
                 }
-                None => Ok(self.total_amount),
-            },
-            None => Ok(self.total_amount),
+                _ => return Ok(()),
+            };
+
+        let elapsed = current_time.saturating_sub(self.last_claim_date.unwrap_or(initiale_date));
+
+        if elapsed >= min_claim_interval {
+            return Ok(());
+        }
+
+        // rounded up, so that the claim is not too early after the returned number of seconds
+        Err(ContractError::ClaimTooEarly(
+            (min_claim_interval - elapsed).div_ceil(NANOS_IN_SECOND),
+        ))
+    }
+
+    // registers the claimed amount, the claim date is kept for the periodic bookkeeping
+    pub(crate) fn register_claim(
+        &mut self,
+        payment_id: u64,
+        amount: u128,
+        claim_date: u64,
+    ) -> Result<(), ContractError> {
+        let claim_date = self.calculate_claim_date(payment_id, claim_date)?;
+
+        self.claimed_amount = self
+            .claimed_amount
+            .checked_add(amount)
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+        self.record_lifetime_claim(payment_id, amount)?;
+        self.last_payment_date = Some(claim_date);
+
+        Ok(())
+    }
+
+    pub(crate) fn record_lifetime_claim(
+        &mut self,
+        payment_id: u64,
+        amount: u128,
+    ) -> Result<(), ContractError> {
+        self.lifetime_claimed_amount = self
+            .lifetime_claimed_amount
+            .checked_add(amount)
+            .ok_or(ContractError::InternalCalculationError(payment_id))?;
+
+        Ok(())
+    }
+
+    pub(crate) fn calculate_remainder_amount(
+        &self,
+        payment_id: u64,
+    ) -> Result<u128, ContractError> {
+        self.total_amount
+            .checked_sub(self.claimed_amount)
+            .ok_or(ContractError::InternalCalculationError(payment_id))
+    }
+
+    // the amount paid by the periods before the last claim, only used to migrate the legacy payments
+    fn legacy_claimed_amount(&self) -> Option<u128> {
+        match (self.initiale_date, self.last_payment_date) {
+            (Some(initiale_date), Some(last_payment_date)) => last_payment_date
+                .checked_sub(initiale_date)
+                .and_then(|value| value.checked_div(self.period_duration))
+                .and_then(|value| self.streamed_amount(value)),
+            _ => Some(0),
         }
     }
 }
@@ -133,7 +530,7 @@ mod tests {
 
     #[test]
     fn test_calculate_payment_status_no_initial_date() {
-        let mut payment_info = PaymentInfo::new(60, 100, 500);
+        let payment_info = PaymentInfo::new(60, 100, 500);
 
         assert_eq!(
             payment_info.calculate_payment_status_impl(0, 0),
@@ -241,8 +638,308 @@ mod tests {
     fn test_calculate_remainder_amount_some_payments_made() {
         let mut payment_info = PaymentInfo::new(60, 100, 500);
         payment_info.initiale_date = Some(0);
-        payment_info.last_payment_date = Some(60);
+        payment_info.register_claim(0, 100, 60).unwrap();
 
+        assert_eq!(payment_info.last_payment_date, Some(60));
         assert_eq!(payment_info.calculate_remainder_amount(0), Ok(400));
     }
+
+    #[test]
+    fn test_max_payments_number_with_partial_final_payment() {
+        assert_eq!(PaymentInfo::new(60, 30, 90).max_payments_number(), Some(3));
+        assert_eq!(PaymentInfo::new(60, 30, 100).max_payments_number(), Some(4));
+        assert_eq!(PaymentInfo::new(60, 0, 100).max_payments_number(), None);
+    }
+
+    #[test]
+    fn test_calculate_payment_status_partial_final_payment() {
+        let mut payment_info = PaymentInfo::new(60, 30, 100);
+        payment_info.initiale_date = Some(0);
+
+        assert_eq!(
+            payment_info.calculate_payment_status_impl(0, 190),
+            Ok(PaymentStatus::PaymentReady(90))
+        );
+
+        // the last period pays only the rest of the total amount
+        assert_eq!(
+            payment_info.calculate_payment_status_impl(0, 240),
+            Ok(PaymentStatus::FinalPayment(100))
+        );
+
+        payment_info.last_payment_date = Some(190);
+        assert_eq!(
+            payment_info.calculate_payment_status_impl(0, 500),
+            Ok(PaymentStatus::FinalPayment(10))
+        );
+    }
+
+    #[test]
+    fn test_calculate_remainder_amount_partial_final_payment() {
+        let mut payment_info = PaymentInfo::new(60, 30, 100);
+        payment_info.initiale_date = Some(0);
+        payment_info.register_claim(0, 60, 130).unwrap();
+
+        assert_eq!(payment_info.calculate_remainder_amount(0), Ok(40));
+
+        payment_info.register_claim(0, 40, 240).unwrap();
+        assert_eq!(payment_info.calculate_remainder_amount(0), Ok(0));
+    }
+
+    #[test]
+    fn test_mul_div_floor() {
+        assert_eq!(mul_div_floor(10, 1, 3), Some(3));
+        assert_eq!(
+            mul_div_floor(u128::MAX, u64::MAX, u64::MAX),
+            Some(u128::MAX)
+        );
+        assert_eq!(
+            mul_div_floor(u128::MAX, u64::MAX - 1, u64::MAX),
+            Some(u128::MAX - u128::MAX / u128::from(u64::MAX))
+        );
+        assert_eq!(mul_div_floor(10, 4, 3), None);
+        assert_eq!(mul_div_floor(10, 0, 0), None);
+    }
+
+    #[test]
+    fn test_calculate_linear_payment_status() {
+        let mut payment_info = PaymentInfo::new(60, 100, 500);
+        payment_info.vesting_mode = VestingMode::Linear;
+
+        assert_eq!(
+            payment_info.calculate_payment_status_impl(0, 0),
+            Err(ContractError::PaymentReceiptNotConfirmed(0))
+        );
+
+        payment_info.initiale_date = Some(0);
+
+        // the amount is unlocked continuously instead of the period jumps
+        assert_eq!(
+            payment_info.calculate_payment_status_impl(0, 0),
+            Ok(PaymentStatus::Absent)
+        );
+        assert_eq!(
+            payment_info.calculate_payment_status_impl(0, 30),
+            Ok(PaymentStatus::PaymentReady(50))
+        );
+        assert_eq!(
+            payment_info.calculate_payment_status_impl(0, 299),
+            Ok(PaymentStatus::PaymentReady(498))
+        );
+
+        payment_info.register_claim(0, 498, 299).unwrap();
+        assert_eq!(payment_info.calculate_remainder_amount(0), Ok(2));
+        assert_eq!(
+            payment_info.calculate_payment_status_impl(0, 299),
+            Ok(PaymentStatus::Absent)
+        );
+        assert_eq!(
+            payment_info.calculate_payment_status_impl(0, 1000),
+            Ok(PaymentStatus::FinalPayment(2))
+        );
+    }
+
+    #[test]
+    fn test_calculate_payment_status_mid_period_by_vesting_mode() {
+        let mut payment_info = PaymentInfo::new(60, 100, 1000);
+        payment_info.initiale_date = Some(0);
+
+        // two and a half periods are passed
+        assert_eq!(
+            payment_info.calculate_payment_status_impl(0, 150),
+            Ok(PaymentStatus::PaymentReady(200))
+        );
+
+        payment_info.vesting_mode = VestingMode::Linear;
+        assert_eq!(
+            payment_info.calculate_payment_status_impl(0, 150),
+            Ok(PaymentStatus::PaymentReady(250))
+        );
+    }
+
+    #[test]
+    fn test_calculate_linear_payment_status_large_amounts() {
+        const ONE_NEAR: u128 = 10u128.pow(24);
+        const NANOS_IN_YEAR: u64 = 365 * 86400 * 1_000_000_000;
+
+        // 1 billion NEAR streamed during 100 years
+        let mut payment_info = PaymentInfo::new(
+            NANOS_IN_YEAR,
+            10_000_000 * ONE_NEAR,
+            1_000_000_000 * ONE_NEAR,
+        );
+        payment_info.vesting_mode = VestingMode::Linear;
+        payment_info.initiale_date = Some(1);
+
+        assert_eq!(
+            payment_info.calculate_payment_status_impl(0, 50 * NANOS_IN_YEAR + 1),
+            Ok(PaymentStatus::PaymentReady(500_000_000 * ONE_NEAR))
+        );
+
+        // one nanosecond unlocks the exact part of the total amount
+        let full_duration = u128::from(100 * NANOS_IN_YEAR);
+        assert_eq!(
+            payment_info.calculate_payment_status_impl(0, 2),
+            Ok(PaymentStatus::PaymentReady(
+                1_000_000_000 * ONE_NEAR / full_duration
+            ))
+        );
+
+        assert_eq!(
+            payment_info.calculate_payment_status_impl(0, u64::MAX),
+            Ok(PaymentStatus::FinalPayment(1_000_000_000 * ONE_NEAR))
+        );
+    }
+
+    #[test]
+    fn test_calculate_payment_status_before_start_date() {
+        let mut payment_info = PaymentInfo::new(60, 100, 1000);
+        payment_info.initiale_date = Some(600);
+
+        assert_eq!(
+            payment_info.calculate_payment_status_impl(0, 599),
+            Ok(PaymentStatus::Absent)
+        );
+
+        payment_info.vesting_mode = VestingMode::Linear;
+        assert_eq!(
+            payment_info.calculate_payment_status_impl(0, 599),
+            Ok(PaymentStatus::Absent)
+        );
+    }
+
+    #[test]
+    fn test_check_claim_interval() {
+        let mut payment_info = PaymentInfo::new(60, 100, 1000);
+        payment_info.initiale_date = Some(10);
+
+        // no interval is set
+        assert_eq!(payment_info.check_claim_interval(10), Ok(()));
+
+        payment_info.min_claim_interval = Some(120 * NANOS_IN_SECOND);
+        assert_eq!(
+            payment_info.check_claim_interval(10),
+            Err(ContractError::ClaimTooEarly(120))
+        );
+
+        // the partial second is rounded up
+        payment_info.last_claim_date = Some(NANOS_IN_SECOND);
+        assert_eq!(
+            payment_info.check_claim_interval(NANOS_IN_SECOND * 61 + NANOS_IN_SECOND / 2),
+            Err(ContractError::ClaimTooEarly(60))
+        );
+        assert_eq!(
+            payment_info.check_claim_interval(NANOS_IN_SECOND * 121),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_register_claim_off_period_boundary() {
+        let mut payment_info = PaymentInfo::new(60, 100, 1000);
+        payment_info.initiale_date = Some(10);
+
+        // the claims are made in the middle of the periods, so the unsnapped dates would lose the periods
+        for claim_date in [100, 195, 230, 400, 459, 700] {
+            match payment_info.calculate_payment_status_impl(0, claim_date) {
+                Ok(PaymentStatus::PaymentReady(amount))
+                | Ok(PaymentStatus::FinalPayment(amount)) => {
+                    payment_info.register_claim(0, amount, claim_date).unwrap()
+                }
+                Ok(PaymentStatus::Absent) => {}
+                Err(error) => panic!("unexpected error {:?}", error),
+            }
+
+            // every passed period is paid exactly once
+            let passed_periods = ((claim_date - 10) / 60).min(10);
+            assert_eq!(
+                payment_info.claimed_amount,
+                u128::from(passed_periods) * 100
+            );
+            assert_eq!(
+                payment_info.last_payment_date,
+                Some(10 + passed_periods * 60)
+            );
+        }
+    }
+
+    #[test]
+    fn test_calculate_progress() {
+        let mut payment_info = PaymentInfo::new(60, 100, 1000);
+
+        assert_eq!(
+            payment_info.calculate_progress(0, 0),
+            Ok(PaymentProgress {
+                confirmed: false,
+                end_date: U64(0),
+                periods_total: U64(10),
+                periods_claimed: U64(0),
+                periods_available: U64(0),
+                amount_claimed: U128(0),
+                amount_remaining: U128(1000),
+            })
+        );
+
+        payment_info.initiale_date = Some(10);
+
+        let progress = payment_info.calculate_progress(0, 59).unwrap();
+        assert!(progress.confirmed);
+        assert_eq!(progress.end_date, U64(610));
+        assert_eq!(progress.periods_total, U64(10));
+        assert_eq!(progress.periods_available, U64(0));
+
+        let progress = payment_info.calculate_progress(0, 250).unwrap();
+        assert_eq!(progress.periods_claimed, U64(0));
+        assert_eq!(progress.periods_available, U64(4));
+        assert_eq!(progress.amount_remaining, U128(1000));
+
+        payment_info.register_claim(0, 400, 250).unwrap();
+
+        assert_eq!(
+            payment_info.calculate_progress(0, 400),
+            Ok(PaymentProgress {
+                confirmed: true,
+                end_date: U64(610),
+                periods_total: U64(10),
+                periods_claimed: U64(4),
+                periods_available: U64(2),
+                amount_claimed: U128(400),
+                amount_remaining: U128(600),
+            })
+        );
+
+        // the available periods are not grown after the end date
+        let progress = payment_info.calculate_progress(0, 10_000).unwrap();
+        assert_eq!(progress.periods_claimed, U64(4));
+        assert_eq!(progress.periods_available, U64(6));
+    }
+
+    #[test]
+    fn test_payment_info_from_v1() {
+        let payment_info: PaymentInfo = PaymentInfoV1 {
+            initiale_date: Some(0),
+            period_duration: 60,
+            payment_amount: 100,
+            total_amount: 500,
+            last_payment_date: Some(130),
+        }
+        .into();
+
+        assert_eq!(payment_info.vesting_mode, VestingMode::Periodic);
+        assert_eq!(payment_info.claimed_amount, 200);
+        assert_eq!(payment_info.lifetime_claimed_amount, 200);
+        assert_eq!(payment_info.calculate_remainder_amount(0), Ok(300));
+    }
+
+    #[test]
+    fn test_remainder_amount_after_payment_amount_change() {
+        let mut payment_info = PaymentInfo::new(60, 100, 500);
+        payment_info.initiale_date = Some(0);
+        payment_info.register_claim(0, 200, 120).unwrap();
+
+        // the remainder is not derived from the claim date, so it survives the change of the terms
+        payment_info.payment_amount = 50;
+        assert_eq!(payment_info.claimed_amount, 200);
+        assert_eq!(payment_info.calculate_remainder_amount(0), Ok(300));
+    }
 }
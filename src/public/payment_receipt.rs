@@ -1,25 +1,244 @@
+use std::borrow::Cow;
+
 use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
+    json_types::{U128, U64},
     AccountId,
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
-use super::payment_info::PaymentInfo;
+use super::payment_info::{PaymentInfo, PaymentInfoV1, PaymentInfoView, PeriodUnit};
+use crate::constants::TOTAL_WEIGHT_BPS;
 
+// only V1 receipts were stored by the released contract, V2 is the layout of the new receipts,
+// the legacy receipts are upgraded on the first write
+#[allow(clippy::large_enum_variant)]
 #[derive(BorshDeserialize, BorshSerialize, Serialize)]
 #[serde(crate = "near_sdk::serde")]
 pub enum PaymentReceipt {
     V1(PaymentReceiptV1),
+    V2(PaymentReceiptV2),
 }
 
-pub type CurrentUserVersion = PaymentReceiptV1;
+pub type CurrentUserVersion = PaymentReceiptV2;
 
 #[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct PaymentReceiptV1 {
+    pub payment_info: PaymentInfoV1,
+    pub issuer: AccountId,
+    pub receiver: AccountId,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PaymentReceiptV2 {
     pub payment_info: PaymentInfo,
     pub issuer: AccountId,
     pub receiver: AccountId,
+    pub memo: Option<String>,
+    pub approval_deadline: Option<u64>,
+    // weighted receivers of the split stream, empty when the whole stream goes to the single receiver
+    pub receivers: Vec<ReceiverShare>,
+    pub created_at: u64,
+    // set when the receiver approves the payment, the stream could be started later than that
+    pub approved_at: Option<u64>,
+    // the stream is started at the creation when the receiver approval is not required
+    pub approval_required: bool,
+    // set by the issuer to make the creation idempotent, freed when the payment is closed
+    pub client_nonce: Option<String>,
+    // set while the payout transfer of the payment is in flight
+    pub locked: bool,
+    // new terms proposed by the issuer and not answered by the receiver yet
+    pub pending_amendment: Option<PendingAmendment>,
+    // the period duration as it was passed by the issuer, the payment info keeps it in nanoseconds
+    pub period_unit: PeriodUnit,
+    pub period_value: u64,
+    // the account which gets the claims of the receiver-of-record instead of it
+    pub payout_address: Option<AccountId>,
+    // the account which could trigger the claims of the receiver-of-record, the payout is not sent to it
+    pub claim_delegate: Option<AccountId>,
+    // early termination offered by the issuer and not answered by the receiver yet
+    pub settlement_offer: Option<SettlementOffer>,
+    // json annotations of the issuer, e.g. the ids of the payroll systems, not interpreted by the contract
+    pub metadata: Option<String>,
+    pub state: ReceiptState,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone, Copy, PartialEq, Debug, Default)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ReceiptState {
+    #[default]
+    Active,
+    // the issuer cancelled the stream, the receiver could claim the periods passed before effective_at
+    CancellationPending {
+        effective_at: u64,
+    },
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingAmendment {
+    pub payment_amount: u128,
+    pub period_duration: u64,
+    // the extra deposit of the issuer, refunded when the amendment is not accepted
+    pub top_up: u128,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SettlementOffer {
+    pub receiver_amount: u128,
+    pub expires_at: u64,
+    // the claimed amount when the offer is made, any claim after it invalidates the offer
+    pub claimed_amount: u128,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReceiverShare {
+    pub receiver: AccountId,
+    pub weight_bps: u16,
+    pub last_payment_date: Option<u64>,
+    // the part of the stream claimed by the receiver before the weight is applied
+    pub claimed_amount: u128,
+    pub last_claim_date: Option<u64>,
+}
+
+// json friendly representation of the current payment receipt
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PaymentReceiptView {
+    pub payment_info: PaymentInfoView,
+    pub issuer: AccountId,
+    pub receiver: AccountId,
+    pub memo: Option<String>,
+    pub approval_deadline: Option<U64>,
+    pub receivers: Vec<ReceiverShareView>,
+    pub created_at: U64,
+    pub approved_at: Option<U64>,
+    pub approval_required: bool,
+    pub pending_amendment: Option<PendingAmendmentView>,
+    pub period_unit: PeriodUnit,
+    pub period_value: U64,
+    pub payout_address: Option<AccountId>,
+    pub claim_delegate: Option<AccountId>,
+    pub settlement_offer: Option<SettlementOfferView>,
+    pub metadata: Option<String>,
+    pub state: ReceiptStateView,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ReceiptStateView {
+    Active,
+    CancellationPending { effective_at: U64 },
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingAmendmentView {
+    pub payment_amount: U128,
+    pub period_duration: U64,
+    pub top_up: U128,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SettlementOfferView {
+    pub receiver_amount: U128,
+    pub expires_at: U64,
+    pub claimed_amount: U128,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReceiverShareView {
+    pub receiver: AccountId,
+    pub weight_bps: u16,
+    pub last_payment_date: Option<U64>,
+    pub claimed_amount: U128,
+}
+
+impl From<&PaymentReceiptV2> for PaymentReceiptView {
+    fn from(receipt: &PaymentReceiptV2) -> Self {
+        PaymentReceiptView {
+            payment_info: (&receipt.payment_info).into(),
+            issuer: receipt.issuer.clone(),
+            receiver: receipt.receiver.clone(),
+            memo: receipt.memo.clone(),
+            approval_deadline: receipt.approval_deadline.map(U64),
+            receivers: receipt
+                .receivers
+                .iter()
+                .map(|share| ReceiverShareView {
+                    receiver: share.receiver.clone(),
+                    weight_bps: share.weight_bps,
+                    last_payment_date: share.last_payment_date.map(U64),
+                    claimed_amount: share.claimed_amount.into(),
+                })
+                .collect(),
+            created_at: receipt.created_at.into(),
+            approved_at: receipt.approved_at.map(U64),
+            approval_required: receipt.approval_required,
+            pending_amendment: receipt.pending_amendment.as_ref().map(|amendment| {
+                PendingAmendmentView {
+                    payment_amount: amendment.payment_amount.into(),
+                    period_duration: amendment.period_duration.into(),
+                    top_up: amendment.top_up.into(),
+                }
+            }),
+            period_unit: receipt.period_unit,
+            period_value: receipt.period_value.into(),
+            payout_address: receipt.payout_address.clone(),
+            claim_delegate: receipt.claim_delegate.clone(),
+            settlement_offer: receipt
+                .settlement_offer
+                .as_ref()
+                .map(|offer| SettlementOfferView {
+                    receiver_amount: offer.receiver_amount.into(),
+                    expires_at: offer.expires_at.into(),
+                    claimed_amount: offer.claimed_amount.into(),
+                }),
+            metadata: receipt.metadata.clone(),
+            state: match receipt.state {
+                ReceiptState::Active => ReceiptStateView::Active,
+                ReceiptState::CancellationPending { effective_at } => {
+                    ReceiptStateView::CancellationPending {
+                        effective_at: effective_at.into(),
+                    }
+                }
+            },
+        }
+    }
+}
+
+impl ReceiverShare {
+    // the share is claimed independently, so it has its own payment progress within the stream
+    pub(crate) fn payment_info(&self, stream_info: &PaymentInfo) -> PaymentInfo {
+        PaymentInfo {
+            last_payment_date: self.last_payment_date,
+            claimed_amount: self.claimed_amount,
+            last_claim_date: self.last_claim_date,
+            ..stream_info.clone()
+        }
+    }
+
+    pub(crate) fn weighted_amount(&self, amount: u128) -> Option<u128> {
+        amount
+            .checked_mul(u128::from(self.weight_bps))
+            .map(|value| value / u128::from(TOTAL_WEIGHT_BPS))
+    }
+
+    // the weight is applied to the cumulative claimed amount, so the rounding of the separate claims is not lost
+    pub(crate) fn weighted_payout(&self, amount: u128) -> Option<u128> {
+        let claimed_amount = self.weighted_amount(self.claimed_amount)?;
+
+        self.claimed_amount
+            .checked_add(amount)
+            .and_then(|value| self.weighted_amount(value))
+            .and_then(|value| value.checked_sub(claimed_amount))
+    }
 }
 
 impl From<PaymentReceiptV1> for PaymentReceipt {
@@ -28,29 +247,333 @@ impl From<PaymentReceiptV1> for PaymentReceipt {
     }
 }
 
+impl From<PaymentReceiptV2> for PaymentReceipt {
+    fn from(account: PaymentReceiptV2) -> Self {
+        PaymentReceipt::V2(account)
+    }
+}
+
+impl From<PaymentReceiptV1> for PaymentReceiptV2 {
+    fn from(receipt: PaymentReceiptV1) -> Self {
+        let initiale_date = receipt.payment_info.initiale_date;
+        let payment_info: PaymentInfo = receipt.payment_info.into();
+        let (period_unit, period_value) = PeriodUnit::from_duration(payment_info.period_duration);
+
+        PaymentReceiptV2 {
+            payment_info,
+            issuer: receipt.issuer,
+            receiver: receipt.receiver,
+            memo: None,
+            approval_deadline: None,
+            receivers: vec![],
+            // the creation date of the legacy payments is unknown, the approval date is the closest one
+            created_at: initiale_date.unwrap_or(0),
+            approved_at: initiale_date,
+            approval_required: true,
+            client_nonce: None,
+            locked: false,
+            pending_amendment: None,
+            period_unit,
+            period_value,
+            payout_address: None,
+            claim_delegate: None,
+            settlement_offer: None,
+            metadata: None,
+            state: ReceiptState::Active,
+        }
+    }
+}
+
+impl PaymentReceiptV2 {
+    // accounts which have the payment in the receiver ledger
+    pub fn receiver_accounts(&self) -> Vec<AccountId> {
+        if self.receivers.is_empty() {
+            vec![self.receiver.clone()]
+        } else {
+            self.receivers
+                .iter()
+                .map(|share| share.receiver.clone())
+                .collect()
+        }
+    }
+
+    // the payout address is applied only to the receiver-of-record
+    pub(crate) fn payout_account(&self, receiver: &AccountId) -> AccountId {
+        match &self.payout_address {
+            Some(payout_address) if receiver == &self.receiver => payout_address.clone(),
+            _ => receiver.clone(),
+        }
+    }
+
+    // the payment is expired when it is not approved until the approval deadline
+    pub(crate) fn is_expired(&self, current_time: u64) -> bool {
+        self.payment_info.initiale_date.is_none()
+            && self
+                .approval_deadline
+                .is_some_and(|approval_deadline| current_time > approval_deadline)
+    }
+
+    // the stream is not vested after the effective date of the pending cancellation
+    pub(crate) fn settlement_time(&self, current_time: u64) -> u64 {
+        match self.state {
+            ReceiptState::Active => current_time,
+            ReceiptState::CancellationPending { effective_at } => current_time.min(effective_at),
+        }
+    }
+
+    // the part of the deposit which is not paid out to the receivers yet, including the top-up of the pending amendment
+    pub(crate) fn locked_amount(&self) -> Option<u128> {
+        if self.receivers.is_empty() {
+            let top_up = self
+                .pending_amendment
+                .as_ref()
+                .map_or(0, |amendment| amendment.top_up);
+
+            return self
+                .payment_info
+                .total_amount
+                .checked_sub(self.payment_info.claimed_amount)
+                .and_then(|amount| amount.checked_add(top_up));
+        }
+
+        self.receivers
+            .iter()
+            .try_fold(self.payment_info.total_amount, |amount, share| {
+                amount.checked_sub(share.weighted_amount(share.claimed_amount)?)
+            })
+    }
+
+    // returns true when every receiver of the split stream got the final payment
+    pub(crate) fn all_shares_claimed(&self) -> Option<bool> {
+        let end_date = self
+            .payment_info
+            .initiale_date
+            .and_then(|initiale_date| self.payment_info.calculate_end_date(initiale_date))?;
+
+        Some(self.receivers.iter().all(|share| {
+            share
+                .last_payment_date
+                .is_some_and(|last_payment_date| last_payment_date >= end_date)
+        }))
+    }
+}
+
 impl PaymentReceipt {
     pub fn create_payment_receipt(
         payment_info: PaymentInfo,
         issuer: AccountId,
         receiver: AccountId,
+        memo: Option<String>,
+        approval_deadline: Option<u64>,
+        created_at: u64,
+        approval_required: bool,
     ) -> PaymentReceipt {
+        let (period_unit, period_value) = PeriodUnit::from_duration(payment_info.period_duration);
+
         CurrentUserVersion {
             payment_info,
             issuer,
             receiver,
+            memo,
+            approval_deadline,
+            receivers: vec![],
+            created_at,
+            approved_at: None,
+            approval_required,
+            client_nonce: None,
+            locked: false,
+            pending_amendment: None,
+            period_unit,
+            period_value,
+            payout_address: None,
+            claim_delegate: None,
+            settlement_offer: None,
+            metadata: None,
+            state: ReceiptState::Active,
+        }
+        .into()
+    }
+
+    // the first receiver of the split stream is the one who approves or rejects the pending payment
+    pub fn create_split_payment_receipt(
+        payment_info: PaymentInfo,
+        issuer: AccountId,
+        receivers: Vec<(AccountId, u16)>,
+        memo: Option<String>,
+        approval_deadline: Option<u64>,
+        created_at: u64,
+        approval_required: bool,
+    ) -> PaymentReceipt {
+        let (period_unit, period_value) = PeriodUnit::from_duration(payment_info.period_duration);
+
+        CurrentUserVersion {
+            payment_info,
+            issuer,
+            receiver: receivers[0].0.clone(),
+            memo,
+            approval_deadline,
+            receivers: receivers
+                .into_iter()
+                .map(|(receiver, weight_bps)| ReceiverShare {
+                    receiver,
+                    weight_bps,
+                    last_payment_date: None,
+                    claimed_amount: 0,
+                    last_claim_date: None,
+                })
+                .collect(),
+            created_at,
+            approved_at: None,
+            approval_required,
+            client_nonce: None,
+            locked: false,
+            pending_amendment: None,
+            period_unit,
+            period_value,
+            payout_address: None,
+            claim_delegate: None,
+            settlement_offer: None,
+            metadata: None,
+            state: ReceiptState::Active,
         }
         .into()
     }
 
-    pub fn into_current(&self) -> &CurrentUserVersion {
+    pub fn into_current(&self) -> Cow<'_, CurrentUserVersion> {
         match self {
-            Self::V1(value) => value,
+            Self::V1(value) => Cow::Owned(value.clone().into()),
+            Self::V2(value) => Cow::Borrowed(value),
         }
     }
 
+    pub(crate) fn is_current(&self) -> bool {
+        matches!(self, Self::V2(_))
+    }
+
+    // upgrades the stored receipt to the current version, so that the changes are written with the new layout
     pub fn into_current_mut(&mut self) -> &mut CurrentUserVersion {
+        if let Self::V1(value) = self {
+            *self = Self::V2(value.clone().into());
+        }
+
         match self {
-            Self::V1(value) => value,
+            Self::V2(value) => value,
+            Self::V1(_) => unreachable!("legacy receipt is upgraded above"),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::accounts;
+
+    use super::*;
+    use crate::constants::NANOS_IN_SECOND;
+
+    #[test]
+    fn test_upgrade_v1_receipt() {
+        let receipt_v1 = PaymentReceiptV1 {
+            payment_info: PaymentInfoV1 {
+                initiale_date: None,
+                period_duration: 60,
+                payment_amount: 100,
+                total_amount: 500,
+                last_payment_date: None,
+            },
+            issuer: accounts(1),
+            receiver: accounts(2),
+        };
+
+        // receipt stored with the previous version of the contract
+        let bytes = PaymentReceipt::V1(receipt_v1).try_to_vec().unwrap();
+        let mut receipt = PaymentReceipt::try_from_slice(&bytes).unwrap();
+
+        let current = receipt.into_current();
+        assert_eq!(current.issuer, accounts(1));
+        assert_eq!(current.receiver, accounts(2));
+        assert_eq!(current.payment_info.total_amount, 500);
+        assert_eq!(current.memo, None);
+        assert_eq!(current.approval_deadline, None);
+        assert_eq!(current.created_at, 0);
+        assert_eq!(current.approved_at, None);
+        assert_eq!(current.receiver_accounts(), vec![accounts(2)]);
+
+        receipt.into_current_mut().memo = Some("memo".to_string());
+        assert!(matches!(receipt, PaymentReceipt::V2(_)));
+        assert_eq!(receipt.into_current().memo, Some("memo".to_string()));
+    }
+
+    #[test]
+    fn test_upgrade_approved_v1_receipt() {
+        let receipt_v1 = PaymentReceiptV1 {
+            payment_info: PaymentInfoV1 {
+                initiale_date: Some(1_000),
+                period_duration: 60 * NANOS_IN_SECOND,
+                payment_amount: 100,
+                total_amount: 500,
+                last_payment_date: None,
+            },
+            issuer: accounts(1),
+            receiver: accounts(2),
+        };
+
+        // receipt stored with the previous version of the contract
+        let bytes = PaymentReceipt::V1(receipt_v1).try_to_vec().unwrap();
+        let mut receipt = PaymentReceipt::try_from_slice(&bytes).unwrap();
+
+        // the approval of the legacy payment started the stream
+        let current = receipt.into_current();
+        assert_eq!(current.created_at, 1_000);
+        assert_eq!(current.approved_at, Some(1_000));
+        assert_eq!(current.period_unit, PeriodUnit::Minutes);
+        assert_eq!(current.period_value, 1);
+        assert_eq!(current.state, ReceiptState::Active);
+        assert!(!receipt.is_current());
+
+        receipt.into_current_mut();
+        let bytes = receipt.try_to_vec().unwrap();
+        let current = PaymentReceipt::try_from_slice(&bytes)
+            .unwrap()
+            .into_current()
+            .into_owned();
+        assert_eq!(current.created_at, 1_000);
+        assert_eq!(current.period_value, 1);
+        assert_eq!(current.approved_at, Some(1_000));
+    }
+
+    #[test]
+    fn test_split_payment_receipt() {
+        let mut payment_info = PaymentInfo::new(60, 100, 500);
+        payment_info.initiale_date = Some(0);
+
+        let receipt = PaymentReceipt::create_split_payment_receipt(
+            payment_info,
+            accounts(1),
+            vec![(accounts(2), 7000), (accounts(3), 3000)],
+            None,
+            None,
+            0,
+            true,
+        );
+        let mut current = receipt.into_current().into_owned();
+
+        assert_eq!(current.receiver, accounts(2));
+        assert_eq!(current.receiver_accounts(), vec![accounts(2), accounts(3)]);
+        assert_eq!(current.receivers[0].weighted_amount(100), Some(70));
+        assert_eq!(current.receivers[1].weighted_amount(100), Some(30));
+
+        // the rounding of the separate claims is compensated by the next ones
+        current.receivers[1].claimed_amount = 1;
+        assert_eq!(current.receivers[1].weighted_payout(1), Some(0));
+        current.receivers[1].claimed_amount = 2;
+        assert_eq!(current.receivers[1].weighted_payout(2), Some(1));
+        current.receivers[1].claimed_amount = 0;
+        assert_eq!(current.all_shares_claimed(), Some(false));
+
+        current.receivers[0].last_payment_date = Some(300);
+        assert_eq!(current.all_shares_claimed(), Some(false));
+
+        current.receivers[1].last_payment_date = Some(310);
+        assert_eq!(current.all_shares_claimed(), Some(true));
+    }
+}
@@ -0,0 +1,48 @@
+use std::borrow::Cow;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+// versioned, so that the preferences could be extended without the state migration
+#[derive(BorshDeserialize, BorshSerialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ReceiverPrefs {
+    V1(ReceiverPrefsV1),
+}
+
+pub type CurrentReceiverPrefs = ReceiverPrefsV1;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReceiverPrefsV1 {
+    pub accepting_payments: bool,
+}
+
+// the account without the stored preferences accepts the payments
+impl Default for ReceiverPrefsV1 {
+    fn default() -> Self {
+        ReceiverPrefsV1 {
+            accepting_payments: true,
+        }
+    }
+}
+
+impl From<ReceiverPrefsV1> for ReceiverPrefs {
+    fn from(prefs: ReceiverPrefsV1) -> Self {
+        ReceiverPrefs::V1(prefs)
+    }
+}
+
+impl ReceiverPrefs {
+    pub fn into_current(&self) -> Cow<'_, CurrentReceiverPrefs> {
+        match self {
+            Self::V1(value) => Cow::Borrowed(value),
+        }
+    }
+
+    pub fn into_current_mut(&mut self) -> &mut CurrentReceiverPrefs {
+        match self {
+            Self::V1(value) => value,
+        }
+    }
+}
@@ -0,0 +1,173 @@
+// Integration tests running the compiled contract in the near sandbox.
+// Build the contract first with ./build.sh and run: cargo test --features integration-tests
+#![cfg(feature = "integration-tests")]
+
+use near_sdk::json_types::{U128, U64};
+use near_workspaces::{network::Sandbox, types::NearToken, Account, Contract, Worker};
+use serde_json::json;
+
+const WASM_FILEPATH: &str = "./res/near_payment_receiver.wasm";
+const ONE_NEAR: u128 = 10u128.pow(24);
+// gas costs paid by the signer are covered by this tolerance
+const GAS_TOLERANCE: u128 = ONE_NEAR / 100;
+const PERIOD_DURATION_SECS: u64 = 60;
+
+async fn init() -> anyhow::Result<(Worker<Sandbox>, Contract, Account, Account)> {
+    let worker = near_workspaces::sandbox().await?;
+    let wasm = std::fs::read(WASM_FILEPATH)?;
+    let contract = worker.dev_deploy(&wasm).await?;
+
+    contract
+        .call("new")
+        .deposit(NearToken::from_yoctonear(1))
+        .transact()
+        .await?
+        .into_result()?;
+
+    let issuer = worker.dev_create_account().await?;
+    let receiver = worker.dev_create_account().await?;
+
+    Ok((worker, contract, issuer, receiver))
+}
+
+async fn create_payment(
+    contract: &Contract,
+    issuer: &Account,
+    receiver: &Account,
+    total_amount: u128,
+    payment_amount: u128,
+) -> anyhow::Result<U64> {
+    let payment_id = issuer
+        .call(contract.id(), "create_payment_with_period_secs")
+        .args_json(json!({
+            "period_duration_secs": U64(PERIOD_DURATION_SECS),
+            "payment_amount": U128(payment_amount),
+            "receiver": receiver.id(),
+        }))
+        .deposit(NearToken::from_yoctonear(total_amount))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?
+        .json::<U64>()?;
+
+    receiver
+        .call(contract.id(), "process_pending_payment")
+        .args_json(json!({ "process_status": { "Approve": payment_id } }))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?;
+
+    Ok(payment_id)
+}
+
+async fn balance(account: &Account) -> anyhow::Result<u128> {
+    Ok(account.view_account().await?.balance.as_yoctonear())
+}
+
+fn assert_delta(before: u128, after: u128, expected: u128) {
+    let delta = after.saturating_sub(before);
+
+    assert!(
+        delta <= expected && expected - delta <= GAS_TOLERANCE,
+        "balance delta {} is not close to {}",
+        delta,
+        expected
+    );
+}
+
+#[tokio::test]
+async fn test_payment_lifecycle() -> anyhow::Result<()> {
+    let (worker, contract, issuer, receiver) = init().await?;
+
+    let issuer_balance = balance(&issuer).await?;
+    let payment_id = create_payment(&contract, &issuer, &receiver, 10 * ONE_NEAR, ONE_NEAR).await?;
+
+    // the deposit is locked on the contract
+    assert!(issuer_balance - balance(&issuer).await? >= 10 * ONE_NEAR);
+
+    // wait for several periods
+    worker.fast_forward(500).await?;
+
+    let receiver_balance = balance(&receiver).await?;
+    let claimed = receiver
+        .call(contract.id(), "claim_payment")
+        .args_json(json!({ "payment_id": payment_id }))
+        .deposit(NearToken::from_yoctonear(1))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?
+        .json::<U128>()?;
+
+    assert!(claimed.0 > 0);
+    assert_delta(receiver_balance, balance(&receiver).await?, claimed.0);
+
+    // the issuer stops the stream and gets the remainder back
+    let issuer_balance = balance(&issuer).await?;
+    let receiver_balance = balance(&receiver).await?;
+    let result = issuer
+        .call(contract.id(), "reject_payment_receipt")
+        .args_json(json!({ "payment_id": payment_id, "role": "Issuer" }))
+        .deposit(NearToken::from_yoctonear(1))
+        .max_gas()
+        .transact()
+        .await?
+        .into_result()?
+        .json::<serde_json::Value>()?;
+
+    let issuer_refund: U128 = serde_json::from_value(result["issuer_refund"].clone())?;
+    let receiver_payout: U128 = serde_json::from_value(result["receiver_payout"].clone())?;
+
+    assert_eq!(
+        claimed.0 + issuer_refund.0 + receiver_payout.0,
+        10 * ONE_NEAR
+    );
+    assert_delta(issuer_balance, balance(&issuer).await?, issuer_refund.0);
+    assert_eq!(
+        balance(&receiver).await? - receiver_balance,
+        receiver_payout.0
+    );
+
+    // the payment does not exist anymore
+    let status = contract
+        .view("get_payment_status")
+        .args_json(json!({ "payment_id": payment_id }))
+        .await;
+    assert!(status.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_claim_payment_not_receiver() -> anyhow::Result<()> {
+    let (worker, contract, issuer, receiver) = init().await?;
+
+    let payment_id = create_payment(&contract, &issuer, &receiver, 10 * ONE_NEAR, ONE_NEAR).await?;
+
+    worker.fast_forward(500).await?;
+
+    let result = issuer
+        .call(contract.id(), "claim_payment")
+        .args_json(json!({ "payment_id": payment_id }))
+        .deposit(NearToken::from_yoctonear(1))
+        .max_gas()
+        .transact()
+        .await?;
+
+    assert!(result.is_failure());
+
+    // the contract error is delivered through the rpc boundary
+    let error = result.into_result().unwrap_err().to_string();
+    assert!(
+        error.contains(&format!(
+            "Account {} does not have a record in receivers store",
+            issuer.id()
+        )),
+        "unexpected error: {}",
+        error
+    );
+
+    Ok(())
+}